@@ -0,0 +1,216 @@
+use bevy::{
+    a11y::{
+        accesskit::{NodeBuilder, Role},
+        AccessibilityNode, Focus,
+    },
+    input::InputSystem,
+    prelude::*,
+};
+
+use crate::{notes_plugin::NoteEditor, pause_plugin::QuitPrompt};
+
+pub struct AccessibilityPlugin;
+
+// A menu line that does whatever its key does. Up and Down move the focus between them and
+// Enter acts on the focused one, so the menu works without remembering the keys.
+#[derive(Component)]
+pub struct MenuOption(pub KeyCode);
+
+// A row in a list the screen moves through itself, read out when it's the selected one.
+#[derive(Component, Default)]
+pub struct ListEntry {
+    pub selected: bool,
+}
+
+#[derive(Resource, Default)]
+struct MenuFocus {
+    focused: Option<Entity>,
+    // The key pressed for the focused option, let go of the next frame.
+    pressed: Option<KeyCode>,
+}
+
+const FOCUS_COLOR: Color = Color::rgba(1.0, 1.0, 0.0, 0.25);
+
+// A gamepad presses the keys every screen already listens to.
+const GAMEPAD_KEYS: [(GamepadButtonType, KeyCode); 7] = [
+    (GamepadButtonType::DPadUp, KeyCode::Up),
+    (GamepadButtonType::DPadDown, KeyCode::Down),
+    (GamepadButtonType::DPadLeft, KeyCode::Left),
+    (GamepadButtonType::DPadRight, KeyCode::Right),
+    (GamepadButtonType::South, KeyCode::Return),
+    (GamepadButtonType::East, KeyCode::Escape),
+    (GamepadButtonType::Start, KeyCode::Space),
+];
+
+fn press_gamepad_keys(
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+) {
+    for gamepad in gamepads.iter() {
+        for (button_type, key) in GAMEPAD_KEYS {
+            let button = GamepadButton::new(gamepad, button_type);
+            if gamepad_buttons.just_pressed(button) {
+                keyboard_input.press(key);
+            } else if gamepad_buttons.just_released(button) {
+                keyboard_input.release(key);
+            }
+        }
+    }
+}
+
+fn menu_open_to_keys(quit_prompt: &QuitPrompt, note_editor: &NoteEditor) -> bool {
+    !quit_prompt.open && !note_editor.editing
+}
+
+// Runs before the menus read their keys, so Enter on a focused option looks to them just like
+// its own key being pressed.
+fn activate_focused_option(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    quit_prompt: Res<QuitPrompt>,
+    note_editor: Res<NoteEditor>,
+    mut menu_focus: ResMut<MenuFocus>,
+    option_query: Query<&MenuOption>,
+) {
+    if let Some(key) = menu_focus.pressed.take() {
+        keyboard_input.release(key);
+    }
+    if !menu_open_to_keys(&quit_prompt, &note_editor)
+        || !keyboard_input.just_pressed(KeyCode::Return)
+    {
+        return;
+    }
+    let Some(option) = menu_focus
+        .focused
+        .and_then(|focused| option_query.get(focused).ok())
+    else {
+        return;
+    };
+    keyboard_input.reset(KeyCode::Return);
+    keyboard_input.press(option.0);
+    menu_focus.pressed = Some(option.0);
+}
+
+fn move_menu_focus(
+    keyboard_input: Res<Input<KeyCode>>,
+    quit_prompt: Res<QuitPrompt>,
+    note_editor: Res<NoteEditor>,
+    mut menu_focus: ResMut<MenuFocus>,
+    option_query: Query<(Entity, &Parent), With<MenuOption>>,
+    children_query: Query<&Children>,
+) {
+    // Options are in the order they're laid out in their menu.
+    let mut options: Vec<(Entity, Entity, usize)> = option_query
+        .iter()
+        .map(|(entity, parent)| {
+            let index = children_query
+                .get(parent.get())
+                .ok()
+                .and_then(|children| children.iter().position(|child| *child == entity))
+                .unwrap_or_default();
+            (parent.get(), entity, index)
+        })
+        .collect();
+    options.sort_by_key(|(parent, _, index)| (*parent, *index));
+
+    if options.is_empty() {
+        if menu_focus.focused.is_some() {
+            menu_focus.focused = None;
+        }
+        return;
+    }
+    let current = menu_focus
+        .focused
+        .and_then(|focused| options.iter().position(|(_, entity, _)| *entity == focused));
+    let next = match current {
+        // A menu has just opened.
+        None => 0,
+        Some(_) if !menu_open_to_keys(&quit_prompt, &note_editor) => return,
+        Some(index) if keyboard_input.just_pressed(KeyCode::Up) => {
+            (index + options.len() - 1) % options.len()
+        }
+        Some(index) if keyboard_input.just_pressed(KeyCode::Down) => (index + 1) % options.len(),
+        Some(_) => return,
+    };
+    menu_focus.focused = Some(options[next].1);
+}
+
+fn show_menu_focus(
+    menu_focus: Res<MenuFocus>,
+    mut option_query: Query<(Entity, &mut BackgroundColor), With<MenuOption>>,
+) {
+    if !menu_focus.is_changed() {
+        return;
+    }
+    for (entity, mut background_color) in &mut option_query {
+        *background_color = if menu_focus.focused == Some(entity) {
+            FOCUS_COLOR.into()
+        } else {
+            Color::NONE.into()
+        };
+    }
+}
+
+type MenuTextQuery<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static Text, Has<MenuOption>),
+    (Changed<Text>, Or<(With<MenuOption>, With<ListEntry>)>),
+>;
+
+// Screen readers read out the same text that's on screen.
+fn label_menu_text(mut commands: Commands, text_query: MenuTextQuery) {
+    for (entity, text, is_option) in &text_query {
+        let mut node = NodeBuilder::new(if is_option {
+            Role::Button
+        } else {
+            Role::ListItem
+        });
+        node.set_name(
+            text.sections
+                .iter()
+                .map(|section| section.value.as_str())
+                .collect::<String>(),
+        );
+        commands.entity(entity).insert(AccessibilityNode(node));
+    }
+}
+
+fn follow_focus(
+    menu_focus: Res<MenuFocus>,
+    entry_query: Query<(Entity, &ListEntry)>,
+    mut focus: ResMut<Focus>,
+) {
+    let focused = menu_focus.focused.or_else(|| {
+        entry_query
+            .iter()
+            .find(|(_, entry)| entry.selected)
+            .map(|(entity, _)| entity)
+    });
+    if focus.0 != focused {
+        focus.0 = focused;
+    }
+}
+
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MenuFocus::default())
+            .add_systems(
+                PreUpdate,
+                (
+                    press_gamepad_keys,
+                    activate_focused_option.after(press_gamepad_keys),
+                )
+                    .after(InputSystem),
+            )
+            .add_systems(
+                Update,
+                (
+                    move_menu_focus,
+                    show_menu_focus.after(move_menu_focus),
+                    label_menu_text,
+                    follow_focus.after(move_menu_focus),
+                ),
+            );
+    }
+}
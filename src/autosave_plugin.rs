@@ -0,0 +1,265 @@
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    campaign_plugin::Campaign,
+    level_hash,
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{
+        InputBuffer, LevelCompletedEvent, LevelLoadedEvent, LevelPack, LevelState, MovementTuning,
+        NextLevelEvent, CAMPAIGN_PACK,
+    },
+    profile::Profile,
+    sokoban_core::step_direction,
+    GameState,
+};
+
+const AUTOSAVE_FILE: &str = "autosave.ron";
+
+// Often enough that a crash loses little, without writing on every step.
+const AUTOSAVE_EVERY_MOVES: usize = 10;
+
+pub struct AutosavePlugin;
+
+// A campaign level part way through. Only the moves are kept, playing them back rebuilds
+// the board and its undo history.
+#[derive(Clone, Serialize, Deserialize)]
+struct SavedLevel {
+    level: i32,
+    hash: u32,
+    moves: String,
+}
+
+#[derive(Resource, Default)]
+struct Autosave {
+    saved: Option<SavedLevel>,
+    // Continuing is offered at startup until it's taken up or the player starts moving.
+    offered: bool,
+    // The moves as of the last save, another is due once enough have changed.
+    saved_moves: String,
+    // Taken up, waiting for the level to load and then for it to be set up.
+    restoring: Option<SavedLevel>,
+    restore_loaded: bool,
+    fast_forwarding: bool,
+}
+
+#[derive(Component)]
+struct ContinuePromptText;
+
+fn load_autosave(mut commands: Commands, profile: Res<Profile>) {
+    let saved = profile.load::<Option<SavedLevel>>(AUTOSAVE_FILE);
+    commands.insert_resource(Autosave {
+        offered: saved.as_ref().is_some_and(|saved| !saved.moves.is_empty()),
+        saved,
+        ..default()
+    });
+}
+
+fn spawn_continue_prompt(mut commands: Commands) {
+    commands.spawn((
+        ContinuePromptText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::YELLOW,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(30.0),
+            left: Val::Px(10.0),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.8)),
+        ZIndex::Global(12),
+        Visibility::Hidden,
+    ));
+}
+
+fn offer_continue(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    campaign: Res<Campaign>,
+    level_state: Res<LevelState>,
+    mut autosave: ResMut<Autosave>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+) {
+    if !autosave.offered {
+        return;
+    }
+    if !level_state.moves.is_empty() {
+        autosave.offered = false;
+        return;
+    }
+    if !keyboard_input.just_pressed(KeyCode::Return) {
+        return;
+    }
+    autosave.offered = false;
+    let Some(saved) = autosave.saved.clone() else {
+        return;
+    };
+    commands.insert_resource(LevelPack::campaign(&campaign));
+    next_level_writer.send(NextLevelEvent(saved.level));
+    autosave.restoring = Some(saved);
+    autosave.restore_loaded = false;
+}
+
+// The level is set up the frame after it's loaded, the moves are played back from there
+// as one quick walk.
+fn restore_saved_moves(
+    mut autosave: ResMut<Autosave>,
+    mut tuning: ResMut<MovementTuning>,
+    mut input_buffer: ResMut<InputBuffer>,
+    mut level_loaded_reader: EventReader<LevelLoadedEvent>,
+) {
+    let Some(restoring) = autosave.restoring.clone() else {
+        return;
+    };
+    if autosave.restore_loaded {
+        input_buffer.walk(restoring.moves.chars().filter_map(step_direction));
+        tuning.fast_forward = true;
+        autosave.fast_forwarding = true;
+        autosave.saved_moves = restoring.moves;
+        autosave.restoring = None;
+        return;
+    }
+    let Some(level_loaded) = level_loaded_reader.read().last() else {
+        return;
+    };
+    if level_loaded.level != restoring.level {
+        return;
+    }
+    if level_loaded.hash != restoring.hash {
+        warn!(
+            "Level {} has changed since it was saved, starting it afresh",
+            restoring.level
+        );
+        autosave.restoring = None;
+        return;
+    }
+    autosave.restore_loaded = true;
+}
+
+fn finish_fast_forward(
+    input_buffer: Res<InputBuffer>,
+    mut tuning: ResMut<MovementTuning>,
+    mut autosave: ResMut<Autosave>,
+) {
+    if autosave.fast_forwarding && !input_buffer.is_walking() {
+        tuning.fast_forward = false;
+        autosave.fast_forwarding = false;
+    }
+}
+
+fn save_level(profile: &Profile, autosave: &mut Autosave, level_state: &LevelState) {
+    let saved = SavedLevel {
+        level: level_state.current_level,
+        hash: level_hash(&level_state.layout),
+        moves: level_state.moves.clone(),
+    };
+    profile.save(AUTOSAVE_FILE, &Some(saved.clone()));
+    autosave.saved_moves = saved.moves.clone();
+    autosave.saved = Some(saved);
+}
+
+// Whether what's being played now should replace what's on disk.
+fn is_saveable(autosave: &Autosave, level_pack: &LevelPack, level_state: &LevelState) -> bool {
+    !autosave.offered
+        && autosave.restoring.is_none()
+        && !autosave.fast_forwarding
+        && level_pack.name == CAMPAIGN_PACK
+        && !level_state.is_solved()
+}
+
+fn autosave_progress(
+    profile: Res<Profile>,
+    level_pack: Res<LevelPack>,
+    level_state: Res<LevelState>,
+    mut autosave: ResMut<Autosave>,
+) {
+    if !is_saveable(&autosave, &level_pack, &level_state)
+        || level_state.moves.len().abs_diff(autosave.saved_moves.len()) < AUTOSAVE_EVERY_MOVES
+    {
+        return;
+    }
+    save_level(&profile, &mut autosave, &level_state);
+}
+
+fn save_on_exit(
+    profile: Res<Profile>,
+    level_pack: Res<LevelPack>,
+    level_state: Res<LevelState>,
+    mut autosave: ResMut<Autosave>,
+    mut app_exit_reader: EventReader<AppExit>,
+) {
+    if app_exit_reader.read().next().is_none() || !is_saveable(&autosave, &level_pack, &level_state)
+    {
+        return;
+    }
+    save_level(&profile, &mut autosave, &level_state);
+}
+
+// A finished level has nothing left to continue.
+fn clear_on_complete(
+    profile: Res<Profile>,
+    mut autosave: ResMut<Autosave>,
+    mut level_completed_reader: EventReader<LevelCompletedEvent>,
+) {
+    if level_completed_reader.read().next().is_none() || autosave.saved.is_none() {
+        return;
+    }
+    profile.save(AUTOSAVE_FILE, &None::<SavedLevel>);
+    autosave.saved = None;
+}
+
+fn update_continue_prompt(
+    autosave: Res<Autosave>,
+    game_state: Res<State<GameState>>,
+    mut prompt_query: Query<(&mut Text, &mut Visibility), With<ContinuePromptText>>,
+) {
+    if !autosave.is_changed() && !game_state.is_changed() {
+        return;
+    }
+    let Ok((mut text, mut visibility)) = prompt_query.get_single_mut() else {
+        return;
+    };
+    let offer = autosave
+        .saved
+        .as_ref()
+        .filter(|_| autosave.offered && *game_state.get() == GameState::Playing);
+    let Some(saved) = offer else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    text.sections[0].value = format!(
+        "Enter: continue level {} where you left off ({} moves)",
+        saved.level,
+        saved.moves.len()
+    );
+    *visibility = Visibility::Inherited;
+}
+
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Autosave::default())
+            .add_systems(Startup, (load_autosave, spawn_continue_prompt))
+            .add_systems(
+                Update,
+                (
+                    offer_continue
+                        .run_if(in_state(GameState::Playing))
+                        .run_if(quit_prompt_closed),
+                    restore_saved_moves.after(offer_continue),
+                    finish_fast_forward.after(restore_saved_moves),
+                    autosave_progress
+                        .after(finish_fast_forward)
+                        .run_if(in_state(GameState::Playing)),
+                    clear_on_complete,
+                    update_continue_prompt.after(offer_continue),
+                ),
+            )
+            .add_systems(Last, save_on_exit);
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::{ImageAddressMode, ImageSampler, ImageSamplerDescriptor},
+    },
+    transform::TransformSystem,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    play_plugin::{LevelLoadedEvent, LevelPack},
+    tiles::Theme,
+    LevelEntity, MainCamera,
+};
+
+const PATTERN_PIXELS: usize = 32;
+// How far the pattern moves for each unit the camera does, it seems further away than the
+// level for moving less.
+const PARALLAX: f32 = 0.5;
+// Behind the floor, which is drawn at 0.
+const BACKGROUND_Z: f32 = -1.0;
+// In front of everything in the level, still short of the camera.
+const GRADING_Z: f32 = 900.0;
+
+// Fills the view around the level with a faint repeating pattern in the theme's colours,
+// rather than the flat clear colour, and lays a level's tint over the view when it has one.
+pub struct BackgroundPlugin;
+
+#[derive(Resource, Default)]
+struct BackgroundPattern(Handle<Image>);
+
+#[derive(Component)]
+struct Background;
+
+// A sprite kept covering the camera's view, at its own depth.
+#[derive(Component)]
+struct FillsView {
+    z: f32,
+}
+
+// Light dots on a darker ground, offset every other row of dots. The theme tints it.
+fn background_pattern() -> Image {
+    let mut pixels = Vec::with_capacity(PATTERN_PIXELS * PATTERN_PIXELS * 4);
+    for y in 0..PATTERN_PIXELS {
+        for x in 0..PATTERN_PIXELS {
+            let shift = (y / 16) * 8;
+            let dot = (x + shift) % 16 < 2 && y % 16 < 2;
+            let value = if dot { 255 } else { 200 };
+            pixels.extend([value, value, value, 255]);
+        }
+    }
+    let mut image = Image::new(
+        Extent3d {
+            width: PATTERN_PIXELS as u32,
+            height: PATTERN_PIXELS as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        pixels,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    // The sprite shows more of the pattern than there is, repeating it.
+    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        ..ImageSamplerDescriptor::nearest()
+    });
+    image
+}
+
+fn create_background_pattern(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    commands.insert_resource(BackgroundPattern(images.add(background_pattern())));
+}
+
+// Both come and go with the level's other entities.
+fn spawn_background(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    level_pack: Res<LevelPack>,
+    background_pattern: Res<BackgroundPattern>,
+    mut level_loaded_reader: EventReader<LevelLoadedEvent>,
+) {
+    let Some(level_loaded) = level_loaded_reader.read().last() else {
+        return;
+    };
+    if let Some((red, green, blue, strength)) = level_pack.metadata(level_loaded.level).tint {
+        commands.spawn((
+            LevelEntity,
+            FillsView { z: GRADING_Z },
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(red, green, blue, strength),
+                    ..default()
+                },
+                ..default()
+            },
+        ));
+    }
+    commands.spawn((
+        LevelEntity,
+        Background,
+        FillsView { z: BACKGROUND_Z },
+        SpriteBundle {
+            sprite: Sprite {
+                color: theme.background(),
+                ..default()
+            },
+            texture: background_pattern.0.clone(),
+            transform: Transform::from_xyz(0.0, 0.0, BACKGROUND_Z),
+            ..default()
+        },
+    ));
+}
+
+// Covers whatever the camera can see, wherever it's moved or zoomed to.
+fn fit_to_view(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<&Transform, (With<MainCamera>, Without<FillsView>)>,
+    mut fills_view_query: Query<(&FillsView, &mut Sprite, &mut Transform, Has<Background>)>,
+) {
+    let (Ok(window), Ok(camera_transform)) = (window_query.get_single(), camera_query.get_single())
+    else {
+        return;
+    };
+    let view_size = Vec2::new(window.width(), window.height()) * camera_transform.scale.truncate();
+    let camera_position = camera_transform.translation.truncate();
+    // The texture's rows run downwards.
+    let pattern_offset = Vec2::new(camera_position.x, -camera_position.y) * PARALLAX;
+    for (fills_view, mut sprite, mut transform, is_background) in &mut fills_view_query {
+        sprite.custom_size = Some(view_size);
+        if is_background {
+            sprite.rect = Some(Rect::from_center_size(pattern_offset, view_size));
+        }
+        transform.translation = camera_position.extend(fills_view.z);
+    }
+}
+
+fn apply_background_theme(
+    theme: Res<Theme>,
+    mut background_query: Query<&mut Sprite, With<Background>>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+    for mut sprite in &mut background_query {
+        sprite.color = theme.background();
+    }
+}
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BackgroundPattern::default())
+            .add_systems(Startup, create_background_pattern)
+            .add_systems(Update, (spawn_background, apply_background_theme))
+            .add_systems(
+                PostUpdate,
+                fit_to_view.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
@@ -0,0 +1,278 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    level_hash,
+    play_plugin::LevelPack,
+    sokoban_core::{cell_from_code, is_known_code, Tile},
+    solver::{is_solver_level, solve},
+    GameState,
+};
+
+// Packs shipped with the game live here, next to the manifest the bundler writes.
+pub const PACKS_DIRECTORY: &str = "assets/packs";
+const MANIFEST_FILE: &str = "manifest.ron";
+// Bundling runs offline, so it can afford a far bigger search than the game does.
+const SOLVE_BUDGET: usize = 2_000_000;
+
+// A pack as it's written by hand.
+#[derive(Deserialize)]
+struct PackSource {
+    name: String,
+    author: String,
+    description: String,
+    levels: Vec<Vec<Vec<i32>>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ManifestLevel {
+    pub hash: u32,
+    // Shortest solution found, `None` when the level uses tiles the solver doesn't model.
+    pub solution_moves: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ManifestPack {
+    pub file: String,
+    pub name: String,
+    pub author: String,
+    pub description: String,
+    pub levels: Vec<ManifestLevel>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct PackManifest {
+    pub packs: Vec<ManifestPack>,
+}
+
+// What every level needs before it can be played, whether it's bundled or downloaded.
+pub fn check_level(level_layout: &[Vec<i32>]) -> Result<(), String> {
+    let tiles: Vec<i32> = level_layout.iter().flatten().copied().collect();
+    if tiles.is_empty() {
+        return Err("is empty".to_string());
+    }
+    if let Some(tile) = tiles.iter().find(|tile| !is_known_code(**tile)) {
+        return Err(format!("has unknown tile {}", tile));
+    }
+    let cells: Vec<Tile> = tiles
+        .iter()
+        .flat_map(|tile| cell_from_code(*tile))
+        .collect();
+    let count = |wanted: fn(&Tile) -> bool| cells.iter().filter(|tile| wanted(tile)).count();
+    let players = count(|tile| *tile == Tile::Player);
+    if players == 0 {
+        return Err("has no player".to_string());
+    }
+    let blocks = count(|tile| matches!(tile, Tile::Block(_) | Tile::HeavyBlock));
+    let goals = count(|tile| matches!(tile, Tile::Goal(_)));
+    if blocks < goals {
+        return Err(format!("has {} goals but only {} blocks", goals, blocks));
+    }
+    // Blocks can start on goals, but not all of them or it's won before the first move.
+    let covered_goals = tiles
+        .iter()
+        .filter(|tile| cell_from_code(**tile).contains(&Tile::Block(0)))
+        .filter(|tile| cell_from_code(**tile).contains(&Tile::Goal(0)))
+        .count();
+    if goals > 0 && covered_goals == goals && count(|tile| *tile == Tile::Exit) == 0 {
+        return Err("is already solved".to_string());
+    }
+    Ok(())
+}
+
+fn validate_level(level_layout: &[Vec<i32>]) -> Result<ManifestLevel, String> {
+    check_level(level_layout)?;
+    let mut solution_moves = None;
+    if is_solver_level(level_layout) {
+        match solve(level_layout, SOLVE_BUDGET) {
+            (Some(solution), _) => solution_moves = Some(solution.len()),
+            (None, stats) => {
+                return Err(format!(
+                    "has no solution, searched {} states",
+                    stats.explored
+                ))
+            }
+        }
+    }
+    Ok(ManifestLevel {
+        hash: level_hash(level_layout),
+        solution_moves,
+    })
+}
+
+fn validate_pack(file: &str, contents: &str) -> Result<ManifestPack, Vec<String>> {
+    let source: PackSource =
+        ron::from_str(contents).map_err(|error| vec![format!("{}: {}", file, error)])?;
+    let mut problems = Vec::new();
+    for (field, value) in [
+        ("name", &source.name),
+        ("author", &source.author),
+        ("description", &source.description),
+    ] {
+        if value.trim().is_empty() {
+            problems.push(format!("{}: {} is missing", file, field));
+        }
+    }
+    if source.levels.is_empty() {
+        problems.push(format!("{}: has no levels", file));
+    }
+
+    let mut levels = Vec::new();
+    for (index, level_layout) in source.levels.iter().enumerate() {
+        match validate_level(level_layout) {
+            Ok(level) => levels.push(level),
+            Err(problem) => problems.push(format!("{}: level {} {}", file, index + 1, problem)),
+        }
+    }
+    if !problems.is_empty() {
+        return Err(problems);
+    }
+    Ok(ManifestPack {
+        file: file.to_string(),
+        name: source.name,
+        author: source.author,
+        description: source.description,
+        levels,
+    })
+}
+
+// Checks every pack in `directory` and writes the manifest the game loads them
+// through. Nothing is written if any pack has a problem.
+pub fn bundle_packs(directory: &Path) -> Result<PackManifest, Vec<String>> {
+    let entries = fs::read_dir(directory)
+        .map_err(|error| vec![format!("{}: {}", directory.display(), error)])?;
+    let mut files: Vec<String> = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|file| file.ends_with(".ron") && file != MANIFEST_FILE)
+        .collect();
+    files.sort();
+
+    let mut manifest = PackManifest::default();
+    let mut problems = Vec::new();
+    for file in files {
+        let contents = match fs::read_to_string(directory.join(&file)) {
+            Ok(contents) => contents,
+            Err(error) => {
+                problems.push(format!("{}: {}", file, error));
+                continue;
+            }
+        };
+        match validate_pack(&file, &contents) {
+            Ok(pack) => manifest.packs.push(pack),
+            Err(pack_problems) => problems.extend(pack_problems),
+        }
+    }
+    if !problems.is_empty() {
+        return Err(problems);
+    }
+
+    let path = directory.join(MANIFEST_FILE);
+    ron::ser::to_string_pretty(&manifest, ron::ser::PrettyConfig::default())
+        .map_err(|error| error.to_string())
+        .and_then(|contents| fs::write(&path, contents).map_err(|error| error.to_string()))
+        .map_err(|error| vec![format!("{}: {}", path.display(), error)])?;
+    Ok(manifest)
+}
+
+pub fn load_manifest(directory: &Path) -> PackManifest {
+    let Ok(contents) = fs::read_to_string(directory.join(MANIFEST_FILE)) else {
+        return PackManifest::default();
+    };
+    ron::from_str(&contents).unwrap_or_else(|error| {
+        bevy::log::warn!("Could not read the pack manifest: {}", error);
+        PackManifest::default()
+    })
+}
+
+// The packs listed in the manifest. A pack whose levels no longer match what was
+// bundled is left out rather than shipped unchecked.
+pub fn load_bundled_packs(directory: &Path) -> Vec<LevelPack> {
+    load_manifest(directory)
+        .packs
+        .into_iter()
+        .filter_map(|pack| {
+            let source: PackSource = fs::read_to_string(directory.join(&pack.file))
+                .ok()
+                .and_then(|contents| ron::from_str(&contents).ok())?;
+            let hashes: Vec<u32> = source
+                .levels
+                .iter()
+                .map(|level| level_hash(level))
+                .collect();
+            let bundled: Vec<u32> = pack.levels.iter().map(|level| level.hash).collect();
+            if hashes != bundled {
+                bevy::log::warn!("{} changed since it was bundled, skipping it", pack.file);
+                return None;
+            }
+            Some(LevelPack {
+                name: pack.name,
+                levels: source.levels,
+                metadata: Vec::new(),
+                return_state: GameState::LevelSelect,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    const VALID_PACK: &str = r#"(
+        name: "Corridors",
+        author: "Tester",
+        description: "One push to the goal.",
+        levels: [[[8, 8, 8, 8, 8], [8, 1, 2, 4, 8], [8, 8, 8, 8, 8]]],
+    )"#;
+
+    // Each test bundles its own directory of packs.
+    fn packs_directory(name: &str, packs: &[(&str, &str)]) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("bundle-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        for (file, contents) in packs {
+            fs::write(directory.join(file), contents).unwrap();
+        }
+        directory
+    }
+
+    #[test]
+    fn bundles_a_valid_pack_into_the_manifest() {
+        let directory = packs_directory("valid", &[("corridors.ron", VALID_PACK)]);
+
+        let manifest = bundle_packs(&directory).unwrap();
+        assert_eq!(manifest.packs.len(), 1);
+        assert!(manifest.packs[0].levels[0].solution_moves.is_some());
+
+        let loaded = load_manifest(&directory);
+        assert_eq!(loaded.packs.len(), 1);
+        assert_eq!(loaded.packs[0].file, "corridors.ron");
+        assert_eq!(loaded.packs[0].name, "Corridors");
+        assert_eq!(load_bundled_packs(&directory).len(), 1);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_pack_with_a_level_without_a_player() {
+        let no_player = VALID_PACK.replace("[8, 1, 2, 4, 8]", "[8, 0, 2, 4, 8]");
+        let directory = packs_directory(
+            "rejected",
+            &[("corridors.ron", VALID_PACK), ("empty.ron", &no_player)],
+        );
+
+        let problems = bundle_packs(&directory).unwrap_err();
+        assert_eq!(
+            problems,
+            vec!["empty.ron: level 1 has no player".to_string()]
+        );
+        // Nothing is written, not even the valid pack.
+        assert!(load_manifest(&directory).packs.is_empty());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,265 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    level_data::{Decoration, LevelMetadata},
+    level_hash,
+    limits_plugin::LevelLimit,
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{LevelPack, LevelState, NextLevelEvent},
+    GameState,
+};
+
+// The campaign is a manifest of chapters, each listing its level files in the order they're played.
+pub const CAMPAIGN_DIRECTORY: &str = "assets/campaign";
+const MANIFEST_FILE: &str = "campaign.ron";
+
+pub struct CampaignPlugin;
+
+#[derive(Deserialize)]
+struct ManifestChapter {
+    title: String,
+    levels: Vec<String>,
+    // Grades every level in the chapter, unless the level has a tint of its own.
+    #[serde(default)]
+    tint: Option<(f32, f32, f32, f32)>,
+}
+
+#[derive(Deserialize)]
+struct CampaignManifest {
+    chapters: Vec<ManifestChapter>,
+}
+
+// A campaign level as it's written by hand.
+#[derive(Deserialize)]
+struct CampaignLevelFile {
+    title: String,
+    #[serde(default)]
+    author: String,
+    #[serde(default)]
+    difficulty: String,
+    #[serde(default)]
+    par: Option<usize>,
+    #[serde(default)]
+    hint: String,
+    #[serde(default)]
+    decorations: Vec<(i32, i32, Decoration)>,
+    #[serde(default)]
+    tint: Option<(f32, f32, f32, f32)>,
+    #[serde(default)]
+    limit: Option<LevelLimit>,
+    layout: Vec<Vec<i32>>,
+}
+
+pub struct CampaignLevel {
+    // Its file name within the campaign directory.
+    pub file: String,
+    pub layout: Vec<Vec<i32>>,
+    pub metadata: LevelMetadata,
+    // What the level has to be solved within when limits are turned on.
+    pub limit: Option<LevelLimit>,
+}
+
+pub struct Chapter {
+    pub title: String,
+    pub levels: Vec<CampaignLevel>,
+}
+
+// Levels are numbered from 1 straight through the chapters.
+#[derive(Resource, Default)]
+pub struct Campaign {
+    pub chapters: Vec<Chapter>,
+}
+
+impl Campaign {
+    pub fn load(directory: &Path) -> Result<Campaign, String> {
+        let manifest_path = directory.join(MANIFEST_FILE);
+        let manifest: CampaignManifest = fs::read_to_string(&manifest_path)
+            .map_err(|error| error.to_string())
+            .and_then(|contents| ron::from_str(&contents).map_err(|error| error.to_string()))
+            .map_err(|error| format!("{}: {}", manifest_path.display(), error))?;
+
+        let mut chapters = Vec::new();
+        for chapter in manifest.chapters {
+            let mut levels = Vec::new();
+            for file in chapter.levels {
+                let path = directory.join(&file);
+                let level_file: CampaignLevelFile = fs::read_to_string(&path)
+                    .map_err(|error| error.to_string())
+                    .and_then(|contents| {
+                        ron::from_str(&contents).map_err(|error| error.to_string())
+                    })
+                    .map_err(|error| format!("{}: {}", path.display(), error))?;
+                levels.push(CampaignLevel {
+                    file,
+                    layout: level_file.layout,
+                    metadata: LevelMetadata {
+                        title: level_file.title,
+                        author: level_file.author,
+                        difficulty: level_file.difficulty,
+                        par: level_file.par,
+                        hint: level_file.hint,
+                        decorations: level_file.decorations,
+                        tint: level_file.tint.or(chapter.tint),
+                    },
+                    limit: level_file.limit,
+                });
+            }
+            chapters.push(Chapter {
+                title: chapter.title,
+                levels,
+            });
+        }
+        Ok(Campaign { chapters })
+    }
+
+    pub fn levels(&self) -> impl Iterator<Item = &CampaignLevel> {
+        self.chapters.iter().flat_map(|chapter| &chapter.levels)
+    }
+
+    pub fn level_count(&self) -> i32 {
+        self.levels().count() as i32
+    }
+
+    pub fn level(&self, level: i32) -> Option<&CampaignLevel> {
+        self.levels().nth(usize::try_from(level - 1).ok()?)
+    }
+
+    // Notes and best replays used to be kept by campaign level number, this moves any
+    // still kept that way over to the level's hash. Returns whether anything moved.
+    pub fn rekey_by_level_hash<T>(&self, entries: &mut BTreeMap<u32, T>) -> bool {
+        let numbered: Vec<u32> = entries
+            .keys()
+            .copied()
+            .filter(|key| (1..=self.level_count() as u32).contains(key))
+            .collect();
+        for key in &numbered {
+            let (Some(level), Some(entry)) = (self.level(*key as i32), entries.remove(key)) else {
+                continue;
+            };
+            entries.entry(level_hash(&level.layout)).or_insert(entry);
+        }
+        !numbered.is_empty()
+    }
+
+    // The chapter that `level` finishes, when another chapter comes after it.
+    pub fn finished_chapter(&self, level: i32) -> Option<usize> {
+        let mut last_level = 0;
+        for (index, chapter) in self.chapters.iter().enumerate() {
+            last_level += chapter.levels.len() as i32;
+            if last_level == level && index + 1 < self.chapters.len() {
+                return Some(index);
+            }
+        }
+        None
+    }
+}
+
+#[derive(Component)]
+struct ChapterCompleteMenu;
+
+fn show_chapter_complete(
+    mut commands: Commands,
+    campaign: Res<Campaign>,
+    level_state: Res<LevelState>,
+) {
+    let Some(chapter) = campaign.finished_chapter(level_state.current_level) else {
+        return;
+    };
+    let next_chapter = &campaign.chapters[chapter + 1];
+
+    commands
+        .spawn((
+            ChapterCompleteMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("Chapter {} complete!", chapter + 1),
+                TextStyle {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                campaign.chapters[chapter].title.clone(),
+                TextStyle {
+                    font_size: 20.0,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                format!("Next up, chapter {}: {}", chapter + 2, next_chapter.title),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::CYAN,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                "Enter: continue  L: levels",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn hide_chapter_complete(
+    mut commands: Commands,
+    chapter_complete_menu_query: Query<Entity, With<ChapterCompleteMenu>>,
+) {
+    for entity in chapter_complete_menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_chapter_complete_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    level_state: Res<LevelState>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        next_level_writer.send(NextLevelEvent(level_state.current_level + 1));
+        game_state.set(GameState::Playing);
+    } else if keyboard_input.just_pressed(KeyCode::L) {
+        game_state.set(GameState::LevelSelect);
+    }
+}
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        let campaign = Campaign::load(Path::new(CAMPAIGN_DIRECTORY)).unwrap_or_else(|error| {
+            error!("Could not load the campaign: {}", error);
+            Campaign::default()
+        });
+        app.insert_resource(LevelPack::campaign(&campaign))
+            .insert_resource(campaign)
+            .add_systems(OnEnter(GameState::ChapterComplete), show_chapter_complete)
+            .add_systems(OnExit(GameState::ChapterComplete), hide_chapter_complete)
+            .add_systems(
+                Update,
+                handle_chapter_complete_input
+                    .run_if(in_state(GameState::ChapterComplete))
+                    .run_if(quit_prompt_closed),
+            );
+    }
+}
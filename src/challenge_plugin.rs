@@ -0,0 +1,345 @@
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    accessibility_plugin::ListEntry,
+    generator::{generate_level, Theme},
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{
+        unload_level, LevelCompletedEvent, LevelPack, NextLevelEvent, PackCompletedEvent,
+    },
+    profile::Profile,
+    GameState,
+};
+
+const CHALLENGES_FILE: &str = "challenges.ron";
+
+pub struct ChallengePlugin;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WeeklyChallenge {
+    pub levels: Vec<Vec<Vec<i32>>>,
+    pub best_total_moves: Option<usize>,
+}
+
+// Every week that has been generated, keyed by ISO week, e.g. "2026-W42".
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct ChallengeArchive(pub BTreeMap<String, WeeklyChallenge>);
+
+#[derive(Resource, Default)]
+struct ChallengeRun {
+    week: String,
+    moves: Vec<usize>,
+}
+
+#[derive(Resource, Default)]
+struct ChallengeMenuState {
+    weeks: Vec<String>,
+    selected: usize,
+}
+
+#[derive(Component)]
+struct ChallengeMenu;
+
+#[derive(Component)]
+struct ChallengeRow(usize);
+
+// Howard Hinnant's days-to-civil conversion.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let mp = (i64::from(month) + 9) % 12;
+    let day_of_year = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+// An ISO week belongs to the year its Thursday falls in.
+pub fn iso_week(days_since_epoch: i64) -> String {
+    // 1970-01-01 was a Thursday.
+    let weekday = (days_since_epoch + 3).rem_euclid(7);
+    let thursday = days_since_epoch - weekday + 3;
+    let (year, _, _) = civil_from_days(thursday);
+    let week = (thursday - days_from_civil(year, 1, 1)) / 7 + 1;
+    format!("{}-W{:02}", year, week)
+}
+
+// As "YYYY-MM-DD".
+pub fn iso_date(days_since_epoch: i64) -> String {
+    let (year, month, day) = civil_from_days(days_since_epoch);
+    format!("{}-{:02}-{:02}", year, month, day)
+}
+
+pub fn days_since_epoch() -> i64 {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    (seconds / 86400) as i64
+}
+
+fn current_week() -> String {
+    iso_week(days_since_epoch())
+}
+
+// FNV-1a over a week or date string, so everyone generates the same levels from it.
+pub fn text_seed(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+pub fn generate_weekly_challenge(week: &str) -> WeeklyChallenge {
+    let seed = text_seed(week);
+    WeeklyChallenge {
+        levels: Theme::ALL
+            .iter()
+            .enumerate()
+            .map(|(index, theme)| generate_level(seed ^ ((index as u64) << 32), *theme))
+            .collect(),
+        best_total_moves: None,
+    }
+}
+
+fn load_challenges(mut commands: Commands, profile: Res<Profile>) {
+    let mut archive = profile.load::<ChallengeArchive>(CHALLENGES_FILE);
+    let week = current_week();
+    if let Entry::Vacant(entry) = archive.0.entry(week) {
+        let challenge = generate_weekly_challenge(entry.key());
+        entry.insert(challenge);
+        profile.save(CHALLENGES_FILE, &archive);
+    }
+    commands.insert_resource(archive);
+}
+
+fn show_challenge_menu(mut commands: Commands, archive: Res<ChallengeArchive>) {
+    let weeks: Vec<String> = archive.0.keys().rev().cloned().collect();
+
+    commands
+        .spawn((
+            ChallengeMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Weekly challenges",
+                TextStyle {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                Theme::ALL
+                    .iter()
+                    .map(Theme::name)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+            for row in 0..weeks.len() {
+                parent.spawn((
+                    ChallengeRow(row),
+                    ListEntry::default(),
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                    ),
+                ));
+            }
+            parent.spawn(TextBundle::from_section(
+                "Up/Down: choose  Enter: play  L: levels",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+        });
+
+    commands.insert_resource(ChallengeMenuState { weeks, selected: 0 });
+}
+
+fn hide_challenge_menu(
+    mut commands: Commands,
+    challenge_menu_query: Query<Entity, With<ChallengeMenu>>,
+) {
+    for entity in challenge_menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_challenge_menu_input(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    archive: Res<ChallengeArchive>,
+    mut challenge_menu_state: ResMut<ChallengeMenuState>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::L) {
+        game_state.set(GameState::LevelSelect);
+        return;
+    }
+
+    let week_count = challenge_menu_state.weeks.len();
+    if week_count == 0 {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        challenge_menu_state.selected =
+            (challenge_menu_state.selected + week_count - 1) % week_count;
+    } else if keyboard_input.just_pressed(KeyCode::Down) {
+        challenge_menu_state.selected = (challenge_menu_state.selected + 1) % week_count;
+    } else if keyboard_input.just_pressed(KeyCode::Return) {
+        let week = challenge_menu_state.weeks[challenge_menu_state.selected].clone();
+        let Some(challenge) = archive.0.get(&week) else {
+            return;
+        };
+        commands.insert_resource(LevelPack {
+            name: week.clone(),
+            levels: challenge.levels.clone(),
+            metadata: Vec::new(),
+            return_state: GameState::Challenges,
+        });
+        commands.insert_resource(ChallengeRun {
+            week,
+            moves: Vec::new(),
+        });
+        next_level_writer.send(NextLevelEvent(1));
+        game_state.set(GameState::Playing);
+    }
+}
+
+fn update_challenge_rows(
+    archive: Res<ChallengeArchive>,
+    challenge_menu_state: Res<ChallengeMenuState>,
+    mut row_query: Query<(&ChallengeRow, &mut Text, &mut ListEntry)>,
+) {
+    let this_week = current_week();
+    for (row, mut text, mut entry) in &mut row_query {
+        let Some(week) = challenge_menu_state.weeks.get(row.0) else {
+            continue;
+        };
+        let score = match archive.0.get(week).and_then(|c| c.best_total_moves) {
+            Some(best_total_moves) => format!("best {} moves", best_total_moves),
+            None => "not completed".to_string(),
+        };
+        let label = if *week == this_week {
+            format!("{} (this week)", week)
+        } else {
+            week.clone()
+        };
+
+        text.sections[0].value = format!("{} - {}", label, score);
+        entry.selected = row.0 == challenge_menu_state.selected;
+        text.sections[0].style.color = if entry.selected {
+            Color::YELLOW
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+// Reads both events in one system so the last level's moves are counted before
+// the pack completion that follows it in the same frame.
+fn track_challenge_run(
+    profile: Res<Profile>,
+    level_pack: Res<LevelPack>,
+    mut challenge_run: ResMut<ChallengeRun>,
+    mut archive: ResMut<ChallengeArchive>,
+    mut level_completed_reader: EventReader<LevelCompletedEvent>,
+    mut pack_completed_reader: EventReader<PackCompletedEvent>,
+) {
+    for level_completed in level_completed_reader.read() {
+        if level_pack.name == challenge_run.week {
+            challenge_run.moves.push(level_completed.moves.len());
+        }
+    }
+
+    for pack_completed in pack_completed_reader.read() {
+        if pack_completed.name != challenge_run.week {
+            continue;
+        }
+        let Some(challenge) = archive.0.get_mut(&challenge_run.week) else {
+            continue;
+        };
+        // The aggregate score is the total number of moves across every level.
+        let total_moves: usize = challenge_run.moves.iter().sum();
+        info!(
+            "Finished challenge {} in {} moves",
+            challenge_run.week, total_moves
+        );
+        if challenge
+            .best_total_moves
+            .is_none_or(|best| total_moves < best)
+        {
+            challenge.best_total_moves = Some(total_moves);
+            profile.save(CHALLENGES_FILE, &*archive);
+        }
+    }
+}
+
+impl Plugin for ChallengePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ChallengeArchive::default())
+            .insert_resource(ChallengeRun::default())
+            .insert_resource(ChallengeMenuState::default())
+            .add_systems(Startup, load_challenges)
+            .add_systems(
+                OnEnter(GameState::Challenges),
+                (unload_level, show_challenge_menu),
+            )
+            .add_systems(OnExit(GameState::Challenges), hide_challenge_menu)
+            .add_systems(
+                Update,
+                (
+                    handle_challenge_menu_input.run_if(quit_prompt_closed),
+                    update_challenge_rows.after(handle_challenge_menu_input),
+                )
+                    .run_if(in_state(GameState::Challenges)),
+            )
+            .add_systems(Update, track_challenge_run);
+    }
+}
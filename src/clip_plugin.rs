@@ -0,0 +1,250 @@
+use std::{collections::VecDeque, fs, path::PathBuf};
+
+use bevy::{prelude::*, render::render_resource::TextureFormat};
+use gif::{Encoder, Frame, Repeat};
+
+use crate::{
+    level_viewer_plugin::{floor_positions, viewer_tiles, ViewerTile},
+    play_plugin::LevelState,
+    GameState, Obstacle, Position, TILE_SIZE,
+};
+
+const CLIP_SECONDS: f32 = 10.0;
+const CLIPS_DIRECTORY: &str = "clips";
+// How long the final board stays up before the GIF loops.
+const FINAL_FRAME_CENTISECONDS: u16 = 100;
+
+pub struct ClipPlugin;
+
+pub struct ClipFrame {
+    pub seconds: f32,
+    pub players: Vec<Position>,
+    pub blocks: Vec<Position>,
+}
+
+impl ClipFrame {
+    // Where the players and blocks are now, in a fixed order so boards can be compared.
+    pub fn of(level_state: &LevelState, seconds: f32) -> Self {
+        let mut blocks: Vec<Position> = level_state
+            .obstacles
+            .iter()
+            .filter(|(_, (_, obstacle))| matches!(obstacle, Obstacle::Block(_)))
+            .map(|(position, _)| *position)
+            .collect();
+        blocks.sort_by_key(|position| (position.y, position.x));
+        let mut players: Vec<Position> = level_state.players.values().copied().collect();
+        players.sort_by_key(|position| (position.y, position.x));
+        Self {
+            seconds,
+            players,
+            blocks,
+        }
+    }
+
+    pub fn same_board(&self, other: &ClipFrame) -> bool {
+        self.players == other.players && self.blocks == other.blocks
+    }
+}
+
+// The board after every move in the last few seconds, ready to be turned into a GIF.
+#[derive(Resource, Default)]
+struct ClipBuffer {
+    layout: Vec<Vec<i32>>,
+    frames: VecDeque<ClipFrame>,
+}
+
+fn record_clip_frames(
+    time: Res<Time>,
+    level_state: Res<LevelState>,
+    mut clip_buffer: ResMut<ClipBuffer>,
+) {
+    if !level_state.is_changed() {
+        return;
+    }
+    if clip_buffer.layout != level_state.layout {
+        clip_buffer.layout = level_state.layout.clone();
+        clip_buffer.frames.clear();
+    }
+
+    let now = time.elapsed_seconds();
+    let frame = ClipFrame::of(&level_state, now);
+    if clip_buffer
+        .frames
+        .back()
+        .is_some_and(|last| last.same_board(&frame))
+    {
+        return;
+    }
+
+    clip_buffer.frames.push_back(frame);
+    // Keep the last frame from before the window, it's the board the clip opens on.
+    while clip_buffer
+        .frames
+        .get(1)
+        .is_some_and(|frame| frame.seconds < now - CLIP_SECONDS)
+    {
+        clip_buffer.frames.pop_front();
+    }
+}
+
+pub fn draw_tile(
+    pixels: &mut [u8],
+    width: usize,
+    position: Position,
+    tile: &ViewerTile,
+    images: &Assets<Image>,
+    asset_server: &AssetServer,
+) {
+    let size = TILE_SIZE as usize;
+    let texture = match tile {
+        ViewerTile::Texture(texture) => images
+            .get(asset_server.load::<Image>(*texture).id())
+            .filter(|image| {
+                image.texture_descriptor.format == TextureFormat::Rgba8UnormSrgb
+                    && image.size() == UVec2::splat(TILE_SIZE as u32)
+            }),
+        ViewerTile::Color(_) => None,
+    };
+    for y in 0..size {
+        for x in 0..size {
+            let color = match (tile, texture) {
+                (_, Some(image)) => {
+                    let start = (y * size + x) * 4;
+                    [
+                        image.data[start],
+                        image.data[start + 1],
+                        image.data[start + 2],
+                        image.data[start + 3],
+                    ]
+                }
+                (ViewerTile::Color(color), None) => color.as_rgba_u8(),
+                // Textures that aren't loaded yet are left out.
+                (ViewerTile::Texture(_), None) => return,
+            };
+            if color[3] < 128 {
+                continue;
+            }
+            let pixel_x = position.x as usize * size + x;
+            let pixel_y = position.y as usize * size + y;
+            let start = (pixel_y * width + pixel_x) * 4;
+            pixels[start..start + 4].copy_from_slice(&[color[0], color[1], color[2], 255]);
+        }
+    }
+}
+
+// Each frame is shown from its `seconds`, or from `start` if that's later, until the next one.
+pub fn encode_board_gif(
+    layout: &[Vec<i32>],
+    frames: &[ClipFrame],
+    start: f32,
+    images: &Assets<Image>,
+    asset_server: &AssetServer,
+) -> Result<Vec<u8>, gif::EncodingError> {
+    // Players and blocks are drawn from each frame, everything else once.
+    let board: Vec<Vec<i32>> = layout
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|tile| if *tile < 16 { tile & !3 } else { *tile })
+                .collect()
+        })
+        .collect();
+    let tiles = viewer_tiles(&board, &floor_positions(layout));
+    let columns = board.iter().map(|row| row.len()).max().unwrap_or(0);
+    let width = columns * TILE_SIZE as usize;
+    let height = board.len() * TILE_SIZE as usize;
+
+    let mut gif = Vec::new();
+    {
+        let mut encoder = Encoder::new(&mut gif, width as u16, height as u16, &[])?;
+        encoder.set_repeat(Repeat::Infinite)?;
+        for (index, frame) in frames.iter().enumerate() {
+            let mut pixels = vec![0; width * height * 4];
+            let mut frame_tiles = tiles.clone();
+            frame_tiles.extend(
+                frame
+                    .blocks
+                    .iter()
+                    .map(|block| (*block, ViewerTile::Texture("block.png"))),
+            );
+            frame_tiles.extend(
+                frame
+                    .players
+                    .iter()
+                    .map(|player| (*player, ViewerTile::Texture("player.png"))),
+            );
+            for (position, tile) in &frame_tiles {
+                draw_tile(&mut pixels, width, *position, tile, images, asset_server);
+            }
+
+            let shown_from = frame.seconds.max(start);
+            let delay = match frames.get(index + 1) {
+                Some(next) => ((next.seconds - shown_from) * 100.0) as u16,
+                None => FINAL_FRAME_CENTISECONDS,
+            };
+            let mut gif_frame =
+                Frame::from_rgba_speed(width as u16, height as u16, &mut pixels, 10);
+            gif_frame.delay = delay.max(1);
+            encoder.write_frame(&gif_frame)?;
+        }
+    }
+    Ok(gif)
+}
+
+fn clip_path(level: i32) -> PathBuf {
+    (1..)
+        .map(|clip| {
+            PathBuf::from(CLIPS_DIRECTORY).join(format!("level-{}-clip-{}.gif", level, clip))
+        })
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+fn export_clip(
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    level_state: Res<LevelState>,
+    mut clip_buffer: ResMut<ClipBuffer>,
+    images: Res<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::C) || clip_buffer.frames.is_empty() {
+        return;
+    }
+
+    let ClipBuffer { layout, frames } = &mut *clip_buffer;
+    let start = time.elapsed_seconds() - CLIP_SECONDS;
+    let gif = match encode_board_gif(
+        layout,
+        frames.make_contiguous(),
+        start,
+        &images,
+        &asset_server,
+    ) {
+        Ok(gif) => gif,
+        Err(error) => {
+            warn!("Could not encode clip: {}", error);
+            return;
+        }
+    };
+    let path = clip_path(level_state.current_level);
+    if let Err(error) = fs::create_dir_all(CLIPS_DIRECTORY).and_then(|_| fs::write(&path, gif)) {
+        warn!("Could not write {}: {}", path.display(), error);
+        return;
+    }
+    info!("Saved clip to {}", path.display());
+}
+
+impl Plugin for ClipPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ClipBuffer::default()).add_systems(
+            Update,
+            (
+                record_clip_frames,
+                export_clip
+                    .after(record_clip_frames)
+                    .run_if(in_state(GameState::Playing)),
+            ),
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    bundle::{load_manifest, PACKS_DIRECTORY},
+    pause_plugin::quit_prompt_closed,
+    GameState,
+};
+
+// Authorship for everything that isn't a level pack: art, audio, themes and so on.
+const CREDITS_FILE: &str = "assets/credits.ron";
+
+pub struct CreditsPlugin;
+
+#[derive(Deserialize)]
+struct CreditEntry {
+    kind: String,
+    work: String,
+    author: String,
+}
+
+#[derive(Deserialize, Default)]
+struct CreditsFile {
+    entries: Vec<CreditEntry>,
+}
+
+#[derive(Component)]
+struct CreditsMenu;
+
+// Each heading and its lines, in the order headings first turn up.
+fn collect_credits() -> Vec<(String, Vec<String>)> {
+    let credits_file: CreditsFile = fs::read_to_string(CREDITS_FILE)
+        .ok()
+        .and_then(|contents| ron::from_str(&contents).ok())
+        .unwrap_or_default();
+    let pack_entries = load_manifest(Path::new(PACKS_DIRECTORY))
+        .packs
+        .into_iter()
+        .map(|pack| CreditEntry {
+            kind: "Level packs".to_string(),
+            work: pack.name,
+            author: pack.author,
+        });
+
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+    for entry in credits_file.entries.into_iter().chain(pack_entries) {
+        let line = format!("{} by {}", entry.work, entry.author);
+        match sections.iter_mut().find(|(kind, _)| *kind == entry.kind) {
+            Some((_, lines)) => lines.push(line),
+            None => sections.push((entry.kind, vec![line])),
+        }
+    }
+    sections
+}
+
+fn show_credits(mut commands: Commands) {
+    let sections = collect_credits();
+
+    commands
+        .spawn((
+            CreditsMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Credits",
+                TextStyle {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+            for (kind, lines) in sections {
+                parent.spawn(TextBundle::from_section(
+                    kind,
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::CYAN,
+                        ..default()
+                    },
+                ));
+                parent.spawn(TextBundle::from_section(
+                    lines.join("\n"),
+                    TextStyle {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                ));
+            }
+            parent.spawn(TextBundle::from_section(
+                "L: levels",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn hide_credits(mut commands: Commands, credits_menu_query: Query<Entity, With<CreditsMenu>>) {
+    for entity in credits_menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_credits_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::L) {
+        game_state.set(GameState::LevelSelect);
+    }
+}
+
+impl Plugin for CreditsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Credits), show_credits)
+            .add_systems(OnExit(GameState::Credits), hide_credits)
+            .add_systems(
+                Update,
+                handle_credits_input
+                    .run_if(in_state(GameState::Credits))
+                    .run_if(quit_prompt_closed),
+            );
+    }
+}
@@ -0,0 +1,423 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    accessibility_plugin::ListEntry,
+    bundle::check_level,
+    difficulty::{estimate_difficulty, Difficulty},
+    level_data::{Decoration, LevelMetadata},
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{LevelPack, NextLevelEvent},
+    thumbnail_plugin::ThumbnailCache,
+    GameState,
+};
+
+// Levels made or collected by the player, one RON file each.
+pub const CUSTOM_LEVELS_DIRECTORY: &str = "levels";
+const THUMBNAIL_HEIGHT: f32 = 24.0;
+
+pub struct CustomLevelsPlugin;
+
+#[derive(Serialize, Deserialize)]
+pub struct CustomLevelFile {
+    pub name: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub difficulty: String,
+    #[serde(default)]
+    pub par: Option<usize>,
+    #[serde(default)]
+    pub hint: String,
+    #[serde(default)]
+    pub decorations: Vec<(i32, i32, Decoration)>,
+    pub layout: Vec<Vec<i32>>,
+}
+
+impl CustomLevelFile {
+    pub fn metadata(&self) -> LevelMetadata {
+        LevelMetadata {
+            title: self.name.clone(),
+            author: self.author.clone(),
+            difficulty: self.difficulty.clone(),
+            par: self.par,
+            hint: self.hint.clone(),
+            decorations: self.decorations.clone(),
+            tint: None,
+        }
+    }
+}
+
+struct CustomLevel {
+    path: PathBuf,
+    file: CustomLevelFile,
+    difficulty: Difficulty,
+    // Why the level can't be played, it's still listed so the player knows to fix it.
+    problem: Option<String>,
+}
+
+impl CustomLevel {
+    // The author's rating when they gave one, otherwise the estimate.
+    fn difficulty_label(&self) -> &str {
+        match self.file.difficulty.as_str() {
+            "" => self.difficulty.label(),
+            difficulty => difficulty,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+enum SortOrder {
+    #[default]
+    Name,
+    Author,
+    Difficulty,
+}
+
+impl SortOrder {
+    fn next(self) -> Self {
+        match self {
+            SortOrder::Name => SortOrder::Author,
+            SortOrder::Author => SortOrder::Difficulty,
+            SortOrder::Difficulty => SortOrder::Name,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortOrder::Name => "name",
+            SortOrder::Author => "author",
+            SortOrder::Difficulty => "difficulty",
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct CustomLevelsState {
+    levels: Vec<CustomLevel>,
+    selected: usize,
+    sort_order: SortOrder,
+    // Delete has to be pressed twice in a row.
+    confirming_delete: bool,
+}
+
+impl CustomLevelsState {
+    fn sort(&mut self) {
+        match self.sort_order {
+            SortOrder::Name => self
+                .levels
+                .sort_by_key(|level| level.file.name.to_lowercase()),
+            SortOrder::Author => self
+                .levels
+                .sort_by_key(|level| level.file.author.to_lowercase()),
+            SortOrder::Difficulty => self.levels.sort_by(|a, b| {
+                a.difficulty
+                    .score()
+                    .partial_cmp(&b.difficulty.score())
+                    .unwrap()
+            }),
+        }
+    }
+}
+
+#[derive(Component)]
+struct CustomLevelsMenu;
+
+#[derive(Component)]
+struct CustomLevelsList;
+
+#[derive(Component)]
+struct CustomLevelsStatus;
+
+// Every level file in the custom levels folder, files that can't be read are skipped.
+fn scan_custom_levels() -> Vec<CustomLevel> {
+    let Ok(entries) = fs::read_dir(CUSTOM_LEVELS_DIRECTORY) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .filter_map(|path| {
+            let contents = fs::read_to_string(&path).ok()?;
+            let file: CustomLevelFile = ron::from_str(&contents)
+                .map_err(|error| warn!("Could not read {}: {}", path.display(), error))
+                .ok()?;
+            let difficulty = estimate_difficulty(&file.layout);
+            let problem = check_level(&file.layout).err();
+            Some(CustomLevel {
+                path,
+                file,
+                difficulty,
+                problem,
+            })
+        })
+        .collect()
+}
+
+fn show_custom_levels(mut commands: Commands) {
+    let mut custom_levels_state = CustomLevelsState {
+        levels: scan_custom_levels(),
+        ..default()
+    };
+    custom_levels_state.sort();
+    commands.insert_resource(custom_levels_state);
+
+    commands
+        .spawn((
+            CustomLevelsMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Custom levels",
+                TextStyle {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                CustomLevelsList,
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                CustomLevelsStatus,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::CYAN,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn(TextBundle::from_section(
+                "Up/Down: choose  Tab: sort  Enter: play  Delete: delete  L: levels",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn hide_custom_levels(
+    mut commands: Commands,
+    custom_levels_menu_query: Query<Entity, With<CustomLevelsMenu>>,
+) {
+    for entity in custom_levels_menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_custom_levels_input(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut custom_levels_state: ResMut<CustomLevelsState>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::L) {
+        game_state.set(GameState::LevelSelect);
+        return;
+    }
+    let level_count = custom_levels_state.levels.len();
+    if level_count == 0 {
+        return;
+    }
+    let pressed_delete = keyboard_input.just_pressed(KeyCode::Delete);
+    // Any other key calls the delete off.
+    let pressed_other = keyboard_input
+        .get_just_pressed()
+        .any(|key| *key != KeyCode::Delete);
+    if custom_levels_state.confirming_delete && pressed_other {
+        custom_levels_state.confirming_delete = false;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        custom_levels_state.selected =
+            (custom_levels_state.selected + level_count - 1) % level_count;
+    } else if keyboard_input.just_pressed(KeyCode::Down) {
+        custom_levels_state.selected = (custom_levels_state.selected + 1) % level_count;
+    } else if keyboard_input.just_pressed(KeyCode::Tab) {
+        custom_levels_state.sort_order = custom_levels_state.sort_order.next();
+        custom_levels_state.sort();
+        custom_levels_state.selected = 0;
+    } else if pressed_delete && !custom_levels_state.confirming_delete {
+        custom_levels_state.confirming_delete = true;
+    } else if pressed_delete {
+        custom_levels_state.confirming_delete = false;
+        let selected = custom_levels_state.selected;
+        let path = custom_levels_state.levels[selected].path.clone();
+        if let Err(error) = fs::remove_file(&path) {
+            warn!("Could not delete {}: {}", path.display(), error);
+            return;
+        }
+        custom_levels_state.levels.remove(selected);
+        custom_levels_state.selected =
+            selected.min(custom_levels_state.levels.len().saturating_sub(1));
+    } else if keyboard_input.just_pressed(KeyCode::Return) {
+        let level = &custom_levels_state.levels[custom_levels_state.selected];
+        if level.problem.is_some() {
+            return;
+        }
+        commands.insert_resource(LevelPack {
+            name: level.file.name.clone(),
+            levels: vec![level.file.layout.clone()],
+            metadata: vec![level.file.metadata()],
+            return_state: GameState::CustomLevels,
+        });
+        next_level_writer.send(NextLevelEvent(1));
+        game_state.set(GameState::Playing);
+    }
+}
+
+// Rebuilds the rows whenever the list, its order or the selection changes.
+fn update_custom_levels_list(
+    mut commands: Commands,
+    custom_levels_state: Res<CustomLevelsState>,
+    mut thumbnail_cache: ResMut<ThumbnailCache>,
+    mut images: ResMut<Assets<Image>>,
+    list_query: Query<Entity, With<CustomLevelsList>>,
+    mut status_query: Query<&mut Text, With<CustomLevelsStatus>>,
+) {
+    if !custom_levels_state.is_changed() {
+        return;
+    }
+    let Ok(list) = list_query.get_single() else {
+        return;
+    };
+    commands.entity(list).despawn_descendants();
+    commands.entity(list).with_children(|parent| {
+        for (row, level) in custom_levels_state.levels.iter().enumerate() {
+            let thumbnail = thumbnail_cache.thumbnail(&level.file.layout, &mut images);
+            let author = match level.file.author.trim() {
+                "" => "unknown",
+                author => author,
+            };
+            let color = if row == custom_levels_state.selected {
+                Color::YELLOW
+            } else if level.problem.is_some() {
+                Color::RED
+            } else {
+                Color::WHITE
+            };
+            let text = match &level.problem {
+                Some(problem) => format!("{} - can't be played, it {}", level.file.name, problem),
+                None => format!(
+                    "{} by {} - {}",
+                    level.file.name,
+                    author,
+                    level.difficulty_label()
+                ),
+            };
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(8.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(ImageBundle {
+                        style: Style {
+                            height: Val::Px(THUMBNAIL_HEIGHT),
+                            ..default()
+                        },
+                        image: UiImage::new(thumbnail),
+                        ..default()
+                    });
+                    parent.spawn((
+                        ListEntry {
+                            selected: row == custom_levels_state.selected,
+                        },
+                        TextBundle::from_section(
+                            text,
+                            TextStyle {
+                                font_size: 20.0,
+                                color,
+                                ..default()
+                            },
+                        ),
+                    ));
+                });
+        }
+    });
+
+    let status = if custom_levels_state.levels.is_empty() {
+        format!("No levels found in {}/", CUSTOM_LEVELS_DIRECTORY)
+    } else if custom_levels_state.confirming_delete {
+        format!(
+            "Press Delete again to delete {}",
+            custom_levels_state.levels[custom_levels_state.selected]
+                .file
+                .name
+        )
+    } else if let Some(level) = custom_levels_state
+        .levels
+        .get(custom_levels_state.selected)
+        .filter(|level| level.problem.is_some())
+    {
+        format!("Fix {} before playing it", level.path.display())
+    } else {
+        format!("Sorted by {}", custom_levels_state.sort_order.label())
+    };
+    for mut text in &mut status_query {
+        text.sections[0].value = status.clone();
+    }
+}
+
+fn open_custom_levels(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::U) {
+        game_state.set(GameState::CustomLevels);
+    }
+}
+
+impl Plugin for CustomLevelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CustomLevelsState::default())
+            .add_systems(OnEnter(GameState::CustomLevels), show_custom_levels)
+            .add_systems(OnExit(GameState::CustomLevels), hide_custom_levels)
+            .add_systems(
+                Update,
+                open_custom_levels
+                    .run_if(in_state(GameState::LevelSelect))
+                    .run_if(quit_prompt_closed),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_custom_levels_input,
+                    update_custom_levels_list.after(handle_custom_levels_input),
+                )
+                    .run_if(in_state(GameState::CustomLevels))
+                    .run_if(quit_prompt_closed),
+            );
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    challenge_plugin::{days_since_epoch, iso_date, text_seed},
+    generator::{generate_level, Theme},
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{LevelCompletedEvent, LevelPack, NextLevelEvent},
+    profile::Profile,
+    GameState,
+};
+
+const DAILY_FILE: &str = "daily.ron";
+const DAILY_PACK_PREFIX: &str = "Daily ";
+
+pub struct DailyPlugin;
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct DailyScore {
+    pub moves: usize,
+    pub pushes: usize,
+}
+
+// Best result for every day played, keyed by date, e.g. "2026-10-17".
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct DailyArchive(pub BTreeMap<String, DailyScore>);
+
+#[derive(Component)]
+struct DailyPanel;
+
+// Each day also picks its own theme, so the week doesn't feel samey.
+pub fn daily_level(days_since_epoch: i64) -> Vec<Vec<i32>> {
+    let date = iso_date(days_since_epoch);
+    let theme = Theme::ALL[days_since_epoch.rem_euclid(Theme::ALL.len() as i64) as usize];
+    generate_level(text_seed(&date), theme)
+}
+
+// Something short to paste to friends, spoilers left out.
+pub fn share_text(date: &str, score: DailyScore) -> String {
+    let blocks = (score.pushes * 10)
+        .checked_div(score.moves)
+        .unwrap_or(0)
+        .min(10);
+    format!(
+        "Sokoban Daily {}: {} moves, {} pushes [{}{}]",
+        date,
+        score.moves,
+        score.pushes,
+        "#".repeat(blocks),
+        ".".repeat(10 - blocks)
+    )
+}
+
+fn load_daily_archive(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(profile.load::<DailyArchive>(DAILY_FILE));
+}
+
+fn start_daily_puzzle(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::P) {
+        return;
+    }
+    let today = days_since_epoch();
+    commands.insert_resource(LevelPack {
+        name: format!("{}{}", DAILY_PACK_PREFIX, iso_date(today)),
+        levels: vec![daily_level(today)],
+        metadata: Vec::new(),
+        return_state: GameState::LevelSelect,
+    });
+    next_level_writer.send(NextLevelEvent(1));
+    game_state.set(GameState::Playing);
+}
+
+// Fewer moves wins, pushes break ties.
+fn track_daily_puzzle(
+    profile: Res<Profile>,
+    level_pack: Res<LevelPack>,
+    mut archive: ResMut<DailyArchive>,
+    mut level_completed_reader: EventReader<LevelCompletedEvent>,
+) {
+    for level_completed in level_completed_reader.read() {
+        let Some(date) = level_pack.name.strip_prefix(DAILY_PACK_PREFIX) else {
+            continue;
+        };
+        let score = DailyScore {
+            moves: level_completed.moves.len(),
+            pushes: level_completed
+                .moves
+                .chars()
+                .filter(char::is_ascii_uppercase)
+                .count(),
+        };
+        info!("{}", share_text(date, score));
+        let is_best = archive
+            .0
+            .get(date)
+            .is_none_or(|best| (score.moves, score.pushes) < (best.moves, best.pushes));
+        if is_best {
+            archive.0.insert(date.to_string(), score);
+            profile.save(DAILY_FILE, &*archive);
+        }
+    }
+}
+
+fn show_daily_panel(mut commands: Commands, archive: Res<DailyArchive>) {
+    let date = iso_date(days_since_epoch());
+    let (status, share) = match archive.0.get(&date) {
+        Some(score) => (
+            format!(
+                "Daily puzzle: best {} moves, {} pushes",
+                score.moves, score.pushes
+            ),
+            share_text(&date, *score),
+        ),
+        None => (
+            "Daily puzzle: not solved yet".to_string(),
+            "P: play today's puzzle".to_string(),
+        ),
+    };
+
+    commands
+        .spawn((
+            DailyPanel,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.0),
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                status,
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::YELLOW,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                share,
+                TextStyle {
+                    font_size: 14.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn hide_daily_panel(mut commands: Commands, daily_panel_query: Query<Entity, With<DailyPanel>>) {
+    for entity in daily_panel_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DailyArchive::default())
+            .add_systems(Startup, load_daily_archive)
+            .add_systems(OnEnter(GameState::LevelSelect), show_daily_panel)
+            .add_systems(OnExit(GameState::LevelSelect), hide_daily_panel)
+            .add_systems(
+                Update,
+                (
+                    start_daily_puzzle
+                        .run_if(in_state(GameState::LevelSelect))
+                        .run_if(quit_prompt_closed),
+                    track_daily_puzzle,
+                ),
+            );
+    }
+}
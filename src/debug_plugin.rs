@@ -0,0 +1,179 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::{mouse_plugin::cursor_tile, play_plugin::LevelState, MainCamera, Position, TILE_SIZE};
+
+const DEBUG_KEY: KeyCode = KeyCode::F3;
+const GRID_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.2);
+
+// For authoring levels and chasing desyncs: F3 shows the grid, the tile under the cursor, what
+// `LevelState` thinks is there and where that entity is actually drawn.
+pub struct DebugPlugin;
+
+#[derive(Resource, Default)]
+pub struct DebugOverlay {
+    enabled: bool,
+}
+
+#[derive(Component)]
+struct DebugText;
+
+pub fn debug_overlay_on(debug_overlay: Res<DebugOverlay>) -> bool {
+    debug_overlay.enabled
+}
+
+fn toggle_debug_overlay(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut debug_overlay: ResMut<DebugOverlay>,
+    mut text_query: Query<&mut Visibility, With<DebugText>>,
+) {
+    if !keyboard_input.just_pressed(DEBUG_KEY) {
+        return;
+    }
+    debug_overlay.enabled = !debug_overlay.enabled;
+    for mut visibility in &mut text_query {
+        *visibility = if debug_overlay.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn spawn_debug_text(mut commands: Commands) {
+    commands.spawn((
+        DebugText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 14.0,
+                color: Color::LIME_GREEN,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+        ZIndex::Global(40),
+        Visibility::Hidden,
+    ));
+}
+
+fn draw_grid(debug_overlay: Res<DebugOverlay>, level_state: Res<LevelState>, mut gizmos: Gizmos) {
+    if !debug_overlay.enabled {
+        return;
+    }
+    let rows = level_state.layout.len() as f32;
+    let columns = level_state
+        .layout
+        .iter()
+        .map(|row| row.len())
+        .max()
+        .unwrap_or(0) as f32;
+    for row in 0..=rows as usize {
+        let y = -(row as f32) * TILE_SIZE;
+        gizmos.line_2d(
+            Vec2::new(0.0, y),
+            Vec2::new(columns * TILE_SIZE, y),
+            GRID_COLOR,
+        );
+    }
+    for column in 0..=columns as usize {
+        let x = column as f32 * TILE_SIZE;
+        gizmos.line_2d(
+            Vec2::new(x, 0.0),
+            Vec2::new(x, -rows * TILE_SIZE),
+            GRID_COLOR,
+        );
+    }
+}
+
+// What the obstacle map and the player map hold at a tile, and where each of those entities
+// is drawn if that's somewhere else.
+fn describe_tile(
+    level_state: &LevelState,
+    position: Position,
+    transform_query: &Query<&Transform>,
+) -> Vec<String> {
+    let drawn_at = |entity: Entity| match transform_query.get(entity) {
+        Ok(transform) => {
+            let drawn = Position::from_translation(transform.translation);
+            if drawn == position {
+                String::new()
+            } else {
+                format!(", drawn at ({}, {})", drawn.x, drawn.y)
+            }
+        }
+        Err(_) => ", not spawned".to_string(),
+    };
+    let mut lines = Vec::new();
+    if level_state.walls.contains(&position) {
+        lines.push("Wall".to_string());
+    }
+    if let Some((entity, obstacle)) = level_state.obstacles.get(&position) {
+        lines.push(format!("{:?} {:?}{}", obstacle, entity, drawn_at(*entity)));
+    }
+    for (entity, player) in &level_state.players {
+        if *player == position {
+            lines.push(format!("Player {:?}{}", entity, drawn_at(*entity)));
+        }
+    }
+    if lines.is_empty() {
+        lines.push("Nothing".to_string());
+    }
+    lines
+}
+
+fn update_debug_text(
+    debug_overlay: Res<DebugOverlay>,
+    diagnostics: Res<DiagnosticsStore>,
+    level_state: Res<LevelState>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    transform_query: Query<&Transform>,
+    mut text_query: Query<&mut Text, With<DebugText>>,
+) {
+    if !debug_overlay.enabled {
+        return;
+    }
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or(0.0);
+    let mut lines = vec![format!("FPS: {:.0}", fps)];
+    match cursor_tile(&window_query, &camera_query) {
+        Some(position) => {
+            lines.push(format!("Cursor: ({}, {})", position.x, position.y));
+            lines.extend(describe_tile(&level_state, position, &transform_query));
+        }
+        None => lines.push("Cursor: off the window".to_string()),
+    }
+    for mut text in &mut text_query {
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+        app.insert_resource(DebugOverlay::default())
+            .add_systems(Startup, spawn_debug_text)
+            .add_systems(
+                Update,
+                (
+                    toggle_debug_overlay,
+                    draw_grid.after(toggle_debug_overlay),
+                    update_debug_text.after(toggle_debug_overlay),
+                ),
+            );
+    }
+}
@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+
+use crate::{
+    play_plugin::{InputBuffer, LevelLoadedEvent, LevelState, NextLevelEvent, Player},
+    sokoban_core::step_direction,
+    solver::{is_solver_level, solve},
+    GameState,
+};
+
+const DEMO_KEY: KeyCode = KeyCode::W;
+// Restarts of the same level before the demo is offered.
+const DEMO_OFFER_RESTARTS: usize = 3;
+const MAX_DEMO_STATES: usize = 2_000_000;
+
+// Plays the solver's solution from the start of the level with the usual animations. A level
+// won while watching it isn't reported as completed, so it counts for nothing.
+pub struct DemoPlugin;
+
+#[derive(Default)]
+enum DemoState {
+    #[default]
+    Off,
+    NoSolution,
+    // Waiting for the level to restart before playing from the top.
+    Starting(String),
+    Playing(String),
+    // Played out or taken over, either way this try doesn't count.
+    Watched,
+}
+
+#[derive(Resource, Default)]
+pub struct Demo {
+    state: DemoState,
+    // Restarts of the level being played, and which level that is.
+    restarts: usize,
+    level_hash: Option<u32>,
+}
+
+impl Demo {
+    // Until the level is restarted, once a demo has been started on it.
+    pub fn is_watched(&self) -> bool {
+        matches!(
+            self.state,
+            DemoState::Starting(_) | DemoState::Playing(_) | DemoState::Watched
+        )
+    }
+}
+
+#[derive(Component)]
+struct DemoText;
+
+fn start_demo(
+    keyboard_input: Res<Input<KeyCode>>,
+    level_state: Res<LevelState>,
+    mut demo: ResMut<Demo>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+) {
+    if !keyboard_input.just_pressed(DEMO_KEY)
+        || demo.is_watched()
+        || !is_solver_level(&level_state.layout)
+    {
+        return;
+    }
+    demo.state = match solve(&level_state.layout, MAX_DEMO_STATES) {
+        (Some(solution), _) => {
+            next_level_writer.send(NextLevelEvent(level_state.current_level));
+            DemoState::Starting(solution)
+        }
+        (None, _) => DemoState::NoSolution,
+    };
+}
+
+// Loading the same level again counts as a restart, and clears a finished demo.
+fn track_restarts(mut demo: ResMut<Demo>, mut level_loaded_reader: EventReader<LevelLoadedEvent>) {
+    for level_loaded in level_loaded_reader.read() {
+        if demo.level_hash == Some(level_loaded.hash) {
+            demo.restarts += 1;
+        } else {
+            demo.restarts = 0;
+            demo.level_hash = Some(level_loaded.hash);
+        }
+        demo.state = match std::mem::take(&mut demo.state) {
+            DemoState::Starting(solution) => DemoState::Playing(solution),
+            _ => DemoState::Off,
+        };
+    }
+}
+
+// Runs after everything has moved for the frame, so a landed move is already on the board.
+// Anything that stops the walk, like an undo, has it picked up again from wherever the
+// player is on the solution.
+fn play_demo(
+    level_state: Res<LevelState>,
+    mut demo: ResMut<Demo>,
+    mut input_buffer: ResMut<InputBuffer>,
+    player_query: Query<&Player>,
+) {
+    let DemoState::Playing(solution) = &demo.state else {
+        return;
+    };
+    if input_buffer.is_walking() || player_query.iter().any(|player| player.is_moving) {
+        return;
+    }
+    match solution.strip_prefix(level_state.moves.as_str()) {
+        Some(rest) if !rest.is_empty() => {
+            input_buffer.walk(rest.chars().filter_map(step_direction));
+        }
+        // Played to the end, or the player stepped off it.
+        _ => demo.state = DemoState::Watched,
+    }
+}
+
+fn show_demo_text(mut commands: Commands) {
+    commands.spawn((
+        DemoText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::GOLD,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(70.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+fn hide_demo_text(mut commands: Commands, text_query: Query<Entity, With<DemoText>>) {
+    for entity in text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn update_demo_text(
+    demo: Res<Demo>,
+    level_state: Res<LevelState>,
+    mut text_query: Query<&mut Text, With<DemoText>>,
+) {
+    let message = match demo.state {
+        DemoState::Off
+            if demo.restarts >= DEMO_OFFER_RESTARTS && is_solver_level(&level_state.layout) =>
+        {
+            "Stuck? W: watch the solution"
+        }
+        DemoState::Off => "",
+        DemoState::NoSolution => "No solution found to show",
+        DemoState::Starting(_) | DemoState::Playing(_) => {
+            "Demo: watching the solution, it won't count as completed"
+        }
+        DemoState::Watched => "Demo over, this try won't count. R: try it yourself",
+    };
+    for mut text in &mut text_query {
+        if text.sections[0].value != message {
+            text.sections[0].value = message.to_string();
+        }
+    }
+}
+
+impl Plugin for DemoPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Demo::default())
+            .add_systems(OnEnter(GameState::Playing), show_demo_text)
+            .add_systems(OnExit(GameState::Playing), hide_demo_text)
+            .add_systems(
+                Update,
+                (
+                    track_restarts,
+                    (start_demo, update_demo_text).run_if(in_state(GameState::Playing)),
+                ),
+            )
+            .add_systems(PostUpdate, play_demo.run_if(in_state(GameState::Playing)));
+    }
+}
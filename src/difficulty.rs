@@ -0,0 +1,58 @@
+use crate::{
+    sokoban_core::{cell_from_code, Tile},
+    solver::solve,
+};
+
+const MAX_SOLVER_STATES: usize = 200_000;
+// What `Difficulty::label` can call a solvable level, easiest first. Authors rate their
+// levels with the same words.
+pub const DIFFICULTY_LABELS: [&str; 4] = ["Easy", "Medium", "Hard", "Expert"];
+
+#[derive(Clone, Debug)]
+pub struct Difficulty {
+    pub solution_length: Option<usize>,
+    pub pushes: Option<usize>,
+    pub blocks: usize,
+    pub branching_factor: f32,
+}
+
+impl Difficulty {
+    pub fn score(&self) -> f32 {
+        let Some(solution_length) = self.solution_length else {
+            return f32::MAX;
+        };
+        solution_length as f32
+            + self.pushes.unwrap_or(0) as f32 * 2.0
+            + self.blocks as f32 * 3.0
+            + self.branching_factor * 10.0
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self.score() {
+            score if score < 40.0 => "Easy",
+            score if score < 80.0 => "Medium",
+            score if score < 150.0 => "Hard",
+            score if score < f32::MAX => "Expert",
+            _ => "Unknown",
+        }
+    }
+}
+
+pub fn estimate_difficulty(level_layout: &[Vec<i32>]) -> Difficulty {
+    let (solution, stats) = solve(level_layout, MAX_SOLVER_STATES);
+    let blocks = level_layout
+        .iter()
+        .flatten()
+        .flat_map(|tile| cell_from_code(*tile))
+        .filter(|tile| *tile == Tile::Block(0))
+        .count();
+
+    Difficulty {
+        solution_length: solution.as_ref().map(|moves| moves.len()),
+        pushes: solution
+            .as_ref()
+            .map(|moves| moves.chars().filter(|c| c.is_ascii_uppercase()).count()),
+        blocks,
+        branching_factor: stats.branching_factor(),
+    }
+}
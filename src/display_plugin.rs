@@ -0,0 +1,101 @@
+use bevy::{
+    input::InputSystem,
+    prelude::*,
+    window::{PrimaryWindow, WindowMode, WindowResized},
+};
+
+use crate::{play_plugin::LevelLoadedEvent, MainCamera, TILE_SIZE};
+
+// Room left around the level so it doesn't touch the window edges.
+const FIT_MARGIN: f32 = 0.9;
+
+// Keeps the level filling the window as it's resized or made fullscreen.
+pub struct DisplayPlugin;
+
+// The size of the level being shown, in tiles.
+#[derive(Resource, Default)]
+struct ShownLevelSize {
+    width: i32,
+    height: i32,
+}
+
+fn track_level_size(
+    mut level_loaded_reader: EventReader<LevelLoadedEvent>,
+    mut shown_level_size: ResMut<ShownLevelSize>,
+) {
+    if let Some(level_loaded) = level_loaded_reader.read().last() {
+        shown_level_size.width = level_loaded.width;
+        shown_level_size.height = level_loaded.height;
+    }
+}
+
+// Zooms in whole steps while the level fits so the pixel art stays crisp, and out as far
+// as it takes for large levels.
+fn fit_zoom(window_size: Vec2, level_size: Vec2) -> f32 {
+    let zoom = (window_size / level_size).min_element() * FIT_MARGIN;
+    if zoom >= 1.0 {
+        zoom.floor()
+    } else {
+        zoom
+    }
+}
+
+fn fit_camera(
+    shown_level_size: Res<ShownLevelSize>,
+    mut resized_reader: EventReader<WindowResized>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    let resized = resized_reader.read().count() > 0;
+    if !resized && !shown_level_size.is_changed() {
+        return;
+    }
+    if shown_level_size.width == 0 || shown_level_size.height == 0 {
+        return;
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let Ok(mut camera_transform) = camera_query.get_single_mut() else {
+        return;
+    };
+    let level_size = Vec2::new(
+        shown_level_size.width as f32,
+        shown_level_size.height as f32,
+    ) * TILE_SIZE;
+    let zoom = fit_zoom(Vec2::new(window.width(), window.height()), level_size);
+    camera_transform.scale = Vec3::new(1.0 / zoom, 1.0 / zoom, 1.0);
+}
+
+// F11 or Alt+Enter switches between a window and fullscreen. Runs straight after input is
+// read so the Enter doesn't also reach the menus.
+fn toggle_fullscreen(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let alt_held = keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    let alt_enter = alt_held && keyboard_input.just_pressed(KeyCode::Return);
+    if alt_enter {
+        keyboard_input.reset(KeyCode::Return);
+    } else if !keyboard_input.just_pressed(KeyCode::F11) {
+        return;
+    }
+    let Ok(mut window) = window_query.get_single_mut() else {
+        return;
+    };
+    window.mode = match window.mode {
+        WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+        _ => WindowMode::Windowed,
+    };
+}
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ShownLevelSize::default())
+            .add_systems(
+                Update,
+                (track_level_size, fit_camera.after(track_level_size)),
+            )
+            .add_systems(PreUpdate, toggle_fullscreen.after(InputSystem));
+    }
+}
@@ -0,0 +1,435 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use bevy::{
+    prelude::*,
+    tasks::{block_on, IoTaskPool, Task},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bundle::check_level,
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{LevelPack, NextLevelEvent},
+    profile::Profile,
+    GameState,
+};
+
+const SOURCES_FILE: &str = "community_packs.ron";
+const MAX_ATTEMPTS: u32 = 3;
+// Doubled after each failed attempt.
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+const PROGRESS_BAR_WIDTH: f32 = 240.0;
+
+pub struct DownloadPlugin;
+
+// Where community packs are downloaded from, edited by hand in the profile.
+#[derive(Serialize, Deserialize, Default)]
+struct CommunitySources {
+    urls: Vec<String>,
+}
+
+// The file a community pack URL points at.
+#[derive(Deserialize)]
+struct CommunityPack {
+    name: String,
+    levels: Vec<Vec<Vec<i32>>>,
+}
+
+#[derive(Debug)]
+pub enum DownloadError {
+    Network(String),
+    Http(u16),
+    Parse(String),
+    // A level in the pack that couldn't be played.
+    Invalid(String),
+    Cancelled,
+    #[allow(dead_code)] // Only built for the web.
+    Unsupported,
+}
+
+impl DownloadError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            DownloadError::Network(_) => true,
+            DownloadError::Http(status) => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloadError::Network(error) => write!(f, "Couldn't reach the server ({})", error),
+            DownloadError::Http(404) => write!(f, "The pack wasn't found on the server"),
+            DownloadError::Http(status) => write!(f, "The server answered with error {}", status),
+            DownloadError::Parse(error) => write!(f, "The pack couldn't be read ({})", error),
+            DownloadError::Invalid(problem) => write!(f, "The pack can't be played ({})", problem),
+            DownloadError::Cancelled => write!(f, "Download cancelled"),
+            DownloadError::Unsupported => write!(f, "Downloads aren't supported here"),
+        }
+    }
+}
+
+// Shared with the download task, which updates it as bytes arrive.
+#[derive(Default)]
+struct DownloadProgress {
+    received: AtomicU64,
+    // Zero when the server didn't say how big the download is.
+    total: AtomicU64,
+    attempt: AtomicU32,
+    cancelled: AtomicBool,
+}
+
+impl DownloadProgress {
+    fn fraction(&self) -> Option<f32> {
+        let total = self.total.load(Ordering::Relaxed);
+        (total > 0).then(|| self.received.load(Ordering::Relaxed) as f32 / total as f32)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+struct ActiveDownload {
+    url: String,
+    progress: Arc<DownloadProgress>,
+    task: Task<Result<CommunityPack, DownloadError>>,
+}
+
+#[derive(Resource, Default)]
+struct Downloads {
+    active: Option<ActiveDownload>,
+    // The next source to download from, cycling through the list.
+    next_source: usize,
+    // The last finished download's outcome, shown until the next one starts.
+    message: Option<(String, Color)>,
+}
+
+#[derive(Event)]
+pub struct DownloadPackEvent(pub String);
+
+#[derive(Component)]
+struct DownloadPanel;
+
+#[derive(Component)]
+struct DownloadText;
+
+#[derive(Component)]
+struct DownloadBar;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn fetch_once(url: &str, progress: &DownloadProgress) -> Result<Vec<u8>, DownloadError> {
+    use std::io::Read;
+
+    let response = ureq::get(url)
+        .timeout(Duration::from_secs(30))
+        .call()
+        .map_err(|error| match error {
+            ureq::Error::Status(status, _) => DownloadError::Http(status),
+            ureq::Error::Transport(transport) => DownloadError::Network(transport.to_string()),
+        })?;
+    let total = response
+        .header("Content-Length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    progress.total.store(total, Ordering::Relaxed);
+    progress.received.store(0, Ordering::Relaxed);
+
+    let mut reader = response.into_reader();
+    let mut bytes = Vec::new();
+    let mut chunk = [0; 8192];
+    loop {
+        if progress.is_cancelled() {
+            return Err(DownloadError::Cancelled);
+        }
+        let read = reader
+            .read(&mut chunk)
+            .map_err(|error| DownloadError::Network(error.to_string()))?;
+        if read == 0 {
+            return Ok(bytes);
+        }
+        bytes.extend_from_slice(&chunk[..read]);
+        progress
+            .received
+            .store(bytes.len() as u64, Ordering::Relaxed);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn fetch_once(_url: &str, _progress: &DownloadProgress) -> Result<Vec<u8>, DownloadError> {
+    Err(DownloadError::Unsupported)
+}
+
+// Runs on the IO task pool, blocking one of its threads rather than the frame.
+fn fetch(url: &str, progress: &DownloadProgress) -> Result<Vec<u8>, DownloadError> {
+    let mut backoff = RETRY_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        progress.attempt.store(attempt, Ordering::Relaxed);
+        match fetch_once(url, progress) {
+            Err(error) if error.is_retryable() && attempt < MAX_ATTEMPTS => {
+                warn!("Download of {} failed, retrying: {}", url, error);
+            }
+            result => return result,
+        }
+        // Sleep in small steps so cancelling doesn't wait out the backoff.
+        let step = Duration::from_millis(100);
+        let mut waited = Duration::ZERO;
+        while waited < backoff {
+            if progress.is_cancelled() {
+                return Err(DownloadError::Cancelled);
+            }
+            std::thread::sleep(step);
+            waited += step;
+        }
+        backoff *= 2;
+        attempt += 1;
+    }
+}
+
+// Levels from the internet get the same checks as bundled ones, one bad level rejects the
+// whole pack.
+fn check_pack(pack: &CommunityPack) -> Result<(), DownloadError> {
+    for (index, level_layout) in pack.levels.iter().enumerate() {
+        check_level(level_layout).map_err(|problem| {
+            DownloadError::Invalid(format!("level {} {}", index + 1, problem))
+        })?;
+    }
+    Ok(())
+}
+
+fn start_downloads(
+    mut downloads: ResMut<Downloads>,
+    mut download_pack_reader: EventReader<DownloadPackEvent>,
+) {
+    for DownloadPackEvent(url) in download_pack_reader.read() {
+        if downloads.active.is_some() {
+            continue;
+        }
+        let progress = Arc::new(DownloadProgress::default());
+        let task_progress = progress.clone();
+        let task_url = url.clone();
+        let task = IoTaskPool::get().spawn(async move {
+            let bytes = fetch(&task_url, &task_progress)?;
+            let pack: CommunityPack = ron::de::from_bytes(&bytes)
+                .map_err(|error| DownloadError::Parse(error.to_string()))?;
+            check_pack(&pack)?;
+            Ok(pack)
+        });
+        downloads.active = Some(ActiveDownload {
+            url: url.clone(),
+            progress,
+            task,
+        });
+        downloads.message = None;
+    }
+}
+
+fn finish_downloads(
+    mut commands: Commands,
+    mut downloads: ResMut<Downloads>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if !downloads
+        .active
+        .as_ref()
+        .is_some_and(|active| active.task.is_finished())
+    {
+        return;
+    }
+    let Some(ActiveDownload { url, task, .. }) = downloads.active.take() else {
+        return;
+    };
+    let result = block_on(task);
+
+    match result {
+        Ok(pack) if pack.levels.is_empty() => {
+            downloads.message = Some((format!("{} has no levels", pack.name), Color::RED));
+        }
+        Ok(pack) => {
+            info!("Downloaded {} from {}", pack.name, url);
+            downloads.message = Some((format!("Downloaded {}", pack.name), Color::GREEN));
+            commands.insert_resource(LevelPack {
+                name: pack.name,
+                levels: pack.levels,
+                metadata: Vec::new(),
+                return_state: GameState::LevelSelect,
+            });
+            next_level_writer.send(NextLevelEvent(1));
+            game_state.set(GameState::Playing);
+        }
+        Err(error) => {
+            warn!("Download of {} failed: {}", url, error);
+            downloads.message = Some((error.to_string(), Color::RED));
+        }
+    }
+}
+
+fn handle_download_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    profile: Res<Profile>,
+    mut downloads: ResMut<Downloads>,
+    mut download_pack_writer: EventWriter<DownloadPackEvent>,
+) {
+    if keyboard_input.just_pressed(KeyCode::X) {
+        if let Some(active) = &downloads.active {
+            active.progress.cancelled.store(true, Ordering::Relaxed);
+        }
+        return;
+    }
+    if !keyboard_input.just_pressed(KeyCode::D) || downloads.active.is_some() {
+        return;
+    }
+
+    let sources: CommunitySources = profile.load(SOURCES_FILE);
+    if sources.urls.is_empty() {
+        // Leave an empty list behind for the player to fill in.
+        profile.save(SOURCES_FILE, &sources);
+        downloads.message = Some((
+            format!("Add community pack URLs to {}", SOURCES_FILE),
+            Color::RED,
+        ));
+        return;
+    }
+    let url = sources.urls[downloads.next_source % sources.urls.len()].clone();
+    downloads.next_source += 1;
+    download_pack_writer.send(DownloadPackEvent(url));
+}
+
+fn spawn_download_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            DownloadPanel,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(10.0),
+                    left: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                DownloadText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                ),
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(PROGRESS_BAR_WIDTH),
+                        height: Val::Px(8.0),
+                        ..default()
+                    },
+                    background_color: Color::DARK_GRAY.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        DownloadBar,
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: Color::CYAN.into(),
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+fn update_download_panel(
+    downloads: Res<Downloads>,
+    game_state: Res<State<GameState>>,
+    mut panel_query: Query<&mut Visibility, With<DownloadPanel>>,
+    mut text_query: Query<&mut Text, With<DownloadText>>,
+    mut bar_query: Query<&mut Style, With<DownloadBar>>,
+) {
+    // Finished downloads are only reported on the level select screen.
+    let shown = downloads.active.is_some()
+        || (downloads.message.is_some() && *game_state.get() == GameState::LevelSelect);
+    for mut visibility in &mut panel_query {
+        *visibility = if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    let (message, color, fraction) = match (&downloads.active, &downloads.message) {
+        (Some(active), _) => {
+            let progress = &active.progress;
+            let attempt = progress.attempt.load(Ordering::Relaxed);
+            let retry = if attempt > 1 {
+                format!(" (attempt {} of {})", attempt, MAX_ATTEMPTS)
+            } else {
+                String::new()
+            };
+            let percent = match progress.fraction() {
+                Some(fraction) => format!("{:.0}%", fraction * 100.0),
+                None => format!("{} KB", progress.received.load(Ordering::Relaxed) / 1024),
+            };
+            (
+                format!(
+                    "Downloading {}{}  {}  X: cancel",
+                    active.url, retry, percent
+                ),
+                Color::WHITE,
+                progress.fraction().unwrap_or(0.0),
+            )
+        }
+        (None, Some((message, color))) => (message.clone(), *color, 0.0),
+        (None, None) => (String::new(), Color::WHITE, 0.0),
+    };
+    for mut text in &mut text_query {
+        text.sections[0].value = message.clone();
+        text.sections[0].style.color = color;
+    }
+    for mut style in &mut bar_query {
+        style.width = Val::Percent(fraction.clamp(0.0, 1.0) * 100.0);
+    }
+}
+
+impl Plugin for DownloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DownloadPackEvent>()
+            .insert_resource(Downloads::default())
+            .add_systems(Startup, spawn_download_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_download_input
+                        .run_if(in_state(GameState::LevelSelect))
+                        .run_if(quit_prompt_closed),
+                    start_downloads.after(handle_download_input),
+                    // Finished packs wait for the player to be back on level select.
+                    finish_downloads
+                        .after(start_downloads)
+                        .run_if(in_state(GameState::LevelSelect)),
+                    update_download_panel.after(finish_downloads),
+                ),
+            );
+    }
+}
@@ -1,69 +1,600 @@
-use bevy::{prelude::*, sprite::Anchor, utils::HashMap};
+use bevy::{
+    prelude::*,
+    sprite::Anchor,
+    utils::{HashMap, HashSet},
+};
 
-use crate::{tiles::spawn_floor, GameState, Position, TILE_SIZE};
+use crate::{
+    level_data::{spawn_tile, Decoration, LevelData},
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{LevelState, MoveHistory},
+    save_dialog_plugin::save_dialog_closed,
+    sokoban_core::Tile,
+    tiles::{spawn_colored_tile, spawn_decoration, spawn_floor, ONE_WAY_DIRECTIONS},
+    GameState, LevelEntity, MainCamera, Position, TILE_SIZE,
+};
 
 pub struct EditPlugin;
 
+const EDITOR_PICKUP_ENERGY: i32 = 10;
+// Blocks, goals, keys and locked doors can be plain or one of this many colors.
+const EDITOR_BLOCK_COLORS: u8 = 4;
+// Teleporters and portals both have this many pair numbers, past it their codes run into
+// other tiles.
+const EDITOR_PAIR_NUMBERS: i32 = 64;
+
 #[derive(Resource, Default)]
-struct EditingState {
+pub struct EditingState {
     floors: HashMap<Position, Entity>,
     walls: HashMap<Position, Entity>,
-    blocks: HashMap<Position, Entity>,
-    goals: HashMap<Position, Entity>,
+    // Blocks and goals with their color, 0 for plain ones.
+    blocks: HashMap<Position, (Entity, u8)>,
+    goals: HashMap<Position, (Entity, u8)>,
+    heavy_blocks: HashMap<Position, Entity>,
+    // The color new blocks, goals, keys and locked doors are placed with.
+    color: u8,
+    switches: HashMap<Position, Entity>,
+    doors: HashMap<Position, Entity>,
+    // Teleporters are paired up in the order they're placed.
+    teleporters: HashMap<Position, (Entity, i32)>,
+    ice: HashMap<Position, Entity>,
+    // The arrow's index in `ONE_WAY_DIRECTIONS`.
+    one_way: HashMap<Position, (Entity, usize)>,
+    pickups: HashMap<Position, Entity>,
+    pits: HashMap<Position, Entity>,
+    // The way each enemy sets off, as an index in `ONE_WAY_DIRECTIONS`.
+    enemies: HashMap<Position, (Entity, usize)>,
+    // Keys and locked doors with their color, they pair up by it.
+    keys: HashMap<Position, (Entity, u8)>,
+    locked_doors: HashMap<Position, (Entity, u8)>,
+    fragile: HashMap<Position, Entity>,
+    // Portals are paired up in the order they're placed, like teleporters.
+    portals: HashMap<Position, (Entity, i32)>,
+    // Drawn on top of whatever else is on the floor, they don't take up the tile.
+    decorations: HashMap<Position, (Entity, Decoration)>,
     player: Option<(Position, Entity)>,
+    mirror: Option<(Position, Entity)>,
+    exit: Option<(Position, Entity)>,
+    // The corner picked with D, the rectangle is filled when D is pressed again.
+    rectangle_anchor: Option<(Position, Entity)>,
+    // The corner picked with A, the region is copied when A is pressed again.
+    selection_anchor: Option<(Position, Entity)>,
+    symmetry: Symmetry,
+    // The cell placements are mirrored about, picked with Shift+U.
+    symmetry_axis: Position,
+    // Set by every edit and cleared when the level is saved.
+    dirty: bool,
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+enum Symmetry {
+    #[default]
+    Off,
+    Horizontal,
+    Vertical,
+    FourWay,
+}
+
+impl Symmetry {
+    fn next(self) -> Self {
+        match self {
+            Symmetry::Off => Symmetry::Horizontal,
+            Symmetry::Horizontal => Symmetry::Vertical,
+            Symmetry::Vertical => Symmetry::FourWay,
+            Symmetry::FourWay => Symmetry::Off,
+        }
+    }
 }
 
 impl EditingState {
-    fn can_place(&self, position: &Position) -> bool {
+    pub fn is_empty(&self) -> bool {
+        self.floors.is_empty()
+    }
+
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.dirty = false;
+    }
+
+    fn can_place(&self, position: &Position, tile: Tile) -> bool {
+        // The player or a plain block can go on a plain goal, like in standard levels.
+        let onto_goal = matches!(tile, Tile::Player | Tile::Block(0))
+            && self
+                .goals
+                .get(position)
+                .is_some_and(|(_, color)| *color == 0);
         self.floors.contains_key(position)
             && !self.blocks.contains_key(position)
-            && !self.goals.contains_key(position)
+            && !self.heavy_blocks.contains_key(position)
+            && (onto_goal || !self.goals.contains_key(position))
+            && !self.switches.contains_key(position)
+            && !self.doors.contains_key(position)
+            && !self.teleporters.contains_key(position)
+            && !self.ice.contains_key(position)
+            && !self.one_way.contains_key(position)
+            && !self.pickups.contains_key(position)
+            && !self.pits.contains_key(position)
+            && !self.enemies.contains_key(position)
+            && !self.keys.contains_key(position)
+            && !self.locked_doors.contains_key(position)
+            && !self.fragile.contains_key(position)
+            && !self.portals.contains_key(position)
             && (self.player.is_none() || &self.player.unwrap().0 != position)
+            && (self.mirror.is_none() || &self.mirror.unwrap().0 != position)
+            && (self.exit.is_none() || &self.exit.unwrap().0 != position)
     }
 
     fn remove_object(&mut self, position: &Position) -> Option<Entity> {
-        if self.blocks.contains_key(position) {
-            return self.blocks.remove(position);
-        } else if self.goals.contains_key(position) {
-            return self.goals.remove(position);
+        if let Some((block_id, _)) = self.blocks.remove(position) {
+            Some(block_id)
+        } else if self.heavy_blocks.contains_key(position) {
+            self.heavy_blocks.remove(position)
+        } else if let Some((goal_id, _)) = self.goals.remove(position) {
+            Some(goal_id)
+        } else if self.switches.contains_key(position) {
+            self.switches.remove(position)
+        } else if self.doors.contains_key(position) {
+            self.doors.remove(position)
+        } else if let Some((teleporter_id, _)) = self.teleporters.remove(position) {
+            Some(teleporter_id)
+        } else if self.ice.contains_key(position) {
+            self.ice.remove(position)
+        } else if let Some((one_way_id, _)) = self.one_way.remove(position) {
+            Some(one_way_id)
+        } else if self.pickups.contains_key(position) {
+            self.pickups.remove(position)
+        } else if self.pits.contains_key(position) {
+            self.pits.remove(position)
+        } else if let Some((enemy_id, _)) = self.enemies.remove(position) {
+            Some(enemy_id)
+        } else if let Some((key_id, _)) = self.keys.remove(position) {
+            Some(key_id)
+        } else if let Some((locked_door_id, _)) = self.locked_doors.remove(position) {
+            Some(locked_door_id)
+        } else if self.fragile.contains_key(position) {
+            self.fragile.remove(position)
+        } else if let Some((portal_id, _)) = self.portals.remove(position) {
+            Some(portal_id)
         } else if self.player.is_some() && self.player.unwrap().0 == *position {
             let player_id = self.player.unwrap().1;
             self.player = None;
-            return Some(player_id);
+            Some(player_id)
+        } else if self.mirror.is_some() && self.mirror.unwrap().0 == *position {
+            let mirror_id = self.mirror.unwrap().1;
+            self.mirror = None;
+            Some(mirror_id)
+        } else if self.exit.is_some() && self.exit.unwrap().0 == *position {
+            let exit_id = self.exit.unwrap().1;
+            self.exit = None;
+            Some(exit_id)
+        } else if let Some((decoration_id, _)) = self.decorations.remove(position) {
+            Some(decoration_id)
         } else {
-            return None;
+            None
+        }
+    }
+
+    fn next_teleporter_pair(&self) -> Option<i32> {
+        (0..EDITOR_PAIR_NUMBERS).find(|pair| {
+            self.teleporters
+                .values()
+                .filter(|(_, teleporter_pair)| teleporter_pair == pair)
+                .count()
+                < 2
+        })
+    }
+
+    fn next_portal_pair(&self) -> Option<i32> {
+        (0..EDITOR_PAIR_NUMBERS).find(|pair| {
+            self.portals
+                .values()
+                .filter(|(_, portal_pair)| portal_pair == pair)
+                .count()
+                < 2
+        })
+    }
+
+    // Moves everything by the offset, returning the entities that need their transforms moved.
+    fn translate(&mut self, x: i32, y: i32) -> Vec<Entity> {
+        self.dirty = true;
+        fn shift<T>(map: &mut HashMap<Position, T>, x: i32, y: i32) {
+            *map = map
+                .drain()
+                .map(|(position, value)| (position.add(x, y), value))
+                .collect();
+        }
+        shift(&mut self.floors, x, y);
+        shift(&mut self.walls, x, y);
+        shift(&mut self.blocks, x, y);
+        shift(&mut self.goals, x, y);
+        shift(&mut self.heavy_blocks, x, y);
+        shift(&mut self.switches, x, y);
+        shift(&mut self.doors, x, y);
+        shift(&mut self.teleporters, x, y);
+        shift(&mut self.ice, x, y);
+        shift(&mut self.one_way, x, y);
+        shift(&mut self.pickups, x, y);
+        shift(&mut self.pits, x, y);
+        shift(&mut self.enemies, x, y);
+        shift(&mut self.keys, x, y);
+        shift(&mut self.locked_doors, x, y);
+        shift(&mut self.fragile, x, y);
+        shift(&mut self.portals, x, y);
+        shift(&mut self.decorations, x, y);
+        for (position, _) in [
+            &mut self.player,
+            &mut self.mirror,
+            &mut self.exit,
+            &mut self.rectangle_anchor,
+            &mut self.selection_anchor,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *position = position.add(x, y);
+        }
+        // Mirroring stays about the same cells of the level.
+        self.symmetry_axis = self.symmetry_axis.add(x, y);
+
+        self.floors
+            .values()
+            .chain(self.walls.values())
+            .chain(self.blocks.values().map(|(entity, _)| entity))
+            .chain(self.goals.values().map(|(entity, _)| entity))
+            .chain(self.heavy_blocks.values())
+            .chain(self.switches.values())
+            .chain(self.doors.values())
+            .chain(self.teleporters.values().map(|(entity, _)| entity))
+            .chain(self.ice.values())
+            .chain(self.one_way.values().map(|(entity, _)| entity))
+            .chain(self.pickups.values())
+            .chain(self.pits.values())
+            .chain(self.enemies.values().map(|(entity, _)| entity))
+            .chain(self.keys.values().map(|(entity, _)| entity))
+            .chain(self.locked_doors.values().map(|(entity, _)| entity))
+            .chain(self.fragile.values())
+            .chain(self.portals.values().map(|(entity, _)| entity))
+            .chain(self.decorations.values().map(|(entity, _)| entity))
+            .chain(
+                [
+                    self.player,
+                    self.mirror,
+                    self.exit,
+                    self.rectangle_anchor,
+                    self.selection_anchor,
+                ]
+                .iter()
+                .flatten()
+                .map(|(_, entity)| entity),
+            )
+            .copied()
+            .collect()
+    }
+
+    // The floors furthest out in the given direction, e.g. the rightmost column for (1, 0).
+    fn edge(&self, x: i32, y: i32) -> Vec<Position> {
+        let reach = |position: &Position| position.x * x + position.y * y;
+        let Some(furthest) = self.floors.keys().map(reach).max() else {
+            return Vec::new();
+        };
+        self.floors
+            .keys()
+            .filter(|position| reach(position) == furthest)
+            .copied()
+            .collect()
+    }
+
+    fn tile_at(&self, position: &Position) -> Option<Tile> {
+        let single = |tile: Option<(Position, Entity)>| {
+            tile.is_some_and(|(tile_position, _)| tile_position == *position)
+        };
+        if let Some((_, color)) = self.blocks.get(position) {
+            Some(Tile::Block(*color))
+        } else if let Some((_, color)) = self.goals.get(position) {
+            Some(Tile::Goal(*color))
+        } else if self.heavy_blocks.contains_key(position) {
+            Some(Tile::HeavyBlock)
+        } else if self.switches.contains_key(position) {
+            Some(Tile::Switch)
+        } else if self.doors.contains_key(position) {
+            Some(Tile::Door)
+        } else if let Some((_, pair)) = self.teleporters.get(position) {
+            Some(Tile::Teleporter(*pair))
+        } else if self.ice.contains_key(position) {
+            Some(Tile::Ice)
+        } else if let Some((_, direction)) = self.one_way.get(position) {
+            Some(Tile::OneWay(*direction))
+        } else if self.pickups.contains_key(position) {
+            Some(Tile::Pickup(EDITOR_PICKUP_ENERGY))
+        } else if self.pits.contains_key(position) {
+            Some(Tile::Pit)
+        } else if let Some((_, direction)) = self.enemies.get(position) {
+            Some(Tile::Enemy(*direction))
+        } else if let Some((_, color)) = self.keys.get(position) {
+            Some(Tile::Key(*color))
+        } else if let Some((_, color)) = self.locked_doors.get(position) {
+            Some(Tile::LockedDoor(*color))
+        } else if self.fragile.contains_key(position) {
+            Some(Tile::Fragile)
+        } else if let Some((_, pair)) = self.portals.get(position) {
+            Some(Tile::Portal(*pair))
+        } else if single(self.player) {
+            Some(Tile::Player)
+        } else if single(self.mirror) {
+            Some(Tile::Mirror)
+        } else if single(self.exit) {
+            Some(Tile::Exit)
+        } else {
+            None
+        }
+    }
+
+    // Everything at `position`, the player or a plain block can be on a plain goal.
+    fn cell_at(&self, position: &Position) -> Vec<Tile> {
+        let on_goal = if self
+            .blocks
+            .get(position)
+            .is_some_and(|(_, color)| *color == 0)
+        {
+            Some(Tile::Block(0))
+        } else if self.player.is_some_and(|(player, _)| player == *position) {
+            Some(Tile::Player)
+        } else {
+            None
+        };
+        match (self.goals.get(position), on_goal) {
+            (Some((_, 0)), Some(tile)) => vec![Tile::Goal(0), tile],
+            _ => vec![self.tile_at(position).unwrap_or(Tile::Empty)],
+        }
+    }
+
+    // Where a placement at `position` is mirrored to, and whether it's flipped across
+    // x and y to get there.
+    fn symmetric_positions(&self, position: Position) -> Vec<(Position, bool, bool)> {
+        let flips: &[(bool, bool)] = match self.symmetry {
+            Symmetry::Off => &[],
+            Symmetry::Horizontal => &[(true, false)],
+            Symmetry::Vertical => &[(false, true)],
+            Symmetry::FourWay => &[(true, false), (false, true), (true, true)],
+        };
+        let axis = self.symmetry_axis;
+        let mut positions: Vec<(Position, bool, bool)> = Vec::new();
+        for (flip_x, flip_y) in flips {
+            let mirrored = Position {
+                x: if *flip_x {
+                    2 * axis.x - position.x
+                } else {
+                    position.x
+                },
+                y: if *flip_y {
+                    2 * axis.y - position.y
+                } else {
+                    position.y
+                },
+            };
+            // Cells on the axis are their own mirror image.
+            if mirrored != position && positions.iter().all(|(other, ..)| *other != mirrored) {
+                positions.push((mirrored, *flip_x, *flip_y));
+            }
         }
+        positions
     }
 
-    fn serialize(&self) -> Vec<Vec<i32>> {
+    // The decorations by column and row of the serialized layout.
+    pub fn serialize_decorations(&self) -> Vec<(i32, i32, Decoration)> {
+        let min_x = self.walls.keys().map(|p| p.x).min().unwrap_or(0);
+        let min_y = self.walls.keys().map(|p| p.y).min().unwrap_or(0);
+        let mut decorations: Vec<_> = self
+            .decorations
+            .iter()
+            .map(|(position, (_, decoration))| {
+                (position.x - min_x, position.y - min_y, *decoration)
+            })
+            .collect();
+        decorations.sort_by_key(|(x, y, _)| (*y, *x));
+        decorations
+    }
+
+    pub fn serialize(&self) -> Vec<Vec<i32>> {
         let wall_positions = self.walls.keys();
         let min_x = wall_positions.clone().map(|p| p.x).min().unwrap();
         let max_x = wall_positions.clone().map(|p| p.x).max().unwrap();
         let min_y = wall_positions.clone().map(|p| p.y).min().unwrap();
         let max_y = wall_positions.clone().map(|p| p.y).max().unwrap();
 
-        let mut level = vec![
-            vec![0; (1 + max_x - min_x).try_into().unwrap()];
-            (1 + max_y - min_y).try_into().unwrap()
-        ];
+        let tiles = (min_y..=max_y)
+            .map(|y| {
+                (min_x..=max_x)
+                    .map(|x| {
+                        let position = Position { x, y };
+                        if self.walls.contains_key(&position) {
+                            vec![Tile::Wall]
+                        } else {
+                            self.cell_at(&position)
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        LevelData { tiles }.to_layout()
+    }
+}
 
-        for wall_position in wall_positions {
-            level[(wall_position.y - min_y) as usize][(wall_position.x - min_x) as usize] = 8;
-        }
+fn key_tile(keyboard_input: &Input<KeyCode>, editing_state: &EditingState) -> Option<Tile> {
+    let tile = if keyboard_input.pressed(KeyCode::X) {
+        Tile::Block(editing_state.color)
+    } else if keyboard_input.pressed(KeyCode::C) {
+        Tile::Goal(editing_state.color)
+    } else if keyboard_input.pressed(KeyCode::V) {
+        Tile::Player
+    } else if keyboard_input.pressed(KeyCode::B) {
+        Tile::Switch
+    } else if keyboard_input.pressed(KeyCode::N) {
+        Tile::Door
+    } else if keyboard_input.pressed(KeyCode::T) {
+        // Nothing is placed once every pair is used up.
+        Tile::Teleporter(editing_state.next_teleporter_pair()?)
+    } else if keyboard_input.pressed(KeyCode::I) {
+        Tile::Ice
+    } else if keyboard_input.pressed(KeyCode::O) {
+        Tile::OneWay(0)
+    } else if keyboard_input.pressed(KeyCode::P) {
+        Tile::Pickup(EDITOR_PICKUP_ENERGY)
+    } else if keyboard_input.pressed(KeyCode::H) {
+        Tile::Pit
+    } else if keyboard_input.pressed(KeyCode::M) {
+        Tile::Mirror
+    } else if keyboard_input.pressed(KeyCode::G) {
+        Tile::Exit
+    } else if keyboard_input.pressed(KeyCode::J) {
+        Tile::HeavyBlock
+    } else if keyboard_input.pressed(KeyCode::Key4) {
+        // The letters are all taken.
+        Tile::Enemy(0)
+    } else if keyboard_input.pressed(KeyCode::Key5) {
+        Tile::Key(editing_state.color)
+    } else if keyboard_input.pressed(KeyCode::Key6) {
+        Tile::LockedDoor(editing_state.color)
+    } else if keyboard_input.pressed(KeyCode::Key7) {
+        Tile::Fragile
+    } else if keyboard_input.pressed(KeyCode::Key8) {
+        Tile::Portal(editing_state.next_portal_pair()?)
+    } else {
+        return None;
+    };
+    Some(tile)
+}
 
-        for goal_position in self.goals.keys() {
-            level[(goal_position.y - min_y) as usize][(goal_position.x - min_x) as usize] = 4;
-        }
+// 1, 2 and 3 dress the floor with a plant, a crack or a crate.
+fn key_decoration(keyboard_input: &Input<KeyCode>) -> Option<Decoration> {
+    if keyboard_input.pressed(KeyCode::Key1) {
+        Some(Decoration::Plant)
+    } else if keyboard_input.pressed(KeyCode::Key2) {
+        Some(Decoration::Crack)
+    } else if keyboard_input.pressed(KeyCode::Key3) {
+        Some(Decoration::Crate)
+    } else {
+        None
+    }
+}
 
-        for block_position in self.blocks.keys() {
-            level[(block_position.y - min_y) as usize][(block_position.x - min_x) as usize] = 2;
-        }
+// Puts a decoration on the floor at `position`, replacing any that was there.
+fn place_decoration(
+    commands: &mut Commands,
+    editing_state: &mut EditingState,
+    position: Position,
+    decoration: Decoration,
+) {
+    if !editing_state.floors.contains_key(&position) {
+        return;
+    }
+    editing_state.dirty = true;
+    let entity = commands
+        .spawn((LevelEntity, spawn_decoration(position, decoration)))
+        .id();
+    if let Some((replaced, _)) = editing_state
+        .decorations
+        .insert(position, (entity, decoration))
+    {
+        commands.entity(replaced).despawn();
+    }
+}
 
-        if let Some((player_position, _)) = self.player {
-            level[(player_position.y - min_y) as usize][(player_position.x - min_x) as usize] = 1;
+// One-way and enemy directions go up, right, down, left.
+fn mirrored_direction(mut direction: usize, flip_x: bool, flip_y: bool) -> usize {
+    if flip_x {
+        direction = (4 - direction) % 4;
+    }
+    if flip_y {
+        direction = (6 - direction) % 4;
+    }
+    direction
+}
+
+// The tile as seen in a mirror, there's only one player, mirror and exit so they aren't.
+fn mirrored_tile(tile: Tile, flip_x: bool, flip_y: bool) -> Option<Tile> {
+    match tile {
+        Tile::Player | Tile::Mirror | Tile::Exit => None,
+        Tile::OneWay(direction) => {
+            Some(Tile::OneWay(mirrored_direction(direction, flip_x, flip_y)))
         }
+        Tile::Enemy(direction) => Some(Tile::Enemy(mirrored_direction(direction, flip_x, flip_y))),
+        tile => Some(tile),
+    }
+}
 
-        level
+// Spawns the tile at `position`, there should be nothing there yet.
+fn place_tile(
+    commands: &mut Commands,
+    editing_state: &mut EditingState,
+    position: Position,
+    tile: Tile,
+) {
+    let Some(entity) = spawn_tile(commands, position, tile) else {
+        return;
+    };
+    editing_state.dirty = true;
+    let replaced = match tile {
+        Tile::Empty => None,
+        Tile::Wall => editing_state.walls.insert(position, entity),
+        Tile::Block(color) => editing_state
+            .blocks
+            .insert(position, (entity, color))
+            .map(|(entity, _)| entity),
+        Tile::Goal(color) => editing_state
+            .goals
+            .insert(position, (entity, color))
+            .map(|(entity, _)| entity),
+        Tile::HeavyBlock => editing_state.heavy_blocks.insert(position, entity),
+        Tile::Switch => editing_state.switches.insert(position, entity),
+        Tile::Door => editing_state.doors.insert(position, entity),
+        Tile::Teleporter(pair) => editing_state
+            .teleporters
+            .insert(position, (entity, pair))
+            .map(|(entity, _)| entity),
+        Tile::Ice => editing_state.ice.insert(position, entity),
+        Tile::OneWay(direction) => editing_state
+            .one_way
+            .insert(position, (entity, direction))
+            .map(|(entity, _)| entity),
+        Tile::Pickup(_) => editing_state.pickups.insert(position, entity),
+        Tile::Pit => editing_state.pits.insert(position, entity),
+        Tile::Enemy(direction) => editing_state
+            .enemies
+            .insert(position, (entity, direction))
+            .map(|(entity, _)| entity),
+        Tile::Key(color) => editing_state
+            .keys
+            .insert(position, (entity, color))
+            .map(|(entity, _)| entity),
+        Tile::LockedDoor(color) => editing_state
+            .locked_doors
+            .insert(position, (entity, color))
+            .map(|(entity, _)| entity),
+        Tile::Fragile => editing_state.fragile.insert(position, entity),
+        Tile::Portal(pair) => editing_state
+            .portals
+            .insert(position, (entity, pair))
+            .map(|(entity, _)| entity),
+        // There's only one of each of these, placing another moves it.
+        Tile::Player => editing_state
+            .player
+            .replace((position, entity))
+            .map(|(_, entity)| entity),
+        Tile::Mirror => editing_state
+            .mirror
+            .replace((position, entity))
+            .map(|(_, entity)| entity),
+        Tile::Exit => editing_state
+            .exit
+            .replace((position, entity))
+            .map(|(_, entity)| entity),
+    };
+    if let Some(replaced) = replaced {
+        commands.entity(replaced).despawn_recursive();
     }
 }
 
@@ -72,24 +603,102 @@ struct Cursor {
     action_timer: Timer,
 }
 
-fn remove_level(mut commands: Commands, almost_everything_query: Query<Entity, Without<Window>>) {
-    for entity in almost_everything_query.iter() {
+// The level being played when the editor was opened, kept so L can go back to it.
+// Its entities stay alive, hidden, since the level state and history refer to them.
+#[derive(Resource)]
+struct StashedSession {
+    level_state: LevelState,
+    move_history: MoveHistory,
+    camera_translation: Vec3,
+}
+
+// Marks a stashed level entity and remembers whether it was visible.
+#[derive(Component)]
+struct Stashed(Visibility);
+
+fn stash_session(
+    mut commands: Commands,
+    level_state: Res<LevelState>,
+    move_history: Res<MoveHistory>,
+    level_entity_query: Query<(Entity, &Visibility), With<LevelEntity>>,
+    camera_query: Query<&Transform, With<MainCamera>>,
+) {
+    if level_entity_query.is_empty() {
+        return;
+    }
+    for (entity, visibility) in &level_entity_query {
+        commands
+            .entity(entity)
+            .remove::<LevelEntity>()
+            .insert((Stashed(*visibility), Visibility::Hidden));
+    }
+    commands.insert_resource(StashedSession {
+        level_state: level_state.clone(),
+        move_history: move_history.clone(),
+        camera_translation: camera_query
+            .get_single()
+            .map(|transform| transform.translation)
+            .unwrap_or_default(),
+    });
+}
+
+// L leaves the editor, back to the interrupted level if there was one.
+fn leave_editor(
+    mut commands: Commands,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    stashed_session: Option<Res<StashedSession>>,
+    level_entity_query: Query<Entity, With<LevelEntity>>,
+    stashed_query: Query<(Entity, &Stashed)>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::L) {
+        return;
+    }
+    keyboard_input.reset(KeyCode::L);
+
+    let Some(stashed_session) = stashed_session else {
+        game_state.set(GameState::LevelSelect);
+        return;
+    };
+    for entity in &level_entity_query {
         commands.entity(entity).despawn();
     }
+    for (entity, stashed) in &stashed_query {
+        commands
+            .entity(entity)
+            .remove::<Stashed>()
+            .insert((LevelEntity, stashed.0));
+    }
+    if let Ok(mut camera_transform) = camera_query.get_single_mut() {
+        camera_transform.translation = stashed_session.camera_translation;
+    }
+    commands.insert_resource(stashed_session.level_state.clone());
+    commands.insert_resource(stashed_session.move_history.clone());
+    commands.remove_resource::<StashedSession>();
+    commands.insert_resource(EditingState::default());
+    game_state.set(GameState::Playing);
 }
 
-fn show_cursor(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let camera_position = Vec3::new(TILE_SIZE / 2.0, -(TILE_SIZE) / 2.0, 1000.0);
-    commands.spawn(Camera2dBundle {
-        transform: Transform {
-            translation: camera_position,
-            scale: Vec3::new(0.5, 0.5, 1.0),
-            ..default()
-        },
-        ..default()
-    });
+// Anything still stashed when the editor closes, e.g. to playtest, is gone for good.
+fn drop_stashed_session(mut commands: Commands, stashed_query: Query<Entity, With<Stashed>>) {
+    for entity in &stashed_query {
+        commands.entity(entity).despawn();
+    }
+    commands.remove_resource::<StashedSession>();
+}
+
+fn show_cursor(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    if let Ok(mut camera_transform) = camera_query.get_single_mut() {
+        camera_transform.translation = Vec3::new(TILE_SIZE / 2.0, -(TILE_SIZE) / 2.0, 1000.0);
+    }
 
     commands.spawn((
+        LevelEntity,
         Cursor {
             action_timer: Timer::from_seconds(0.2, TimerMode::Once),
         },
@@ -107,9 +716,456 @@ fn show_cursor(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.insert_resource(EditingState::default());
 }
 
+fn spawn_wall(commands: &mut Commands, position: Position) -> Entity {
+    spawn_tile(commands, position, Tile::Wall).unwrap()
+}
+
+const WALL_COMBINATIONS: [(i32, i32); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+// Lays floor at `position` and walls in any empty space around it.
+fn place_floor(commands: &mut Commands, editing_state: &mut EditingState, position: Position) {
+    let floor_entity = commands.spawn((LevelEntity, spawn_floor(position))).id();
+    editing_state.floors.insert(position, floor_entity);
+    editing_state.dirty = true;
+
+    if let Some(wall_entity) = editing_state.walls.remove(&position) {
+        commands.entity(wall_entity).despawn();
+    }
+
+    for (relative_x, relative_y) in WALL_COMBINATIONS {
+        let wall_position = position.add(relative_x, relative_y);
+        if !editing_state.floors.contains_key(&wall_position)
+            && !editing_state.walls.contains_key(&wall_position)
+        {
+            let wall_id = spawn_wall(commands, wall_position);
+            editing_state.walls.insert(wall_position, wall_id);
+        }
+    }
+}
+
+// Shift moves the whole level, Ctrl grows it by a row or column and Alt crops one off.
+fn layout_direction(keyboard_input: &Input<KeyCode>) -> Option<(i32, i32)> {
+    if keyboard_input.pressed(KeyCode::Up) {
+        Some((0, -1))
+    } else if keyboard_input.pressed(KeyCode::Down) {
+        Some((0, 1))
+    } else if keyboard_input.pressed(KeyCode::Left) {
+        Some((-1, 0))
+    } else if keyboard_input.pressed(KeyCode::Right) {
+        Some((1, 0))
+    } else {
+        None
+    }
+}
+
+fn shift_held(keyboard_input: &Input<KeyCode>) -> bool {
+    keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+}
+
+fn control_held(keyboard_input: &Input<KeyCode>) -> bool {
+    keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+fn alt_held(keyboard_input: &Input<KeyCode>) -> bool {
+    keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+}
+
+// Cropping can leave walls with no floor next to them, so they're all redone.
+fn rebuild_walls(commands: &mut Commands, editing_state: &mut EditingState) {
+    for (_, wall_entity) in editing_state.walls.drain() {
+        commands.entity(wall_entity).despawn();
+    }
+    let floors: Vec<Position> = editing_state.floors.keys().copied().collect();
+    for position in floors {
+        for (relative_x, relative_y) in WALL_COMBINATIONS {
+            let wall_position = position.add(relative_x, relative_y);
+            if !editing_state.floors.contains_key(&wall_position)
+                && !editing_state.walls.contains_key(&wall_position)
+            {
+                let wall_id = spawn_wall(commands, wall_position);
+                editing_state.walls.insert(wall_position, wall_id);
+            }
+        }
+    }
+}
+
+// Takes out the floor and anything on it, the walls need rebuilding afterwards.
+fn clear_floor(commands: &mut Commands, editing_state: &mut EditingState, position: Position) {
+    editing_state.dirty = true;
+    while let Some(removed_entity) = editing_state.remove_object(&position) {
+        commands.entity(removed_entity).despawn_recursive();
+    }
+    if let Some(floor_entity) = editing_state.floors.remove(&position) {
+        commands.entity(floor_entity).despawn();
+    }
+}
+
+const FILL_DIRECTIONS: [(i32, i32); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+// The empty space around `start` enclosed by walls, `None` if it leaks out of the level.
+fn enclosed_area(editing_state: &EditingState, start: Position) -> Option<Vec<Position>> {
+    let min_x = editing_state.walls.keys().map(|p| p.x).min()?;
+    let max_x = editing_state.walls.keys().map(|p| p.x).max()?;
+    let min_y = editing_state.walls.keys().map(|p| p.y).min()?;
+    let max_y = editing_state.walls.keys().map(|p| p.y).max()?;
+    let is_open = |position: &Position| {
+        !editing_state.floors.contains_key(position) && !editing_state.walls.contains_key(position)
+    };
+    if !is_open(&start) {
+        return None;
+    }
+
+    let mut area = vec![start];
+    let mut seen = HashSet::from([start]);
+    let mut index = 0;
+    while let Some(position) = area.get(index).copied() {
+        index += 1;
+        if position.x <= min_x || position.x >= max_x || position.y <= min_y || position.y >= max_y
+        {
+            return None;
+        }
+        for (relative_x, relative_y) in FILL_DIRECTIONS {
+            let next = position.add(relative_x, relative_y);
+            if is_open(&next) && seen.insert(next) {
+                area.push(next);
+            }
+        }
+    }
+    Some(area)
+}
+
+// The floors connected to `start`.
+fn connected_floors(editing_state: &EditingState, start: Position) -> Vec<Position> {
+    if !editing_state.floors.contains_key(&start) {
+        return Vec::new();
+    }
+    let mut area = vec![start];
+    let mut seen = HashSet::from([start]);
+    let mut index = 0;
+    while let Some(position) = area.get(index).copied() {
+        index += 1;
+        for (relative_x, relative_y) in FILL_DIRECTIONS {
+            let next = position.add(relative_x, relative_y);
+            if editing_state.floors.contains_key(&next) && seen.insert(next) {
+                area.push(next);
+            }
+        }
+    }
+    area
+}
+
+fn rectangle(corner: Position, other_corner: Position) -> Vec<Position> {
+    let (min_x, max_x) = (corner.x.min(other_corner.x), corner.x.max(other_corner.x));
+    let (min_y, max_y) = (corner.y.min(other_corner.y), corner.y.max(other_corner.y));
+    (min_y..=max_y)
+        .flat_map(|y| (min_x..=max_x).map(move |x| Position { x, y }))
+        .collect()
+}
+
+// D drags out a rectangle and F flood fills, both lay floor or carve walls with Shift held.
+fn handle_fill_input(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut editing_state: ResMut<EditingState>,
+    cursor_query: Query<&Transform, With<Cursor>>,
+) {
+    let Ok(cursor_transform) = cursor_query.get_single() else {
+        return;
+    };
+    let cursor_position = Position::from_translation(cursor_transform.translation);
+
+    let area = if keyboard_input.just_pressed(KeyCode::D) {
+        match editing_state.rectangle_anchor.take() {
+            Some((anchor, marker_entity)) => {
+                commands.entity(marker_entity).despawn();
+                rectangle(anchor, cursor_position)
+            }
+            None => {
+                let marker_entity = commands
+                    .spawn((
+                        LevelEntity,
+                        spawn_colored_tile(cursor_position, Color::rgba(1.0, 1.0, 1.0, 0.4), 1.5),
+                    ))
+                    .id();
+                editing_state.rectangle_anchor = Some((cursor_position, marker_entity));
+                return;
+            }
+        }
+    } else if keyboard_input.just_pressed(KeyCode::F) {
+        if shift_held(&keyboard_input) {
+            connected_floors(&editing_state, cursor_position)
+        } else {
+            let Some(area) = enclosed_area(&editing_state, cursor_position) else {
+                warn!("Nothing to fill, the space isn't enclosed by walls");
+                return;
+            };
+            area
+        }
+    } else {
+        return;
+    };
+
+    if shift_held(&keyboard_input) {
+        for position in area {
+            clear_floor(&mut commands, &mut editing_state, position);
+        }
+        rebuild_walls(&mut commands, &mut editing_state);
+    } else {
+        for position in area {
+            if !editing_state.floors.contains_key(&position) {
+                place_floor(&mut commands, &mut editing_state, position);
+            }
+        }
+    }
+}
+
+// A copied region, the floors in it and what sits on them, relative to its top left corner.
+#[derive(Resource, Default)]
+struct Clipboard {
+    width: i32,
+    height: i32,
+    cells: Vec<(Position, Option<Tile>)>,
+}
+
+impl Clipboard {
+    fn copy(editing_state: &EditingState, corner: Position, other_corner: Position) -> Self {
+        let min_x = corner.x.min(other_corner.x);
+        let min_y = corner.y.min(other_corner.y);
+        let cells = rectangle(corner, other_corner)
+            .into_iter()
+            .filter(|position| editing_state.floors.contains_key(position))
+            .map(|position| {
+                (
+                    position.add(-min_x, -min_y),
+                    editing_state.tile_at(&position),
+                )
+            })
+            .collect();
+        Self {
+            width: (corner.x - other_corner.x).abs() + 1,
+            height: (corner.y - other_corner.y).abs() + 1,
+            cells,
+        }
+    }
+
+    fn transform(
+        &mut self,
+        move_cell: impl Fn(Position) -> Position,
+        turn_direction: impl Fn(usize) -> usize,
+    ) {
+        for (position, tile) in &mut self.cells {
+            *position = move_cell(*position);
+            if let Some(Tile::OneWay(direction) | Tile::Enemy(direction)) = tile {
+                *direction = turn_direction(*direction);
+            }
+        }
+    }
+
+    // One-way and enemy directions go up, right, down, left.
+    fn mirror_horizontally(&mut self) {
+        let width = self.width;
+        self.transform(
+            |position| Position {
+                x: width - 1 - position.x,
+                y: position.y,
+            },
+            |direction| (4 - direction) % 4,
+        );
+    }
+
+    fn mirror_vertically(&mut self) {
+        let height = self.height;
+        self.transform(
+            |position| Position {
+                x: position.x,
+                y: height - 1 - position.y,
+            },
+            |direction| (6 - direction) % 4,
+        );
+    }
+
+    fn rotate_clockwise(&mut self) {
+        let height = self.height;
+        self.transform(
+            |position| Position {
+                x: height - 1 - position.y,
+                y: position.x,
+            },
+            |direction| (direction + 1) % 4,
+        );
+        (self.width, self.height) = (self.height, self.width);
+    }
+}
+
+fn paste(
+    commands: &mut Commands,
+    editing_state: &mut EditingState,
+    clipboard: &Clipboard,
+    corner: Position,
+) {
+    // Pasted teleporters and portals get pairs of their own so they don't link up with the
+    // originals, and are left out once the pair numbers run out.
+    let mut next_pair = editing_state
+        .teleporters
+        .values()
+        .map(|(_, pair)| pair + 1)
+        .max()
+        .unwrap_or(0);
+    let mut pairs = HashMap::new();
+    let mut next_portal_pair = editing_state
+        .portals
+        .values()
+        .map(|(_, pair)| pair + 1)
+        .max()
+        .unwrap_or(0);
+    let mut portal_pairs = HashMap::new();
+
+    for (offset, tile) in &clipboard.cells {
+        let position = corner.add(offset.x, offset.y);
+        if !editing_state.floors.contains_key(&position) {
+            place_floor(commands, editing_state, position);
+        }
+        while let Some(removed_entity) = editing_state.remove_object(&position) {
+            commands.entity(removed_entity).despawn_recursive();
+        }
+        let tile = match tile {
+            Some(Tile::Teleporter(pair)) => {
+                Some(Tile::Teleporter(*pairs.entry(*pair).or_insert_with(|| {
+                    next_pair += 1;
+                    next_pair - 1
+                })))
+            }
+            Some(Tile::Portal(pair)) => Some(Tile::Portal(
+                *portal_pairs.entry(*pair).or_insert_with(|| {
+                    next_portal_pair += 1;
+                    next_portal_pair - 1
+                }),
+            )),
+            _ => *tile,
+        };
+        let tile = tile.filter(|tile| match tile {
+            Tile::Teleporter(pair) | Tile::Portal(pair) => *pair < EDITOR_PAIR_NUMBERS,
+            _ => true,
+        });
+        if let Some(tile) = tile {
+            place_tile(commands, editing_state, position, tile);
+        }
+    }
+}
+
+// A marks the corners of a region to copy, Q pastes it at the cursor.
+// W mirrors the copy (vertically with Shift held) and Y turns it clockwise.
+fn handle_clipboard_input(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut editing_state: ResMut<EditingState>,
+    mut clipboard: ResMut<Clipboard>,
+    cursor_query: Query<&Transform, With<Cursor>>,
+) {
+    let Ok(cursor_transform) = cursor_query.get_single() else {
+        return;
+    };
+    let cursor_position = Position::from_translation(cursor_transform.translation);
+
+    if keyboard_input.just_pressed(KeyCode::A) {
+        match editing_state.selection_anchor.take() {
+            Some((anchor, marker_entity)) => {
+                commands.entity(marker_entity).despawn();
+                *clipboard = Clipboard::copy(&editing_state, anchor, cursor_position);
+                info!("Copied {} tiles", clipboard.cells.len());
+            }
+            None => {
+                let marker_entity = commands
+                    .spawn((
+                        LevelEntity,
+                        spawn_colored_tile(cursor_position, Color::rgba(0.3, 0.6, 1.0, 0.4), 1.5),
+                    ))
+                    .id();
+                editing_state.selection_anchor = Some((cursor_position, marker_entity));
+            }
+        }
+    } else if keyboard_input.just_pressed(KeyCode::Q) {
+        paste(
+            &mut commands,
+            &mut editing_state,
+            &clipboard,
+            cursor_position,
+        );
+    } else if keyboard_input.just_pressed(KeyCode::W) {
+        if shift_held(&keyboard_input) {
+            clipboard.mirror_vertically();
+        } else {
+            clipboard.mirror_horizontally();
+        }
+    } else if keyboard_input.just_pressed(KeyCode::Y) {
+        clipboard.rotate_clockwise();
+    }
+}
+
+fn handle_layout_input(
+    mut commands: Commands,
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut editing_state: ResMut<EditingState>,
+    mut action_timer: Local<Option<Timer>>,
+    mut transform_query: Query<(&mut Transform, Has<Cursor>)>,
+) {
+    let action_timer =
+        action_timer.get_or_insert_with(|| Timer::from_seconds(0.2, TimerMode::Once));
+    if !action_timer.finished() {
+        action_timer.tick(time.delta());
+        return;
+    }
+    let Some((x, y)) = layout_direction(&keyboard_input) else {
+        return;
+    };
+
+    if shift_held(&keyboard_input) {
+        action_timer.reset();
+
+        let offset = Vec3::new(x as f32 * TILE_SIZE, y as f32 * -TILE_SIZE, 0.0);
+        for entity in editing_state.translate(x, y) {
+            if let Ok((mut transform, _)) = transform_query.get_mut(entity) {
+                transform.translation += offset;
+            }
+        }
+        for (mut transform, is_cursor) in &mut transform_query {
+            if is_cursor {
+                transform.translation += offset;
+            }
+        }
+    } else if control_held(&keyboard_input) {
+        action_timer.reset();
+
+        for position in editing_state.edge(x, y) {
+            place_floor(&mut commands, &mut editing_state, position.add(x, y));
+        }
+    } else if alt_held(&keyboard_input) {
+        action_timer.reset();
+
+        let edge = editing_state.edge(x, y);
+        // Always keep at least one row or column to work on.
+        if edge.len() == editing_state.floors.len() {
+            return;
+        }
+        for position in edge {
+            clear_floor(&mut commands, &mut editing_state, position);
+        }
+        rebuild_walls(&mut commands, &mut editing_state);
+    }
+}
+
 fn handle_edit_input(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
     time: Res<Time>,
     keyboard_input: Res<Input<KeyCode>>,
     mut editing_state: ResMut<EditingState>,
@@ -128,16 +1184,11 @@ fn handle_edit_input(
         return;
     }
 
-    let mut movement: Option<(i32, i32)> = None;
-    if keyboard_input.pressed(KeyCode::Up) {
-        movement = Some((0, -1));
-    } else if keyboard_input.pressed(KeyCode::Down) {
-        movement = Some((0, 1));
-    } else if keyboard_input.pressed(KeyCode::Left) {
-        movement = Some((-1, 0));
-    } else if keyboard_input.pressed(KeyCode::Right) {
-        movement = Some((1, 0));
-    }
+    // With a modifier held the arrows change the layout instead, the cursor moves along
+    // with the level when it's shifted.
+    let layout_modifier_held =
+        shift_held(&keyboard_input) || control_held(&keyboard_input) || alt_held(&keyboard_input);
+    let movement = layout_direction(&keyboard_input).filter(|_| !layout_modifier_held);
 
     let mut cursor_position = Position::from_translation(transform.translation);
 
@@ -151,117 +1202,129 @@ fn handle_edit_input(
     if keyboard_input.pressed(KeyCode::Z) && !editing_state.floors.contains_key(&cursor_position) {
         cursor.action_timer.reset();
 
-        let floor_entity = commands
-            .spawn(spawn_floor(&asset_server, cursor_position))
-            .id();
-
-        editing_state.floors.insert(cursor_position, floor_entity);
-
-        if let Some(wall_entity) = editing_state.walls.get(&cursor_position) {
-            commands.entity(*wall_entity).despawn();
-            editing_state.walls.remove(&cursor_position);
+        place_floor(&mut commands, &mut editing_state, cursor_position);
+        for (position, ..) in editing_state.symmetric_positions(cursor_position) {
+            if !editing_state.floors.contains_key(&position) {
+                place_floor(&mut commands, &mut editing_state, position);
+            }
         }
+    } else if let Some(tile) = key_tile(&keyboard_input, &editing_state)
+        .filter(|tile| editing_state.can_place(&cursor_position, *tile))
+    {
+        cursor.action_timer.reset();
 
-        let wall_combinations = vec![
-            (-1, -1),
-            (-1, 0),
-            (-1, 1),
-            (0, -1),
-            (0, 1),
-            (1, -1),
-            (1, 0),
-            (1, 1),
-        ];
-        for (relative_x, relative_y) in wall_combinations {
-            let wall_position = cursor_position.add(relative_x, relative_y);
-
-            if !editing_state.floors.contains_key(&wall_position)
-                && !editing_state.walls.contains_key(&wall_position)
-            {
-                let wall_id = commands
-                    .spawn(SpriteBundle {
-                        sprite: Sprite {
-                            anchor: Anchor::TopLeft,
-                            ..default()
-                        },
-                        texture: asset_server.load("wall.png"),
-                        transform: Transform::from_translation(wall_position.to_translation()),
-                        ..default()
-                    })
-                    .id();
-                editing_state.walls.insert(wall_position, wall_id);
+        place_tile(&mut commands, &mut editing_state, cursor_position, tile);
+        for (position, flip_x, flip_y) in editing_state.symmetric_positions(cursor_position) {
+            let Some(mirrored_tile) = mirrored_tile(tile, flip_x, flip_y) else {
+                continue;
+            };
+            if !editing_state.can_place(&position, mirrored_tile) {
+                continue;
             }
+            // Each mirrored teleporter or portal pairs up with the one placed before it.
+            let mirrored_tile = match mirrored_tile {
+                Tile::Teleporter(_) => editing_state.next_teleporter_pair().map(Tile::Teleporter),
+                Tile::Portal(_) => editing_state.next_portal_pair().map(Tile::Portal),
+                mirrored_tile => Some(mirrored_tile),
+            };
+            let Some(mirrored_tile) = mirrored_tile else {
+                continue;
+            };
+            place_tile(&mut commands, &mut editing_state, position, mirrored_tile);
         }
-    } else if keyboard_input.pressed(KeyCode::X) && editing_state.can_place(&cursor_position) {
+    } else if let Some(decoration) = key_decoration(&keyboard_input)
+        .filter(|_| editing_state.floors.contains_key(&cursor_position))
+    {
         cursor.action_timer.reset();
 
-        let block_translation = cursor_position.to_translation();
-
-        let block_id = commands
-            .spawn(SpriteBundle {
-                sprite: Sprite {
-                    anchor: Anchor::TopLeft,
-                    ..default()
-                },
-                texture: asset_server.load("block.png"),
-                transform: Transform::from_translation(block_translation),
-                ..default()
-            })
-            .id();
-        editing_state.blocks.insert(cursor_position, block_id);
-    } else if keyboard_input.pressed(KeyCode::C) && editing_state.can_place(&cursor_position) {
+        place_decoration(
+            &mut commands,
+            &mut editing_state,
+            cursor_position,
+            decoration,
+        );
+        for (position, ..) in editing_state.symmetric_positions(cursor_position) {
+            place_decoration(&mut commands, &mut editing_state, position, decoration);
+        }
+    } else if keyboard_input.pressed(KeyCode::R) {
+        // Turns the one-way tile or enemy under the cursor clockwise.
+        let turned = match editing_state.tile_at(&cursor_position) {
+            Some(Tile::OneWay(direction)) => {
+                Tile::OneWay((direction + 1) % ONE_WAY_DIRECTIONS.len())
+            }
+            Some(Tile::Enemy(direction)) => Tile::Enemy((direction + 1) % ONE_WAY_DIRECTIONS.len()),
+            _ => return,
+        };
         cursor.action_timer.reset();
 
-        let goal_translation = cursor_position.to_translation_z(0.5);
-
-        let goal_id = commands
-            .spawn(SpriteBundle {
-                sprite: Sprite {
-                    anchor: Anchor::TopLeft,
-                    ..default()
-                },
-                texture: asset_server.load("goal.png"),
-                transform: Transform::from_translation(goal_translation),
-                ..default()
-            })
-            .id();
-        editing_state.goals.insert(cursor_position, goal_id);
-    } else if keyboard_input.pressed(KeyCode::V) && editing_state.can_place(&cursor_position) {
+        if let Some(turned_id) = editing_state.remove_object(&cursor_position) {
+            commands.entity(turned_id).despawn_recursive();
+        }
+        place_tile(&mut commands, &mut editing_state, cursor_position, turned);
+    } else if keyboard_input.pressed(KeyCode::K) {
+        // Cycles the color of the next blocks, goals, keys and locked doors placed.
         cursor.action_timer.reset();
 
-        let player_translation = cursor_position.to_translation();
-
-        let player_id = commands
-            .spawn(SpriteBundle {
-                sprite: Sprite {
-                    anchor: Anchor::TopLeft,
-                    ..default()
-                },
-                texture: asset_server.load("player.png"),
-                transform: Transform::from_translation(player_translation),
-                ..default()
-            })
-            .id();
-
-        if editing_state.player.is_some() {
-            commands.entity(editing_state.player.unwrap().1).despawn();
-        }
-        editing_state.player = Some((cursor_position, player_id));
+        editing_state.color = (editing_state.color + 1) % (EDITOR_BLOCK_COLORS + 1);
     } else if keyboard_input.pressed(KeyCode::S) {
         let Some(removed_entity) = editing_state.remove_object(&cursor_position) else {
             return;
         };
+        editing_state.dirty = true;
+
+        commands.entity(removed_entity).despawn_recursive();
+        for (position, ..) in editing_state.symmetric_positions(cursor_position) {
+            if let Some(removed_entity) = editing_state.remove_object(&position) {
+                commands.entity(removed_entity).despawn_recursive();
+            }
+        }
+    }
+}
 
-        commands.entity(removed_entity).despawn();
+// U cycles the symmetry mode, Shift+U puts the axis through the cursor.
+fn handle_symmetry_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut editing_state: ResMut<EditingState>,
+    cursor_query: Query<&Transform, With<Cursor>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::U) {
+        return;
     }
+    if shift_held(&keyboard_input) {
+        let Ok(cursor_transform) = cursor_query.get_single() else {
+            return;
+        };
+        editing_state.symmetry_axis = Position::from_translation(cursor_transform.translation);
+    } else {
+        editing_state.symmetry = editing_state.symmetry.next();
+    }
+    info!(
+        "Symmetry {:?} about {:?}",
+        editing_state.symmetry, editing_state.symmetry_axis
+    );
 }
 
 impl Plugin for EditPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Editing), (remove_level, show_cursor))
+        app.insert_resource(Clipboard::default())
+            .add_systems(
+                OnEnter(GameState::Editing),
+                (stash_session, show_cursor).chain(),
+            )
+            .add_systems(OnExit(GameState::Editing), drop_stashed_session)
             .add_systems(
                 Update,
-                handle_edit_input.run_if(in_state(GameState::Editing)),
+                (
+                    handle_edit_input,
+                    handle_layout_input,
+                    handle_fill_input,
+                    handle_clipboard_input,
+                    handle_symmetry_input,
+                    leave_editor,
+                )
+                    .run_if(in_state(GameState::Editing))
+                    .run_if(quit_prompt_closed)
+                    .run_if(save_dialog_closed),
             );
     }
 }
@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+use crate::{play_plugin::LevelState, GameState};
+
+// Tiles per second an enemy eases along at, a little quicker than the player walks so it's
+// in place before the next move.
+const ENEMY_SPEED: f32 = 12.0;
+
+// Enemies take their step in `LevelState::apply` as each move is recorded, this only shows
+// where they've got to and says when one has caught the player.
+pub struct EnemyPlugin;
+
+#[derive(Component)]
+pub struct Enemy;
+
+#[derive(Component)]
+struct CaughtText;
+
+// Eases each enemy towards where the level state has it, after an undo as much as a move.
+fn move_enemies(
+    time: Res<Time>,
+    level_state: Res<LevelState>,
+    mut enemy_query: Query<(Entity, &mut Transform), With<Enemy>>,
+) {
+    let step = (time.delta_seconds() * ENEMY_SPEED).min(1.0);
+    for (entity, mut transform) in &mut enemy_query {
+        let Some(patrol) = level_state.enemies.get(&entity) else {
+            continue;
+        };
+        let target = patrol.position.to_translation();
+        if transform.translation != target {
+            let next = transform.translation.lerp(target, step);
+            transform.translation = if next.distance(target) < 0.1 {
+                target
+            } else {
+                next
+            };
+        }
+    }
+}
+
+fn show_caught(mut commands: Commands) {
+    commands.spawn((
+        CaughtText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::RED,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(130.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+fn hide_caught(mut commands: Commands, caught_text_query: Query<Entity, With<CaughtText>>) {
+    for entity in caught_text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn update_caught_text(
+    level_state: Res<LevelState>,
+    mut caught_text_query: Query<&mut Text, With<CaughtText>>,
+) {
+    let message = if level_state.caught {
+        "Caught!  U: undo  R: restart"
+    } else {
+        ""
+    };
+    for mut text in &mut caught_text_query {
+        if text.sections[0].value != message {
+            text.sections[0].value = message.to_string();
+        }
+    }
+}
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), show_caught)
+            .add_systems(OnExit(GameState::Playing), hide_caught)
+            .add_systems(
+                Update,
+                (move_enemies, update_caught_text).run_if(in_state(GameState::Playing)),
+            );
+    }
+}
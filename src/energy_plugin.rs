@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+
+use crate::{play_plugin::LevelState, GameState};
+
+pub struct EnergyPlugin;
+
+#[derive(Component)]
+struct EnergyText;
+
+fn show_energy(mut commands: Commands) {
+    commands.spawn((
+        EnergyText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+fn hide_energy(mut commands: Commands, energy_text_query: Query<Entity, With<EnergyText>>) {
+    for entity in energy_text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn update_energy_text(
+    level_state: Res<LevelState>,
+    mut energy_text_query: Query<&mut Text, With<EnergyText>>,
+) {
+    for mut text in &mut energy_text_query {
+        let section = &mut text.sections[0];
+        (section.value, section.style.color) = match level_state.energy {
+            None => (String::new(), Color::WHITE),
+            Some(_) if level_state.is_out_of_energy() && !level_state.is_solved() => (
+                "Out of energy!  U: undo  R: restart".to_string(),
+                Color::RED,
+            ),
+            Some(energy) => (format!("Energy: {}", energy), Color::WHITE),
+        };
+    }
+}
+
+// Pickups disappear once collected and come back if the move is undone.
+fn update_pickups(level_state: Res<LevelState>, mut visibility_query: Query<&mut Visibility>) {
+    if !level_state.is_changed() {
+        return;
+    }
+    let pickups = level_state
+        .pickups
+        .values()
+        .map(|(entity, _)| (entity, Visibility::Inherited));
+    let collected = level_state
+        .collected
+        .iter()
+        .map(|(_, (entity, _), _)| (entity, Visibility::Hidden));
+    for (entity, shown) in pickups.chain(collected) {
+        if let Ok(mut visibility) = visibility_query.get_mut(*entity) {
+            *visibility = shown;
+        }
+    }
+}
+
+impl Plugin for EnergyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), show_energy)
+            .add_systems(OnExit(GameState::Playing), hide_energy)
+            .add_systems(
+                Update,
+                (update_energy_text, update_pickups).run_if(in_state(GameState::Playing)),
+            );
+    }
+}
@@ -0,0 +1,85 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::{play_plugin::LevelState, sokoban_core::Tile, tiles::Theme, GameState};
+
+const CRUMBLE_SECONDS: f32 = 0.35;
+const CRUMBLE_SHAKE_PIXELS: f32 = 1.5;
+
+// Fragile floor gives way in `LevelState::apply` once the player steps off it, this shows it
+// crumbling into the pit it leaves, and pulling itself back together when that's undone.
+pub struct FragilePlugin;
+
+// How far the tile has crumbled, from 0 for whole to 1 for gone.
+#[derive(Component, Default)]
+pub struct Crumble {
+    progress: f32,
+}
+
+fn mix(from: Color, to: Color, amount: f32) -> Color {
+    let [from_red, from_green, from_blue, from_alpha] = from.as_rgba_f32();
+    let [to_red, to_green, to_blue, to_alpha] = to.as_rgba_f32();
+    Color::rgba(
+        from_red + (to_red - from_red) * amount,
+        from_green + (to_green - from_green) * amount,
+        from_blue + (to_blue - from_blue) * amount,
+        from_alpha + (to_alpha - from_alpha) * amount,
+    )
+}
+
+// The colour is set every frame rather than on changes, so it holds when the theme retints
+// every tile.
+fn crumble(
+    time: Res<Time>,
+    theme: Res<Theme>,
+    level_state: Res<LevelState>,
+    mut crumble_query: Query<(Entity, &mut Crumble, &mut Sprite, &mut Transform)>,
+) {
+    for (entity, mut crumble, mut sprite, mut transform) in &mut crumble_query {
+        let broken = level_state
+            .broken
+            .iter()
+            .find(|(_, broken_entity, _)| *broken_entity == entity)
+            .map(|(position, ..)| (*position, 1.0));
+        let whole = || {
+            level_state
+                .fragile
+                .iter()
+                .find(|(_, fragile_entity)| **fragile_entity == entity)
+                .map(|(position, _)| (*position, 0.0))
+        };
+        let Some((position, target)) = broken.or_else(whole) else {
+            continue;
+        };
+
+        let step = time.delta_seconds() / CRUMBLE_SECONDS;
+        crumble.progress = if target > crumble.progress {
+            (crumble.progress + step).min(target)
+        } else {
+            (crumble.progress - step).max(target)
+        };
+        let color = mix(
+            theme.tint(Tile::Fragile),
+            theme.tint(Tile::Pit),
+            crumble.progress,
+        );
+        if sprite.color != color {
+            sprite.color = color;
+        }
+        // Shudders on the way down and back up, and is still otherwise.
+        let shake = (crumble.progress * PI).sin()
+            * (time.elapsed_seconds() * 60.0).sin()
+            * CRUMBLE_SHAKE_PIXELS;
+        let translation = position.to_translation_z(transform.translation.z) + Vec3::X * shake;
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+    }
+}
+
+impl Plugin for FragilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, crumble.run_if(in_state(GameState::Playing)));
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::VecDeque;
+
+const MAX_ATTEMPTS: u64 = 500;
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+// SplitMix64, small and deterministic so the same seed builds the same level everywhere.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn chance(&mut self, probability: f32) -> bool {
+        (self.next_u64() % 10_000) as f32 / 10_000.0 < probability
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Theme {
+    OpenRoom,
+    Pillars,
+    Corridors,
+    Crowded,
+    Finale,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 5] = [
+        Theme::OpenRoom,
+        Theme::Pillars,
+        Theme::Corridors,
+        Theme::Crowded,
+        Theme::Finale,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::OpenRoom => "Open Room",
+            Theme::Pillars => "Pillars",
+            Theme::Corridors => "Corridors",
+            Theme::Crowded => "Crowded",
+            Theme::Finale => "Finale",
+        }
+    }
+
+    // (width, height, wall chance, blocks, pulls)
+    fn parameters(&self) -> (i32, i32, f32, usize, usize) {
+        match self {
+            Theme::OpenRoom => (7, 7, 0.0, 2, 30),
+            Theme::Pillars => (9, 7, 0.15, 2, 40),
+            Theme::Corridors => (9, 9, 0.3, 2, 50),
+            Theme::Crowded => (8, 8, 0.1, 4, 60),
+            Theme::Finale => (10, 9, 0.2, 4, 80),
+        }
+    }
+}
+
+struct Grid {
+    width: i32,
+    height: i32,
+    walls: Vec<bool>,
+}
+
+impl Grid {
+    fn index(&self, (x, y): (i32, i32)) -> usize {
+        (y * self.width + x) as usize
+    }
+
+    fn is_floor(&self, position: (i32, i32)) -> bool {
+        let (x, y) = position;
+        x >= 0 && y >= 0 && x < self.width && y < self.height && !self.walls[self.index(position)]
+    }
+
+    fn reachable(&self, from: (i32, i32), blocks: &[(i32, i32)]) -> Vec<bool> {
+        let mut visited = vec![false; self.walls.len()];
+        let mut to_visit = VecDeque::from([from]);
+        visited[self.index(from)] = true;
+        while let Some((x, y)) = to_visit.pop_front() {
+            for (dx, dy) in DIRECTIONS {
+                let next = (x + dx, y + dy);
+                if !self.is_floor(next) || blocks.contains(&next) || visited[self.index(next)] {
+                    continue;
+                }
+                visited[self.index(next)] = true;
+                to_visit.push_back(next);
+            }
+        }
+        visited
+    }
+}
+
+// Builds a level backwards: blocks start on their goals and the player pulls them
+// away, so every generated level is solvable by pushing them back.
+pub fn generate_level(seed: u64, theme: Theme) -> Vec<Vec<i32>> {
+    (0..MAX_ATTEMPTS)
+        .find_map(|attempt| try_generate(&mut Rng::new(seed.wrapping_add(attempt)), theme))
+        .expect("Could not generate a level")
+}
+
+fn try_generate(rng: &mut Rng, theme: Theme) -> Option<Vec<Vec<i32>>> {
+    let (width, height, wall_chance, block_count, pulls) = theme.parameters();
+    let mut grid = Grid {
+        width,
+        height,
+        walls: vec![true; (width * height) as usize],
+    };
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let index = grid.index((x, y));
+            grid.walls[index] = rng.chance(wall_chance);
+        }
+    }
+
+    let floors: Vec<(i32, i32)> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|position| grid.is_floor(*position))
+        .collect();
+    if floors.is_empty() {
+        return None;
+    }
+    let mut player = floors[rng.below(floors.len())];
+
+    // Wall off any floor the player could never reach.
+    let reachable = grid.reachable(player, &[]);
+    for (wall, reachable) in grid.walls.iter_mut().zip(reachable) {
+        *wall |= !reachable;
+    }
+    let mut floors: Vec<(i32, i32)> = floors
+        .into_iter()
+        .filter(|position| grid.is_floor(*position) && *position != player)
+        .collect();
+    if floors.len() < block_count * 3 + 4 {
+        return None;
+    }
+
+    let mut goals = Vec::new();
+    for _ in 0..block_count {
+        goals.push(floors.swap_remove(rng.below(floors.len())));
+    }
+    let mut blocks = goals.clone();
+
+    for _ in 0..pulls {
+        let reachable = grid.reachable(player, &blocks);
+        let block_index = rng.below(blocks.len());
+        let (dx, dy) = DIRECTIONS[rng.below(DIRECTIONS.len())];
+        let (block_x, block_y) = blocks[block_index];
+        let stand = (block_x + dx, block_y + dy);
+        let step = (block_x + dx * 2, block_y + dy * 2);
+        if !grid.is_floor(stand)
+            || !grid.is_floor(step)
+            || !reachable[grid.index(stand)]
+            || blocks.contains(&step)
+        {
+            continue;
+        }
+        blocks[block_index] = stand;
+        player = step;
+    }
+
+    // Block-on-goal and player-on-goal tiles can't be written in the level format.
+    if blocks.iter().any(|block| goals.contains(block)) {
+        return None;
+    }
+    if goals.contains(&player) {
+        let reachable = grid.reachable(player, &blocks);
+        let (x, y) = DIRECTIONS
+            .iter()
+            .map(|(dx, dy)| (player.0 + dx, player.1 + dy))
+            .find(|next| {
+                grid.is_floor(*next)
+                    && reachable[grid.index(*next)]
+                    && !blocks.contains(next)
+                    && !goals.contains(next)
+            })?;
+        player = (x, y);
+    }
+
+    let mut layout = vec![vec![0; width as usize]; height as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let tile = if !grid.is_floor((x, y)) {
+                8
+            } else if (x, y) == player {
+                1
+            } else if blocks.contains(&(x, y)) {
+                2
+            } else if goals.contains(&(x, y)) {
+                4
+            } else {
+                0
+            };
+            layout[y as usize][x as usize] = tile;
+        }
+    }
+    Some(layout)
+}
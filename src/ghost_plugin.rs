@@ -0,0 +1,128 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    play_plugin::{LevelLoadedEvent, LevelPack, MoveResolved, MovementTuning},
+    replay_plugin::BestReplays,
+    sokoban_core::{step_direction, Board},
+    theme_plugin::SkinTextures,
+    GameState, LevelEntity, Position,
+};
+
+const GHOST_ALPHA: f32 = 0.35;
+
+// Races a see-through copy of the player along its best solution to the level.
+pub struct GhostPlugin;
+
+// The ghost is off unless turned on from the pause menu.
+#[derive(Resource, Default)]
+pub struct GhostSetting {
+    pub enabled: bool,
+}
+
+#[derive(Component)]
+struct Ghost {
+    path: Vec<Position>,
+    step: usize,
+    // Waits for the player's first move so both start together.
+    started: bool,
+    timer: Timer,
+}
+
+pub fn ghost_text(ghost_setting: &GhostSetting) -> String {
+    let state = if ghost_setting.enabled { "on" } else { "off" };
+    format!("H: ghost of your best solution ({})", state)
+}
+
+// Where the player stands after each move, only walking and pushing are followed.
+fn ghost_path(start: Position, moves: &str) -> Vec<Position> {
+    let mut path = vec![start];
+    for (move_x, move_y) in moves.chars().filter_map(step_direction) {
+        let last = path[path.len() - 1];
+        path.push(last.add(move_x, move_y));
+    }
+    path
+}
+
+fn spawn_ghost(
+    mut commands: Commands,
+    ghost_setting: Res<GhostSetting>,
+    best_replays: Res<BestReplays>,
+    level_pack: Res<LevelPack>,
+    movement_tuning: Res<MovementTuning>,
+    skin_textures: SkinTextures,
+    mut level_loaded_reader: EventReader<LevelLoadedEvent>,
+) {
+    let Some(level_loaded) = level_loaded_reader.read().last() else {
+        return;
+    };
+    if !ghost_setting.enabled {
+        return;
+    }
+    let Some(moves) = best_replays.0.get(&level_loaded.hash) else {
+        return;
+    };
+    let Some((_, start, _)) = level_pack
+        .level(level_loaded.level)
+        // The ghost walks each step straight, so it only races on plain levels.
+        .and_then(|layout| Board::parse(&layout).ok())
+    else {
+        return;
+    };
+    commands.spawn((
+        LevelEntity,
+        Ghost {
+            path: ghost_path(start, moves),
+            step: 0,
+            started: false,
+            timer: Timer::from_seconds(movement_tuning.walk_seconds, TimerMode::Repeating),
+        },
+        SpriteBundle {
+            texture: skin_textures.load("player.png"),
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, GHOST_ALPHA),
+                anchor: Anchor::TopLeft,
+                ..default()
+            },
+            // Just under the player, so the player stays on top when they overlap.
+            transform: Transform::from_translation(start.to_translation_z(0.9)),
+            ..default()
+        },
+    ));
+}
+
+fn move_ghosts(
+    time: Res<Time>,
+    ghost_setting: Res<GhostSetting>,
+    mut move_resolved_reader: EventReader<MoveResolved>,
+    mut ghost_query: Query<(&mut Ghost, &mut Transform, &mut Visibility)>,
+) {
+    let moved = move_resolved_reader
+        .read()
+        .any(|move_resolved| !move_resolved.blocked);
+    for (mut ghost, mut transform, mut visibility) in &mut ghost_query {
+        *visibility = if ghost_setting.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        ghost.started |= moved;
+        let last_step = ghost.path.len() - 1;
+        if !ghost.started || ghost.step >= last_step {
+            continue;
+        }
+        ghost.timer.tick(time.delta());
+        ghost.step = (ghost.step + ghost.timer.times_finished_this_tick() as usize).min(last_step);
+        let from = ghost.path[ghost.step].to_translation_z(transform.translation.z);
+        let to = ghost.path[(ghost.step + 1).min(last_step)].to_translation_z(from.z);
+        transform.translation = from.lerp(to, ghost.timer.percent());
+    }
+}
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GhostSetting::default()).add_systems(
+            Update,
+            (spawn_ghost, move_ghosts.after(spawn_ghost)).run_if(in_state(GameState::Playing)),
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+
+use crate::{play_plugin::LevelState, GameState};
+
+pub struct GoalProgressPlugin;
+
+// How many goals have the right block on them, kept up to date as moves, undos and
+// rewinds change the board.
+#[derive(Resource, Default, PartialEq, Debug)]
+pub struct GoalProgress {
+    pub covered: usize,
+    pub total: usize,
+}
+
+#[derive(Component)]
+struct GoalProgressText;
+
+fn track_goal_progress(level_state: Res<LevelState>, mut goal_progress: ResMut<GoalProgress>) {
+    if !level_state.is_changed() {
+        return;
+    }
+    let progress = GoalProgress {
+        covered: level_state.covered_goals(),
+        total: level_state.goals.len(),
+    };
+    goal_progress.set_if_neq(progress);
+}
+
+fn show_goal_progress(mut commands: Commands) {
+    commands.spawn((
+        GoalProgressText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+fn hide_goal_progress(mut commands: Commands, text_query: Query<Entity, With<GoalProgressText>>) {
+    for entity in text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn update_goal_progress_text(
+    goal_progress: Res<GoalProgress>,
+    mut text_query: Query<&mut Text, With<GoalProgressText>>,
+) {
+    for mut text in &mut text_query {
+        text.sections[0].value = match goal_progress.total {
+            0 => String::new(),
+            1 => format!("{}/1 block placed", goal_progress.covered),
+            total => format!("{}/{} blocks placed", goal_progress.covered, total),
+        };
+    }
+}
+
+impl Plugin for GoalProgressPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GoalProgress::default())
+            .add_systems(OnEnter(GameState::Playing), show_goal_progress)
+            .add_systems(OnExit(GameState::Playing), hide_goal_progress)
+            .add_systems(
+                Update,
+                (
+                    track_goal_progress,
+                    update_goal_progress_text.after(track_goal_progress),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
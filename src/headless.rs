@@ -0,0 +1,418 @@
+// The real play rules run without a window or renderer, so push rules, undo and winning can be
+// checked from tests and scripts. Only built with `--features headless`.
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    campaign_plugin::{Campaign, CAMPAIGN_DIRECTORY},
+    demo_plugin::Demo,
+    pause_plugin::MovePreviewSetting,
+    play_plugin::{
+        FixedTimestepSetting, HistoryEvent, LevelPack, LevelState, LoadLevelEvent, MoveRequested,
+        PlayPlugin, Player,
+    },
+    sokoban_core::step_direction,
+    tiles::Theme,
+    transition_plugin::TransitionState,
+    GameState,
+};
+
+// How far the clock moves each frame, small enough that every move takes a few frames.
+const FRAME_SECONDS: f32 = 1.0 / 60.0;
+
+// Gives up on a move that never lands, rather than spinning forever.
+const MAX_FRAMES_PER_MOVE: usize = 1000;
+
+// A win only changes the state on the frame after it's noticed, so a move has only settled
+// once this many frames in a row have nothing moving and no state change waiting.
+const QUIET_FRAMES: usize = 2;
+
+pub struct Simulation {
+    app: App,
+}
+
+impl Simulation {
+    pub fn new(level_layout: Vec<Vec<i32>>) -> Self {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                FRAME_SECONDS,
+            )))
+            .add_state::<GameState>()
+            .insert_resource(NextState(Some(GameState::Playing)))
+            // Nothing presses keys, these are only here for the systems that read them.
+            .insert_resource(Input::<KeyCode>::default())
+            .insert_resource(MovePreviewSetting::default())
+            .insert_resource(Demo::default())
+            .insert_resource(TransitionState::default())
+            .insert_resource(Theme::default())
+            .insert_resource(LevelPack {
+                name: "Simulation".to_string(),
+                levels: vec![level_layout],
+                metadata: Vec::new(),
+                return_state: GameState::Playing,
+            })
+            .add_plugins(PlayPlugin)
+            // Moves play out the same however the frames happen to fall.
+            .insert_resource(FixedTimestepSetting { enabled: true });
+        app.world.send_event(LoadLevelEvent(1));
+        app.update();
+        Self { app }
+    }
+
+    // Plays a LURD move string a step at a time. Steps into walls go nowhere, just like
+    // pressing an arrow at one, so check `level_state().moves` for what actually happened.
+    pub fn play(&mut self, moves: &str) -> Result<(), String> {
+        for (index, step) in moves.chars().enumerate() {
+            let Some(direction) = step_direction(step) else {
+                return Err(format!("Move {} is not one of LURD", index + 1));
+            };
+            if self.is_won() {
+                return Err(format!("Move {} comes after the level was won", index + 1));
+            }
+            self.app.world.send_event(MoveRequested { direction });
+            self.settle()?;
+        }
+        Ok(())
+    }
+
+    // Undo and redo ease things back into place, so these wait for that like moves do.
+    pub fn undo(&mut self) -> Result<(), String> {
+        self.app.world.send_event(HistoryEvent::Undo);
+        self.settle()
+    }
+
+    pub fn redo(&mut self) -> Result<(), String> {
+        self.app.world.send_event(HistoryEvent::Redo);
+        self.settle()
+    }
+
+    pub fn level_state(&self) -> &LevelState {
+        self.app.world.resource::<LevelState>()
+    }
+
+    // Winning moves the game on to the level complete screen, same as when playing.
+    pub fn is_won(&self) -> bool {
+        *self.app.world.resource::<State<GameState>>().get() == GameState::LevelComplete
+    }
+
+    // Runs frames until every move, slide and undo has landed, and what came of it has been
+    // recorded and any change of state has gone through.
+    fn settle(&mut self) -> Result<(), String> {
+        let mut quiet_frames = 0;
+        for _ in 0..MAX_FRAMES_PER_MOVE {
+            self.app.update();
+            let mut player_query = self.app.world.query::<&Player>();
+            let moving = player_query
+                .iter(&self.app.world)
+                .any(|player| player.is_moving);
+            let changing_state = self
+                .app
+                .world
+                .resource::<NextState<GameState>>()
+                .0
+                .is_some();
+            if moving || changing_state {
+                quiet_frames = 0;
+            } else {
+                quiet_frames += 1;
+            }
+            if quiet_frames == QUIET_FRAMES {
+                return Ok(());
+            }
+        }
+        Err("A move never finished".to_string())
+    }
+}
+
+// For `--simulate <level> <moves>`: plays a campaign level headlessly.
+pub fn simulate_campaign_level(level: i32, moves: &str) -> Result<String, String> {
+    let campaign = Campaign::load(std::path::Path::new(CAMPAIGN_DIRECTORY))?;
+    let Some(campaign_level) = campaign.level(level) else {
+        return Err(format!("There is no level {}", level));
+    };
+    let mut simulation = Simulation::new(campaign_level.layout.clone());
+    simulation.play(moves)?;
+    let level_state = simulation.level_state();
+    Ok(format!(
+        "Level {} after {} moves: {}",
+        level,
+        level_state.moves.len(),
+        if simulation.is_won() {
+            "solved"
+        } else {
+            "not solved"
+        }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Obstacle, Position};
+
+    fn simulation(layout: &[&[i32]]) -> Simulation {
+        Simulation::new(layout.iter().map(|row| row.to_vec()).collect())
+    }
+
+    // A single row walled in on every side. Each row keeps a goal no block ends up on, so the
+    // level isn't won partway through a test.
+    fn corridor(row: &[i32]) -> Simulation {
+        let walls = vec![8; row.len() + 2];
+        let row = [&[8][..], row, &[8]].concat();
+        Simulation::new(vec![walls.clone(), row, walls])
+    }
+
+    fn block_at(simulation: &Simulation, position: Position) -> bool {
+        matches!(
+            simulation.level_state().obstacles.get(&position),
+            Some((_, Obstacle::Block(_)))
+        )
+    }
+
+    #[test]
+    fn pushes_a_block() {
+        let mut simulation = simulation(&[&[8, 8, 8, 8, 8], &[8, 1, 2, 0, 8], &[8, 8, 8, 8, 8]]);
+
+        simulation.play("R").unwrap();
+
+        assert_eq!(simulation.level_state().moves, "R");
+        assert_eq!(
+            simulation.level_state().player_position(),
+            Position { x: 2, y: 1 }
+        );
+        assert!(block_at(&simulation, Position { x: 3, y: 1 }));
+    }
+
+    #[test]
+    fn walls_stop_the_block() {
+        let mut simulation = simulation(&[&[8, 8, 8, 8], &[8, 1, 2, 8], &[8, 8, 8, 8]]);
+
+        simulation.play("r").unwrap();
+
+        assert_eq!(simulation.level_state().moves, "");
+        assert!(block_at(&simulation, Position { x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn undo_and_redo_the_push() {
+        let mut simulation = corridor(&[1, 2, 0, 4]);
+        simulation.play("r").unwrap();
+
+        simulation.undo().unwrap();
+        assert_eq!(simulation.level_state().moves, "");
+        assert!(block_at(&simulation, Position { x: 2, y: 1 }));
+
+        simulation.redo().unwrap();
+        assert_eq!(simulation.level_state().moves, "R");
+        assert!(block_at(&simulation, Position { x: 3, y: 1 }));
+    }
+
+    #[test]
+    fn wins_once_every_goal_is_covered() {
+        let mut simulation = simulation(&[
+            &[8, 8, 8, 8, 8, 8],
+            &[8, 1, 2, 0, 4, 8],
+            &[8, 8, 8, 8, 8, 8],
+        ]);
+
+        simulation.play("R").unwrap();
+        assert!(!simulation.is_won());
+
+        simulation.play("R").unwrap();
+        assert!(simulation.is_won());
+        assert!(simulation.level_state().is_solved());
+    }
+
+    #[test]
+    fn blocks_that_start_on_goals_count_towards_winning() {
+        let mut simulation = simulation(&[
+            &[8, 8, 8, 8, 8, 8],
+            &[8, 6, 1, 2, 4, 8],
+            &[8, 8, 8, 8, 8, 8],
+        ]);
+        assert!(block_at(&simulation, Position { x: 1, y: 1 }));
+
+        simulation.play("R").unwrap();
+        assert!(simulation.is_won());
+    }
+
+    #[test]
+    fn the_player_can_start_on_a_goal() {
+        let mut simulation = simulation(&[
+            &[8, 8, 8, 8, 8, 8],
+            &[8, 5, 2, 0, 8, 8],
+            &[8, 8, 8, 8, 8, 8],
+        ]);
+        assert_eq!(
+            simulation.level_state().player_position(),
+            Position { x: 1, y: 1 }
+        );
+
+        simulation.play("Rl").unwrap();
+        assert_eq!(simulation.level_state().moves, "Rl");
+        assert!(!simulation.is_won());
+    }
+
+    // Only a block of the goal's color covers it, not a wall or any other block.
+    #[test]
+    fn only_matching_blocks_cover_goals() {
+        let goal = Position { x: 1, y: 1 };
+        let mut level_state = LevelState::default();
+        level_state.goals.insert(goal, (Entity::PLACEHOLDER, 1));
+
+        level_state.walls.insert(goal);
+        assert!(!level_state.is_solved());
+        level_state.walls.clear();
+
+        level_state
+            .obstacles
+            .insert(goal, (Entity::PLACEHOLDER, Obstacle::Block(0)));
+        assert!(!level_state.is_solved());
+
+        level_state
+            .obstacles
+            .insert(goal, (Entity::PLACEHOLDER, Obstacle::Block(1)));
+        assert!(level_state.is_solved());
+    }
+
+    // 32769 is an enemy setting off to the right, it turns straight back at the wall.
+    #[test]
+    fn an_enemy_catches_the_player_and_undo_escapes() {
+        let mut simulation = corridor(&[1, 0, 4, 0, 32769]);
+        let enemy_at = |simulation: &Simulation| {
+            let enemies = &simulation.level_state().enemies;
+            enemies.values().next().unwrap().position
+        };
+
+        simulation.play("r").unwrap();
+        assert_eq!(enemy_at(&simulation), Position { x: 4, y: 1 });
+        assert!(!simulation.level_state().caught);
+
+        simulation.play("r").unwrap();
+        assert_eq!(enemy_at(&simulation), Position { x: 3, y: 1 });
+        assert!(simulation.level_state().caught);
+
+        // Nothing moves once caught.
+        simulation.play("l").unwrap();
+        assert_eq!(simulation.level_state().moves, "rr");
+
+        simulation.undo().unwrap();
+        assert_eq!(enemy_at(&simulation), Position { x: 4, y: 1 });
+        assert!(!simulation.level_state().caught);
+        simulation.play("l").unwrap();
+        assert_eq!(simulation.level_state().moves, "rl");
+    }
+
+    // 65537 is a key and 65553 a locked door, both of color 1, and 65554 a door of color 2.
+    #[test]
+    fn a_key_opens_its_door_and_undo_locks_it_again() {
+        let mut simulation = corridor(&[1, 65537, 65553, 65554, 4]);
+        let door = Position { x: 3, y: 1 };
+
+        simulation.play("r").unwrap();
+        assert_eq!(simulation.level_state().inventory, vec![1]);
+
+        simulation.play("rr").unwrap();
+        assert_eq!(simulation.level_state().moves, "rr");
+        assert!(simulation.level_state().inventory.is_empty());
+        assert!(!simulation.level_state().locked_doors.contains_key(&door));
+
+        simulation.undo().unwrap();
+        assert_eq!(simulation.level_state().inventory, vec![1]);
+        assert!(simulation.level_state().locked_doors.contains_key(&door));
+
+        simulation.undo().unwrap();
+        assert!(simulation.level_state().inventory.is_empty());
+        assert!(simulation
+            .level_state()
+            .keys
+            .contains_key(&Position { x: 2, y: 1 }));
+    }
+
+    // 131072 is fragile floor.
+    #[test]
+    fn fragile_floor_breaks_behind_the_player() {
+        let mut simulation = corridor(&[1, 131072, 0, 4]);
+        let fragile = Position { x: 2, y: 1 };
+
+        simulation.play("r").unwrap();
+        assert!(simulation.level_state().fragile.contains_key(&fragile));
+
+        simulation.play("rl").unwrap();
+        assert_eq!(simulation.level_state().moves, "rr");
+        assert!(matches!(
+            simulation.level_state().obstacles.get(&fragile),
+            Some((_, Obstacle::Pit))
+        ));
+
+        simulation.undo().unwrap();
+        assert!(simulation.level_state().fragile.contains_key(&fragile));
+        simulation.play("l").unwrap();
+        assert_eq!(simulation.level_state().moves, "rl");
+    }
+
+    // 262144 is a pair of portals with a wall between them.
+    #[test]
+    fn blocks_and_the_player_come_out_past_the_twin_portal() {
+        let mut simulation = corridor(&[1, 2, 262144, 8, 262144, 0, 0, 4]);
+
+        simulation.play("R").unwrap();
+        assert!(block_at(&simulation, Position { x: 6, y: 1 }));
+        assert_eq!(
+            simulation.level_state().player_position(),
+            Position { x: 2, y: 1 }
+        );
+
+        simulation.play("R").unwrap();
+        assert!(block_at(&simulation, Position { x: 7, y: 1 }));
+        assert_eq!(
+            simulation.level_state().player_position(),
+            Position { x: 6, y: 1 }
+        );
+
+        simulation.undo().unwrap();
+        assert!(block_at(&simulation, Position { x: 6, y: 1 }));
+        assert_eq!(
+            simulation.level_state().player_position(),
+            Position { x: 2, y: 1 }
+        );
+    }
+
+    // 16384 is a heavy block, it only gives on the second push in a row.
+    #[test]
+    fn a_heavy_block_strains_before_it_gives_and_undo_keeps_the_strain() {
+        let mut simulation = corridor(&[1, 16384, 0, 0, 4]);
+        let heavy = Position { x: 2, y: 1 };
+
+        simulation.play("r").unwrap();
+        assert_eq!(simulation.level_state().moves, "R");
+        assert!(block_at(&simulation, heavy));
+        assert_eq!(simulation.level_state().strained.len(), 1);
+
+        simulation.undo().unwrap();
+        assert_eq!(simulation.level_state().moves, "");
+        assert!(simulation.level_state().strained.is_empty());
+
+        simulation.play("rr").unwrap();
+        assert_eq!(simulation.level_state().moves, "RR");
+        assert!(block_at(&simulation, Position { x: 3, y: 1 }));
+        assert!(simulation.level_state().strained.is_empty());
+
+        // Undoing the push leaves the block strained, so pushing again moves it straight away.
+        simulation.undo().unwrap();
+        assert!(block_at(&simulation, heavy));
+        assert_eq!(simulation.level_state().strained.len(), 1);
+        simulation.play("r").unwrap();
+        assert_eq!(simulation.level_state().moves, "RR");
+        assert!(block_at(&simulation, Position { x: 3, y: 1 }));
+    }
+
+    #[test]
+    fn rejects_steps_that_are_not_lurd() {
+        let mut simulation = simulation(&[&[8, 8, 8, 8], &[8, 1, 0, 8], &[8, 8, 8, 8]]);
+
+        assert!(simulation.play("rx").is_err());
+    }
+}
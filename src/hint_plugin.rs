@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+
+use crate::{
+    play_plugin::LevelState,
+    sokoban_core::step_direction,
+    solver::{is_solver_level, solve_after},
+    GameState, LevelEntity, TILE_SIZE,
+};
+
+const HINT_KEY: KeyCode = KeyCode::Slash;
+// How many of the solution's moves are shown ahead of the player.
+const HINT_MOVES: usize = 3;
+const MAX_HINT_STATES: usize = 200_000;
+const WALK_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.6);
+const PUSH_COLOR: Color = Color::rgba(1.0, 0.6, 0.1, 0.8);
+
+// Draws arrows for the next few moves of a solution from wherever the player is. Only
+// levels the solver understands get hints.
+pub struct HintPlugin;
+
+// A solution worked out once the player had made `from_moves`, kept for as long as the
+// player follows it.
+struct CachedSolution {
+    layout: Vec<Vec<i32>>,
+    from_moves: String,
+    // `None` when there wasn't one to be found from there.
+    solution: Option<String>,
+}
+
+#[derive(Resource, Default)]
+struct Hint {
+    shown: bool,
+    cached: Option<CachedSolution>,
+}
+
+impl Hint {
+    // What's left of the cached solution, if the player hasn't strayed from it.
+    fn upcoming(&self, level_state: &LevelState) -> Option<Option<&str>> {
+        let cached = self.cached.as_ref()?;
+        if cached.layout != level_state.layout {
+            return None;
+        }
+        let followed = level_state.moves.strip_prefix(cached.from_moves.as_str())?;
+        match &cached.solution {
+            Some(solution) => solution.strip_prefix(followed).map(Some),
+            None if followed.is_empty() => Some(None),
+            None => None,
+        }
+    }
+}
+
+#[derive(Component)]
+struct HintArrow;
+
+fn toggle_hint(keyboard_input: Res<Input<KeyCode>>, mut hint: ResMut<Hint>) {
+    if keyboard_input.just_pressed(HINT_KEY) {
+        hint.shown = !hint.shown;
+    }
+}
+
+// A bar from the middle of one tile to the middle of the next.
+fn hint_arrow(
+    from: Vec3,
+    (x, y): (i32, i32),
+    push: bool,
+) -> (LevelEntity, HintArrow, SpriteBundle) {
+    let size = if x != 0 {
+        Vec2::new(TILE_SIZE, 4.0)
+    } else {
+        Vec2::new(4.0, TILE_SIZE)
+    };
+    let middle = from + Vec3::new(TILE_SIZE / 2.0, -TILE_SIZE / 2.0, 0.0);
+    let translation = middle + Vec3::new(x as f32, -y as f32, 0.0) * TILE_SIZE / 2.0;
+    (
+        LevelEntity,
+        HintArrow,
+        SpriteBundle {
+            sprite: Sprite {
+                color: if push { PUSH_COLOR } else { WALK_COLOR },
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_translation(translation.truncate().extend(3.0)),
+            ..default()
+        },
+    )
+}
+
+// Solves again only when the player leaves the cached solution, then redraws the arrows
+// whenever the board changes.
+fn show_hint(
+    mut commands: Commands,
+    mut hint: ResMut<Hint>,
+    level_state: Res<LevelState>,
+    arrow_query: Query<Entity, With<HintArrow>>,
+) {
+    if !hint.is_changed() && !level_state.is_changed() {
+        return;
+    }
+    for entity in &arrow_query {
+        commands.entity(entity).despawn();
+    }
+    if !hint.shown || !is_solver_level(&level_state.layout) {
+        return;
+    }
+    if hint.upcoming(&level_state).is_none() {
+        let (solution, _) = solve_after(&level_state.layout, &level_state.moves, MAX_HINT_STATES);
+        // Caching it isn't a change worth redrawing for.
+        hint.bypass_change_detection().cached = Some(CachedSolution {
+            layout: level_state.layout.clone(),
+            from_moves: level_state.moves.clone(),
+            solution,
+        });
+    }
+    let Some(Some(upcoming)) = hint.upcoming(&level_state) else {
+        return;
+    };
+
+    let mut position = level_state.player_position();
+    for step in upcoming.chars().take(HINT_MOVES) {
+        let Some(direction) = step_direction(step) else {
+            break;
+        };
+        commands.spawn(hint_arrow(
+            position.to_translation(),
+            direction,
+            step.is_ascii_uppercase(),
+        ));
+        position = position.add(direction.0, direction.1);
+    }
+}
+
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Hint::default()).add_systems(
+            Update,
+            (toggle_hint, show_hint.after(toggle_hint)).run_if(in_state(GameState::Playing)),
+        );
+    }
+}
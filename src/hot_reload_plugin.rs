@@ -0,0 +1,187 @@
+use std::path::Path;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+
+use crate::{
+    campaign_plugin::{Campaign, CAMPAIGN_DIRECTORY},
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{LevelPack, LevelState, NextLevelEvent, CAMPAIGN_PACK},
+    GameState,
+};
+
+// Where the campaign sits relative to the asset folder.
+const CAMPAIGN_ASSET_DIRECTORY: &str = "campaign";
+
+// For level designers: run with `--features hot_reload` and saving a campaign level in another
+// editor offers to reload it in place with F5.
+pub struct HotReloadPlugin;
+
+// A campaign level file, loaded through the asset server only so it's watched for changes.
+#[derive(Asset, TypePath)]
+struct LevelFile;
+
+#[derive(Default)]
+struct LevelFileLoader;
+
+impl AssetLoader for LevelFileLoader {
+    type Asset = LevelFile;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<LevelFile, std::io::Error>> {
+        Box::pin(async move {
+            let mut contents = Vec::new();
+            reader.read_to_end(&mut contents).await?;
+            Ok(LevelFile)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+#[derive(Resource, Default)]
+struct HotReload {
+    // Kept so the files stay loaded, and watched.
+    handles: Vec<Handle<LevelFile>>,
+    // The file that changed since the level was last reloaded.
+    changed: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Component)]
+struct ReloadPromptText;
+
+fn watch_level_files(
+    asset_server: Res<AssetServer>,
+    campaign: Res<Campaign>,
+    mut hot_reload: ResMut<HotReload>,
+) {
+    hot_reload.handles = campaign
+        .levels()
+        .map(|level| asset_server.load(format!("{}/{}", CAMPAIGN_ASSET_DIRECTORY, level.file)))
+        .collect();
+}
+
+fn spawn_reload_prompt(mut commands: Commands) {
+    commands.spawn((
+        ReloadPromptText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::YELLOW,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.8)),
+        ZIndex::Global(12),
+        Visibility::Hidden,
+    ));
+}
+
+fn detect_level_file_changes(
+    asset_server: Res<AssetServer>,
+    mut level_file_events: EventReader<AssetEvent<LevelFile>>,
+    mut hot_reload: ResMut<HotReload>,
+) {
+    for level_file_event in level_file_events.read() {
+        let AssetEvent::Modified { id } = level_file_event else {
+            continue;
+        };
+        let Some(path) = asset_server.get_path(*id) else {
+            continue;
+        };
+        info!("{} changed on disk", path);
+        hot_reload.changed = Some(path.to_string());
+        hot_reload.error = None;
+    }
+}
+
+fn reload_level(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    level_state: Res<LevelState>,
+    level_pack: Res<LevelPack>,
+    mut hot_reload: ResMut<HotReload>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+) {
+    if hot_reload.changed.is_none() || !keyboard_input.just_pressed(KeyCode::F5) {
+        return;
+    }
+    let campaign = match Campaign::load(Path::new(CAMPAIGN_DIRECTORY)) {
+        Ok(campaign) => campaign,
+        Err(error) => {
+            hot_reload.error = Some(error);
+            return;
+        }
+    };
+    // Only the level's number carries over, everything else starts again from the file.
+    if level_pack.name == CAMPAIGN_PACK {
+        let pack = LevelPack::campaign(&campaign);
+        if pack.level(level_state.current_level).is_some() {
+            next_level_writer.send(NextLevelEvent(level_state.current_level));
+        }
+        commands.insert_resource(pack);
+    }
+    commands.insert_resource(campaign);
+    hot_reload.changed = None;
+    hot_reload.error = None;
+}
+
+fn update_reload_prompt(
+    hot_reload: Res<HotReload>,
+    mut prompt_query: Query<(&mut Text, &mut Visibility), With<ReloadPromptText>>,
+) {
+    if !hot_reload.is_changed() {
+        return;
+    }
+    let Ok((mut text, mut visibility)) = prompt_query.get_single_mut() else {
+        return;
+    };
+    let Some(changed) = &hot_reload.changed else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    text.sections[0].value = match &hot_reload.error {
+        Some(error) => format!("Could not reload: {}", error),
+        None => format!("{} changed. F5: reload the level", changed),
+    };
+    *visibility = Visibility::Inherited;
+}
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LevelFile>()
+            .init_asset_loader::<LevelFileLoader>()
+            .insert_resource(HotReload::default())
+            .add_systems(Startup, (watch_level_files, spawn_reload_prompt))
+            .add_systems(
+                Update,
+                (
+                    detect_level_file_changes,
+                    reload_level
+                        .after(detect_level_file_changes)
+                        .run_if(in_state(GameState::Playing))
+                        .run_if(quit_prompt_closed),
+                    update_reload_prompt.after(reload_level),
+                ),
+            );
+    }
+}
@@ -0,0 +1,193 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    debug_plugin::debug_overlay_on,
+    juice_plugin::{Bump, Thud},
+    play_plugin::{
+        Block, BoardChangedEvent, Goal, HistoryEvent, LevelLoadedEvent, LevelState, Moving, Shake,
+    },
+    sokoban_core::{cell_from_code, Tile},
+    Obstacle, Position,
+};
+
+// Debug builds, and release builds while the debug overlay is up, re-check the board after
+// everything that rewrites it and name the culprit when something no longer adds up.
+pub struct InvariantsPlugin;
+
+// Where things are drawn, and whether they're animating away from their tile.
+type DrawnQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Transform,
+        Has<Moving>,
+        Has<Shake>,
+        Has<Thud>,
+        Has<Bump>,
+    ),
+>;
+
+// Every block and goal that was spawned, whether or not the map still knows about it.
+type SpawnedQuery<'w, 's> = Query<'w, 's, (Entity, Has<Block>), Or<(With<Block>, With<Goal>)>>;
+
+fn board_violations(
+    level_state: &LevelState,
+    transform_query: &DrawnQuery,
+    spawned_query: &SpawnedQuery,
+) -> Vec<String> {
+    let mut violations = Vec::new();
+
+    // Walkers can't share a tile with each other or stand on an obstacle.
+    let mut walkers: Vec<(&str, Entity, Position)> = level_state
+        .players
+        .iter()
+        .map(|(entity, position)| ("player", *entity, *position))
+        .collect();
+    let mut occupied = HashSet::new();
+    for (name, _, position) in &walkers {
+        if !occupied.insert(*position) {
+            violations.push(format!("two walkers on {:?}", position));
+        }
+        if level_state.walls.contains(position) {
+            violations.push(format!("{} in a wall at {:?}", name, position));
+        }
+        if let Some((_, obstacle)) = level_state.obstacles.get(position) {
+            violations.push(format!("{} on a {:?} at {:?}", name, obstacle, position));
+        }
+    }
+    if let Some(mirror_position) = level_state.mirror_position {
+        if occupied.contains(&mirror_position) {
+            violations.push(format!("mirror on a player at {:?}", mirror_position));
+        }
+        if level_state.walls.contains(&mirror_position)
+            || level_state.obstacles.contains_key(&mirror_position)
+        {
+            violations.push(format!("mirror on an obstacle at {:?}", mirror_position));
+        }
+    }
+
+    // Everything the map places has to be drawn where the map says, unless it's
+    // still animating there.
+    walkers.extend(
+        level_state
+            .obstacles
+            .iter()
+            .map(|(position, (entity, _))| ("obstacle", *entity, *position)),
+    );
+    for (name, entity, position) in walkers {
+        match transform_query.get(entity) {
+            Ok((transform, moving, shaking, thudding, bumping)) => {
+                let animating = moving || shaking || thudding || bumping;
+                if !animating
+                    && transform.translation.truncate() != position.to_translation().truncate()
+                {
+                    violations.push(format!(
+                        "{} {:?} is drawn away from {:?}",
+                        name, entity, position
+                    ));
+                }
+            }
+            Err(_) => violations.push(format!("{} {:?} at {:?} is gone", name, entity, position)),
+        }
+    }
+
+    let tiles = || {
+        level_state
+            .layout
+            .iter()
+            .flatten()
+            .flat_map(|tile| cell_from_code(*tile))
+    };
+    let goal_tiles = tiles().filter(|tile| matches!(tile, Tile::Goal(_))).count();
+    if level_state.goals.len() != goal_tiles {
+        violations.push(format!(
+            "{} goals tracked but the layout has {}",
+            level_state.goals.len(),
+            goal_tiles
+        ));
+    }
+    let block_tiles = tiles()
+        .filter(|tile| matches!(tile, Tile::Block(_) | Tile::HeavyBlock))
+        .count();
+    let blocks = level_state
+        .obstacles
+        .values()
+        .filter(|(_, obstacle)| matches!(obstacle, Obstacle::Block(_)))
+        .count()
+        + level_state.filled_pits.len();
+    if blocks != block_tiles {
+        violations.push(format!(
+            "{} blocks tracked but the layout has {}",
+            blocks, block_tiles
+        ));
+    }
+
+    // And nothing spawned has been dropped from the map.
+    let tracked: HashSet<Entity> = level_state
+        .obstacles
+        .values()
+        .map(|(entity, _)| *entity)
+        .chain(
+            level_state
+                .filled_pits
+                .iter()
+                .map(|(_, _, (entity, _), _)| *entity),
+        )
+        .chain(level_state.goals.values().map(|(entity, _)| *entity))
+        .collect();
+    for (entity, is_block) in spawned_query {
+        if !tracked.contains(&entity) {
+            let name = if is_block { "block" } else { "goal" };
+            violations.push(format!("{} {:?} isn't on the board", name, entity));
+        }
+    }
+
+    violations
+}
+
+fn check_board(
+    level_state: Res<LevelState>,
+    mut board_changed_reader: EventReader<BoardChangedEvent>,
+    mut history_reader: EventReader<HistoryEvent>,
+    mut level_loaded_reader: EventReader<LevelLoadedEvent>,
+    mut landed_reader: RemovedComponents<Moving>,
+    transform_query: DrawnQuery,
+    spawned_query: SpawnedQuery,
+) {
+    let mut sources: Vec<&str> = board_changed_reader
+        .read()
+        .map(|board_changed| board_changed.source)
+        .collect();
+    sources.extend(history_reader.read().map(|_| "history"));
+    sources.extend(level_loaded_reader.read().map(|_| "level load"));
+    // Undo and rewind change the map as they start, so sprites are only checked against it
+    // once they land.
+    if landed_reader.read().next().is_some() {
+        sources.push("landing");
+    }
+    if sources.is_empty() {
+        return;
+    }
+
+    for violation in board_violations(&level_state, &transform_query, &spawned_query) {
+        error!(
+            "Board invariant broken after {}: {}",
+            sources.join(", "),
+            violation
+        );
+    }
+}
+
+fn is_debug_build() -> bool {
+    cfg!(debug_assertions)
+}
+
+impl Plugin for InvariantsPlugin {
+    fn build(&self, app: &mut App) {
+        // Late enough for this frame's commands to have spawned and moved things.
+        app.add_systems(
+            PostUpdate,
+            check_board.run_if(is_debug_build.or_else(debug_overlay_on)),
+        );
+    }
+}
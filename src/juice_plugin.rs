@@ -0,0 +1,230 @@
+use std::time::Duration;
+
+use bevy::{
+    audio::{Pitch, PitchBundle},
+    prelude::*,
+};
+
+use crate::{
+    pause_plugin::JuiceIntensity,
+    play_plugin::{Block, LevelLoadedEvent, LevelState, MoveResolved, Moving},
+    GameState, MainCamera, TILE_SIZE,
+};
+
+pub struct JuicePlugin;
+
+const CAMERA_SHAKE_SECONDS: f32 = 0.2;
+const CAMERA_SHAKE_PIXELS: f32 = 2.0;
+const THUD_SECONDS: f32 = 0.12;
+const THUD_SQUASH: f32 = 0.12;
+const BUMP_SECONDS: f32 = 0.15;
+const BUMP_PIXELS: f32 = 3.0;
+const BUMP_PITCH_HZ: f32 = 90.0;
+
+// Jolts the camera when the player runs into something that won't move.
+#[derive(Component)]
+struct CameraShake {
+    timer: Timer,
+    // What the shake added last frame, taken off again before the next offset.
+    offset: Vec3,
+}
+
+// Flattens a block for a moment as it lands.
+#[derive(Component)]
+pub struct Thud {
+    timer: Timer,
+}
+
+// Nudges the player toward whatever stopped it and back, so a refused move is never
+// silently dropped.
+#[derive(Component)]
+pub struct Bump {
+    direction: (i32, i32),
+    timer: Timer,
+}
+
+fn start_camera_shake(
+    mut commands: Commands,
+    juice_intensity: Res<JuiceIntensity>,
+    mut move_resolved_reader: EventReader<MoveResolved>,
+    camera_query: Query<Entity, (With<MainCamera>, Without<CameraShake>)>,
+) {
+    if !move_resolved_reader.read().any(|resolved| resolved.blocked) {
+        return;
+    }
+    if juice_intensity.scale() == 0.0 {
+        return;
+    }
+    for camera in &camera_query {
+        commands.entity(camera).insert(CameraShake {
+            timer: Timer::from_seconds(CAMERA_SHAKE_SECONDS, TimerMode::Once),
+            offset: Vec3::ZERO,
+        });
+    }
+}
+
+fn shake_camera(
+    time: Res<Time>,
+    mut commands: Commands,
+    juice_intensity: Res<JuiceIntensity>,
+    mut level_loaded_reader: EventReader<LevelLoadedEvent>,
+    mut camera_query: Query<(Entity, &mut CameraShake, &mut Transform)>,
+) {
+    // A freshly loaded level has already put the camera where it belongs.
+    let recentered = level_loaded_reader.read().count() > 0;
+    for (entity, mut shake, mut transform) in &mut camera_query {
+        if !recentered {
+            transform.translation -= shake.offset;
+        }
+        shake.timer.tick(time.delta());
+        if recentered || shake.timer.finished() {
+            commands.entity(entity).remove::<CameraShake>();
+            continue;
+        }
+        let fade = 1.0 - shake.timer.percent();
+        let elapsed = shake.timer.elapsed_secs();
+        let strength = CAMERA_SHAKE_PIXELS * juice_intensity.scale() * fade;
+        shake.offset = Vec3::new(
+            (elapsed * 70.0).sin() * strength,
+            (elapsed * 53.0).cos() * strength,
+            0.0,
+        );
+        transform.translation += shake.offset;
+    }
+}
+
+// Heavy blocks that strain already shake, only moves that went nowhere bump.
+fn start_bumps(
+    mut commands: Commands,
+    juice_intensity: Res<JuiceIntensity>,
+    level_state: Res<LevelState>,
+    mut pitches: ResMut<Assets<Pitch>>,
+    mut move_resolved_reader: EventReader<MoveResolved>,
+) {
+    let Some(refused) = move_resolved_reader
+        .read()
+        .filter(|resolved| resolved.blocked && resolved.pushed.is_none())
+        .last()
+    else {
+        return;
+    };
+    let Some(mut player) = commands.get_entity(level_state.active_player) else {
+        return;
+    };
+    player.insert(Bump {
+        direction: refused.direction,
+        timer: Timer::from_seconds(BUMP_SECONDS, TimerMode::Once),
+    });
+    if juice_intensity.scale() > 0.0 {
+        commands.spawn(PitchBundle {
+            source: pitches.add(Pitch::new(
+                BUMP_PITCH_HZ,
+                Duration::from_secs_f32(BUMP_SECONDS),
+            )),
+            settings: PlaybackSettings::DESPAWN,
+        });
+    }
+}
+
+fn bump_players(
+    time: Res<Time>,
+    mut commands: Commands,
+    level_state: Res<LevelState>,
+    mut bump_query: Query<(Entity, &mut Bump, &mut Transform, Has<Moving>)>,
+) {
+    for (entity, mut bump, mut transform, is_moving) in &mut bump_query {
+        // A move that got going since takes over drawing the player.
+        let resting = level_state
+            .players
+            .get(&entity)
+            .map(|player| player.to_translation());
+        let (Some(resting), false) = (resting, is_moving) else {
+            commands.entity(entity).remove::<Bump>();
+            continue;
+        };
+        bump.timer.tick(time.delta());
+        if bump.timer.finished() {
+            transform.translation = resting;
+            commands.entity(entity).remove::<Bump>();
+            continue;
+        }
+        let reach = BUMP_PIXELS * (bump.timer.percent() * std::f32::consts::PI).sin();
+        transform.translation = resting
+            + Vec3::new(
+                bump.direction.0 as f32 * reach,
+                -bump.direction.1 as f32 * reach,
+                0.0,
+            );
+    }
+}
+
+fn start_thuds(
+    mut commands: Commands,
+    juice_intensity: Res<JuiceIntensity>,
+    mut stopped: RemovedComponents<Moving>,
+    block_query: Query<Entity, With<Block>>,
+) {
+    let stopped: Vec<Entity> = stopped.read().collect();
+    if juice_intensity.scale() == 0.0 {
+        return;
+    }
+    for block_entity in block_query.iter_many(&stopped) {
+        commands.entity(block_entity).insert(Thud {
+            timer: Timer::from_seconds(THUD_SECONDS, TimerMode::Once),
+        });
+    }
+}
+
+fn thud_blocks(
+    time: Res<Time>,
+    mut commands: Commands,
+    juice_intensity: Res<JuiceIntensity>,
+    level_state: Res<LevelState>,
+    mut thud_query: Query<(Entity, &mut Thud, &mut Transform), Without<Moving>>,
+) {
+    for (entity, mut thud, mut transform) in &mut thud_query {
+        let Some(position) = level_state
+            .obstacles
+            .iter()
+            .find(|(_, (block_entity, _))| *block_entity == entity)
+            .map(|(position, _)| *position)
+        else {
+            commands.entity(entity).remove::<Thud>();
+            continue;
+        };
+        thud.timer.tick(time.delta());
+        let resting = position.to_translation();
+        if thud.timer.finished() {
+            transform.translation = resting;
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<Thud>();
+            continue;
+        }
+        // Squash down onto the floor and spread out, scaled about the tile's bottom edge.
+        let amount = THUD_SQUASH
+            * juice_intensity.scale()
+            * (thud.timer.percent() * std::f32::consts::PI).sin();
+        let scale = Vec3::new(1.0 + amount, 1.0 - amount, 1.0);
+        transform.scale = scale;
+        transform.translation = resting;
+        transform.translation.x += (1.0 - scale.x) * TILE_SIZE / 2.0;
+        transform.translation.y -= (1.0 - scale.y) * TILE_SIZE;
+    }
+}
+
+impl Plugin for JuicePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                start_camera_shake,
+                shake_camera.after(start_camera_shake),
+                start_thuds,
+                thud_blocks.after(start_thuds),
+                start_bumps,
+                bump_players.after(start_bumps),
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+
+use crate::{play_plugin::LevelState, sokoban_core::Tile, tiles::Theme, GameState};
+
+// Shows the keys being carried, and hides keys once taken and locked doors once opened.
+pub struct KeysPlugin;
+
+#[derive(Component)]
+struct InventoryText;
+
+fn show_inventory(mut commands: Commands) {
+    commands.spawn((
+        InventoryText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(160.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+fn hide_inventory(mut commands: Commands, text_query: Query<Entity, With<InventoryText>>) {
+    for entity in text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// One mark per key in the color of the doors it opens.
+fn update_inventory_text(
+    level_state: Res<LevelState>,
+    theme: Res<Theme>,
+    mut text_query: Query<(&mut Text, Ref<InventoryText>)>,
+) {
+    let style = |color| TextStyle {
+        font_size: 16.0,
+        color,
+        ..default()
+    };
+    let label = if level_state.inventory.is_empty() {
+        ""
+    } else {
+        "Keys:"
+    };
+    for (mut text, inventory_text) in &mut text_query {
+        if !level_state.is_changed() && !theme.is_changed() && !inventory_text.is_added() {
+            continue;
+        }
+        text.sections = std::iter::once(TextSection::new(label, style(Color::WHITE)))
+            .chain(
+                level_state
+                    .inventory
+                    .iter()
+                    .map(|color| TextSection::new(" *", style(theme.tint(Tile::Key(*color))))),
+            )
+            .collect();
+    }
+}
+
+fn update_keys_and_doors(
+    level_state: Res<LevelState>,
+    mut visibility_query: Query<&mut Visibility>,
+) {
+    if !level_state.is_changed() {
+        return;
+    }
+    let shown = level_state
+        .keys
+        .values()
+        .chain(level_state.locked_doors.values())
+        .map(|(entity, _)| (entity, Visibility::Inherited));
+    let hidden = level_state
+        .taken_keys
+        .iter()
+        .chain(&level_state.unlocked_doors)
+        .map(|(_, (entity, _), _)| (entity, Visibility::Hidden));
+    for (entity, visibility) in shown.chain(hidden) {
+        if let Ok(mut entity_visibility) = visibility_query.get_mut(*entity) {
+            *entity_visibility = visibility;
+        }
+    }
+}
+
+impl Plugin for KeysPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), show_inventory)
+            .add_systems(OnExit(GameState::Playing), hide_inventory)
+            .add_systems(
+                Update,
+                (update_inventory_text, update_keys_and_doors).run_if(in_state(GameState::Playing)),
+            );
+    }
+}
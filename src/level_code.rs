@@ -0,0 +1,102 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+use crate::sokoban_core::{cell_from_code, is_known_code, Tile};
+
+const LEVEL_CODE_VERSION: &str = "LVL1";
+const MAX_LEVEL_SIDE: usize = 64;
+
+// Tile codes and run lengths are written 7 bits at a time, low bits first, with the top bit
+// set on every byte but the last.
+fn push_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &mut impl Iterator<Item = u8>) -> Result<u32, String> {
+    let mut value = 0;
+    for shift in (0..32).step_by(7) {
+        let byte = bytes.next().ok_or("Level code is cut short")?;
+        value |= ((byte & 0x7f) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("Level code has a bad number in it".to_string())
+}
+
+// The level's size and then runs of the same tile, row by row, e.g. "LVL1-BwUBCA...".
+pub fn encode_level(layout: &[Vec<i32>]) -> String {
+    let width = layout.iter().map(Vec::len).max().unwrap_or(0);
+    let mut bytes = Vec::new();
+    push_varint(&mut bytes, width as u32);
+    push_varint(&mut bytes, layout.len() as u32);
+
+    let tiles = layout
+        .iter()
+        .flat_map(|row| (0..width).map(|x| row.get(x).copied().unwrap_or(Tile::Empty.code())));
+    let mut run: Option<(i32, u32)> = None;
+    for code in tiles {
+        match &mut run {
+            Some((run_code, count)) if *run_code == code => *count += 1,
+            _ => {
+                if let Some((run_code, count)) = run {
+                    push_varint(&mut bytes, count);
+                    push_varint(&mut bytes, run_code as u32);
+                }
+                run = Some((code, 1));
+            }
+        }
+    }
+    if let Some((run_code, count)) = run {
+        push_varint(&mut bytes, count);
+        push_varint(&mut bytes, run_code as u32);
+    }
+
+    format!("{}-{}", LEVEL_CODE_VERSION, URL_SAFE_NO_PAD.encode(bytes))
+}
+
+pub fn decode_level(code: &str) -> Result<Vec<Vec<i32>>, String> {
+    let Some((version, data)) = code.trim().split_once('-') else {
+        return Err("Level code should start with its version".to_string());
+    };
+    if version != LEVEL_CODE_VERSION {
+        return Err(format!("Unsupported level code version {}", version));
+    }
+    let bytes = URL_SAFE_NO_PAD
+        .decode(data)
+        .map_err(|_| "Level code isn't valid base64".to_string())?;
+    let mut bytes = bytes.into_iter();
+
+    let width = read_varint(&mut bytes)? as usize;
+    let height = read_varint(&mut bytes)? as usize;
+    if width == 0 || height == 0 || width > MAX_LEVEL_SIDE || height > MAX_LEVEL_SIDE {
+        return Err(format!("A {}x{} level is not allowed", width, height));
+    }
+    let mut tiles = Vec::with_capacity(width * height);
+    while tiles.len() < width * height {
+        let count = read_varint(&mut bytes)? as usize;
+        let code = read_varint(&mut bytes)? as i32;
+        if !is_known_code(code) {
+            return Err(format!("Unknown tile {}", code));
+        }
+        if count == 0 || tiles.len() + count > width * height {
+            return Err("Level code has more tiles than fit the level".to_string());
+        }
+        tiles.extend(std::iter::repeat_n(code, count));
+    }
+    if bytes.next().is_some() {
+        return Err("Level code has data after the level".to_string());
+    }
+
+    let layout: Vec<Vec<i32>> = tiles.chunks(width).map(<[i32]>::to_vec).collect();
+    if !tiles
+        .iter()
+        .any(|code| cell_from_code(*code).contains(&Tile::Player))
+    {
+        return Err("Level has no player".to_string());
+    }
+    Ok(layout)
+}
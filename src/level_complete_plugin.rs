@@ -0,0 +1,241 @@
+use bevy::prelude::*;
+
+use crate::{
+    campaign_plugin::Campaign,
+    medals_plugin::Medal,
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{
+        LevelCompletedEvent, LevelPack, LevelState, NextLevelEvent, PackCompletedEvent,
+        CAMPAIGN_PACK,
+    },
+    GameState,
+};
+
+pub struct LevelCompletePlugin;
+
+#[derive(Resource, Default, Clone, Copy, PartialEq)]
+pub enum AutoAdvance {
+    Off,
+    #[default]
+    ThreeSeconds,
+    FiveSeconds,
+}
+
+impl AutoAdvance {
+    fn seconds(&self) -> Option<f32> {
+        match self {
+            AutoAdvance::Off => None,
+            AutoAdvance::ThreeSeconds => Some(3.0),
+            AutoAdvance::FiveSeconds => Some(5.0),
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            AutoAdvance::Off => AutoAdvance::ThreeSeconds,
+            AutoAdvance::ThreeSeconds => AutoAdvance::FiveSeconds,
+            AutoAdvance::FiveSeconds => AutoAdvance::Off,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct LevelComplete {
+    level: i32,
+    moves: usize,
+    countdown: Option<Timer>,
+}
+
+#[derive(Component)]
+struct LevelCompleteMenu;
+
+#[derive(Component)]
+struct AutoAdvanceText;
+
+pub fn complete_level(
+    mut commands: Commands,
+    mut level_completed_reader: EventReader<LevelCompletedEvent>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let Some(level_completed) = level_completed_reader.read().last() else {
+        return;
+    };
+    commands.insert_resource(LevelComplete {
+        level: level_completed.level,
+        moves: level_completed.moves.len(),
+        countdown: None,
+    });
+    game_state.set(GameState::LevelComplete);
+}
+
+fn start_countdown(level_complete: &mut LevelComplete, auto_advance: AutoAdvance) {
+    level_complete.countdown = auto_advance
+        .seconds()
+        .map(|seconds| Timer::from_seconds(seconds, TimerMode::Once));
+}
+
+fn show_level_complete(
+    mut commands: Commands,
+    auto_advance: Res<AutoAdvance>,
+    level_state: Res<LevelState>,
+    mut level_complete: ResMut<LevelComplete>,
+) {
+    start_countdown(&mut level_complete, *auto_advance);
+    let par = level_state.metadata.par;
+
+    commands
+        .spawn((
+            LevelCompleteMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Level complete!",
+                TextStyle {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                format!("Solved in {} moves", level_complete.moves),
+                TextStyle {
+                    font_size: 20.0,
+                    ..default()
+                },
+            ));
+            if let Some(par) = par {
+                let medal = Medal::for_moves(level_complete.moves, par);
+                parent.spawn(TextBundle::from_section(
+                    format!("{} medal (par {})", medal.name(), par),
+                    TextStyle {
+                        font_size: 20.0,
+                        color: medal.color(),
+                        ..default()
+                    },
+                ));
+            }
+            parent.spawn((
+                AutoAdvanceText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+fn hide_level_complete(
+    mut commands: Commands,
+    level_complete_menu_query: Query<Entity, With<LevelCompleteMenu>>,
+) {
+    for entity in level_complete_menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_level_complete_input(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut auto_advance: ResMut<AutoAdvance>,
+    mut level_complete: ResMut<LevelComplete>,
+) {
+    if keyboard_input.just_pressed(KeyCode::A) {
+        *auto_advance = auto_advance.next();
+        start_countdown(&mut level_complete, *auto_advance);
+    } else if keyboard_input.get_just_pressed().next().is_some() {
+        // Any other key stops the countdown, Enter still moves on.
+        level_complete.countdown = None;
+    }
+
+    if let Some(countdown) = &mut level_complete.countdown {
+        countdown.tick(time.delta());
+    }
+}
+
+fn advance_level(
+    keyboard_input: Res<Input<KeyCode>>,
+    campaign: Res<Campaign>,
+    level_pack: Res<LevelPack>,
+    level_complete: Res<LevelComplete>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+    mut pack_completed_writer: EventWriter<PackCompletedEvent>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let countdown_finished = level_complete
+        .countdown
+        .as_ref()
+        .is_some_and(Timer::finished);
+    if !countdown_finished && !keyboard_input.just_pressed(KeyCode::Return) {
+        return;
+    }
+
+    let next_level = level_complete.level + 1;
+    let finished_chapter = level_pack.name == CAMPAIGN_PACK
+        && campaign.finished_chapter(level_complete.level).is_some();
+    if finished_chapter {
+        game_state.set(GameState::ChapterComplete);
+    } else if level_pack.level(next_level).is_some() {
+        next_level_writer.send(NextLevelEvent(next_level));
+        game_state.set(GameState::Playing);
+    } else {
+        pack_completed_writer.send(PackCompletedEvent {
+            name: level_pack.name.clone(),
+        });
+        game_state.set(level_pack.return_state);
+    }
+}
+
+fn update_auto_advance_text(
+    auto_advance: Res<AutoAdvance>,
+    level_complete: Res<LevelComplete>,
+    mut auto_advance_text_query: Query<&mut Text, With<AutoAdvanceText>>,
+) {
+    let Ok(mut text) = auto_advance_text_query.get_single_mut() else {
+        return;
+    };
+    let setting = match auto_advance.seconds() {
+        Some(seconds) => format!("{}s", seconds),
+        None => "off".to_string(),
+    };
+    let countdown = match &level_complete.countdown {
+        Some(countdown) => format!("Continuing in {:.0}  ", countdown.remaining_secs().ceil()),
+        None => String::new(),
+    };
+    text.sections[0].value = format!("{}Enter: continue  A: auto-advance {}", countdown, setting);
+}
+
+impl Plugin for LevelCompletePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AutoAdvance::default())
+            .insert_resource(LevelComplete::default())
+            .add_systems(OnEnter(GameState::LevelComplete), show_level_complete)
+            .add_systems(OnExit(GameState::LevelComplete), hide_level_complete)
+            .add_systems(
+                Update,
+                (
+                    handle_level_complete_input,
+                    advance_level.after(handle_level_complete_input),
+                    update_auto_advance_text.after(handle_level_complete_input),
+                )
+                    .run_if(in_state(GameState::LevelComplete))
+                    .run_if(quit_prompt_closed),
+            );
+    }
+}
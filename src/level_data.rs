@@ -0,0 +1,236 @@
+use bevy::{prelude::*, sprite::Anchor, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    sokoban_core::{cell_code, cell_from_code, flood_fill, Tile},
+    tilemap_plugin::spawn_tilemap,
+    tiles::{
+        block_color, door_color, enemy_color, exit_color, facing_marker, fragile_color,
+        heavy_marker, ice_color, keyhole, mirror_color, one_way_arrow, one_way_color, pit_color,
+        portal_color, portal_core, spawn_colored_tile, spawn_key, spawn_pickup, switch_color,
+        teleporter_color, Themed, ONE_WAY_DIRECTIONS, STARTING_FACING,
+    },
+    LevelEntity, Position,
+};
+
+// Everything about a level besides its tiles, any of it can be left out.
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelMetadata {
+    pub title: String,
+    pub author: String,
+    // How hard the author rates it, one of the labels difficulty estimates use.
+    pub difficulty: String,
+    // Fewest moves the level can be solved in, if anyone has worked it out.
+    pub par: Option<usize>,
+    pub hint: String,
+    // Props drawn on the board, by column and row like the layout.
+    pub decorations: Vec<(i32, i32, Decoration)>,
+    // A colour laid over the whole view to grade it: red, green, blue and how strongly.
+    pub tint: Option<(f32, f32, f32, f32)>,
+}
+
+// Something drawn on a tile just for looks, the movement rules never see it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Decoration {
+    Plant,
+    Crack,
+    Crate,
+}
+
+pub struct LevelData {
+    // Each cell's tiles, bottom first.
+    pub tiles: Vec<Vec<Vec<Tile>>>,
+}
+
+impl LevelData {
+    pub fn from_layout(layout: &[Vec<i32>]) -> Self {
+        Self {
+            tiles: layout
+                .iter()
+                .map(|row| row.iter().copied().map(cell_from_code).collect())
+                .collect(),
+        }
+    }
+
+    pub fn to_layout(&self) -> Vec<Vec<i32>> {
+        self.tiles
+            .iter()
+            .map(|row| row.iter().map(|cell| cell_code(cell)).collect())
+            .collect()
+    }
+
+    // Every tile in reading order, both of them for a cell with something on a goal.
+    pub fn iter(&self) -> impl Iterator<Item = (Position, Tile)> + '_ {
+        self.tiles.iter().enumerate().flat_map(|(y, row)| {
+            row.iter().enumerate().flat_map(move |(x, cell)| {
+                cell.iter().map(move |tile| {
+                    (
+                        Position {
+                            x: x as i32,
+                            y: y as i32,
+                        },
+                        *tile,
+                    )
+                })
+            })
+        })
+    }
+
+    pub fn get(&self, position: Position) -> Option<&[Tile]> {
+        let row = usize::try_from(position.y).ok()?;
+        let column = usize::try_from(position.x).ok()?;
+        self.tiles.get(row)?.get(column).map(Vec::as_slice)
+    }
+
+    // Everywhere the player or the mirror can reach, the mirror can start in a room of its own
+    // and a portal can lead into one.
+    pub fn floor_positions(&self) -> HashSet<Position> {
+        let starts = self
+            .iter()
+            .filter(|(_, tile)| matches!(tile, Tile::Player | Tile::Mirror | Tile::Portal(_)))
+            .map(|(position, _)| position);
+        flood_fill(starts, |position| {
+            self.get(position)
+                .is_some_and(|cell| !cell.contains(&Tile::Wall))
+        })
+        .into_iter()
+        .collect()
+    }
+}
+
+// Spawns the sprite for a tile, the gameplay components are left to whoever spawned it.
+pub fn spawn_tile(commands: &mut Commands, position: Position, tile: Tile) -> Option<Entity> {
+    // The sprite is picked from the tile atlas once the tile is spawned.
+    let textured = |color: Color, z: f32| SpriteSheetBundle {
+        sprite: TextureAtlasSprite {
+            color,
+            anchor: Anchor::TopLeft,
+            ..default()
+        },
+        transform: Transform::from_translation(position.to_translation_z(z)),
+        ..default()
+    };
+
+    let entity = match tile {
+        Tile::Empty => return None,
+        Tile::Player => commands
+            .spawn((LevelEntity, textured(Color::WHITE, 1.0)))
+            .with_children(|parent| {
+                parent.spawn((LevelEntity, facing_marker(STARTING_FACING)));
+            })
+            .id(),
+        Tile::Block(color) => commands
+            .spawn((LevelEntity, textured(block_color(color), 1.0)))
+            .id(),
+        Tile::HeavyBlock => commands
+            .spawn((LevelEntity, textured(Color::WHITE, 1.0)))
+            .with_children(|parent| {
+                parent.spawn((LevelEntity, heavy_marker()));
+            })
+            .id(),
+        Tile::Goal(color) => commands
+            .spawn((LevelEntity, textured(block_color(color), 0.5)))
+            .id(),
+        Tile::Wall => commands
+            .spawn((LevelEntity, textured(Color::WHITE, 1.0)))
+            .id(),
+        Tile::Switch => commands
+            .spawn((
+                LevelEntity,
+                spawn_colored_tile(position, switch_color(), 0.5),
+            ))
+            .id(),
+        Tile::Door => commands
+            .spawn((LevelEntity, spawn_colored_tile(position, door_color(), 1.0)))
+            .id(),
+        Tile::Teleporter(pair) => commands
+            .spawn((
+                LevelEntity,
+                spawn_colored_tile(position, teleporter_color(pair), 0.5),
+            ))
+            .id(),
+        Tile::Ice => commands
+            .spawn((LevelEntity, spawn_colored_tile(position, ice_color(), 0.25)))
+            .id(),
+        Tile::OneWay(direction) => commands
+            .spawn((
+                LevelEntity,
+                spawn_colored_tile(position, one_way_color(), 0.25),
+            ))
+            .with_children(|parent| {
+                parent.spawn((LevelEntity, one_way_arrow(ONE_WAY_DIRECTIONS[direction])));
+            })
+            .id(),
+        Tile::Pickup(_) => commands.spawn((LevelEntity, spawn_pickup(position))).id(),
+        Tile::Pit => commands
+            .spawn((LevelEntity, spawn_colored_tile(position, pit_color(), 0.5)))
+            .id(),
+        Tile::Mirror => commands
+            .spawn((LevelEntity, textured(mirror_color(), 1.0)))
+            .id(),
+        Tile::Exit => commands
+            .spawn((
+                LevelEntity,
+                spawn_colored_tile(position, exit_color(), 0.25),
+            ))
+            .id(),
+        Tile::Enemy(_) => commands
+            .spawn((
+                LevelEntity,
+                spawn_colored_tile(position, enemy_color(), 1.0),
+            ))
+            .id(),
+        Tile::Key(color) => commands
+            .spawn((LevelEntity, spawn_key(position, color)))
+            .id(),
+        Tile::LockedDoor(color) => commands
+            .spawn((
+                LevelEntity,
+                spawn_colored_tile(position, block_color(color), 1.0),
+            ))
+            .with_children(|parent| {
+                parent.spawn((LevelEntity, keyhole()));
+            })
+            .id(),
+        Tile::Fragile => commands
+            .spawn((
+                LevelEntity,
+                spawn_colored_tile(position, fragile_color(), 0.25),
+            ))
+            .id(),
+        Tile::Portal(pair) => commands
+            .spawn((
+                LevelEntity,
+                spawn_colored_tile(position, portal_color(pair), 0.5),
+            ))
+            .with_children(|parent| {
+                parent.spawn((LevelEntity, portal_core()));
+            })
+            .id(),
+    };
+    commands.entity(entity).insert(Themed(tile));
+    Some(entity)
+}
+
+// Spawns the floor and every tile, returning the tiles' entities in reading order. Floors
+// and walls are drawn into the tilemap, so walls come back with the chunk they're drawn in.
+// Goals stay sprites of their own so they can be tinted one at a time.
+pub fn spawn_level(
+    commands: &mut Commands,
+    level_data: &LevelData,
+) -> Vec<(Position, Tile, Entity)> {
+    let walls = level_data.iter().filter(|(_, tile)| *tile == Tile::Wall);
+    let floors = level_data
+        .floor_positions()
+        .into_iter()
+        .map(|position| (position, Tile::Empty));
+    let chunks = spawn_tilemap(commands, floors.chain(walls));
+    level_data
+        .iter()
+        .filter_map(|(position, tile)| match tile {
+            Tile::Wall => Some((position, tile, chunks[&position])),
+            _ => spawn_tile(commands, position, tile).map(|entity| (position, tile, entity)),
+        })
+        .collect()
+}
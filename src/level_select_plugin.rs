@@ -0,0 +1,408 @@
+use std::path::Path;
+
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+
+use crate::{
+    accessibility_plugin::ListEntry,
+    bundle::{load_bundled_packs, PACKS_DIRECTORY},
+    campaign_plugin::Campaign,
+    difficulty::{estimate_difficulty, Difficulty},
+    level_viewer_plugin::LevelViewer,
+    limits_plugin::{limits_text, LimitsSetting},
+    medals_plugin::Medals,
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{unload_level, LevelPack, NextLevelEvent},
+    recommend::suggest_level,
+    stats_plugin::GlobalStats,
+    thumbnail_plugin::ThumbnailCache,
+    GameState,
+};
+
+pub struct LevelSelectPlugin;
+
+#[derive(Resource, Default)]
+pub struct DifficultyCache(pub HashMap<i32, Difficulty>);
+
+#[derive(Resource, Default)]
+struct LevelSelectState {
+    levels: Vec<i32>,
+    selected: usize,
+    sort_by_difficulty: bool,
+}
+
+// Packs that passed the bundler, B plays them in turn.
+#[derive(Resource, Default)]
+struct BundledPacks {
+    packs: Vec<LevelPack>,
+    next: usize,
+}
+
+// Everything Enter and B can start playing.
+#[derive(SystemParam)]
+struct Packs<'w> {
+    campaign: Res<'w, Campaign>,
+    bundled: ResMut<'w, BundledPacks>,
+}
+
+#[derive(Component)]
+struct LevelSelectMenu;
+
+#[derive(Component)]
+struct LevelRow(usize);
+
+#[derive(Component)]
+struct LevelThumbnail(usize);
+
+#[derive(Component)]
+struct LimitsText;
+
+// Who made the selected level and its hint.
+#[derive(Component)]
+struct LevelDetails;
+
+const PREVIEW_TILE_SIZE: f32 = 8.0;
+const THUMBNAIL_HEIGHT: f32 = 24.0;
+
+fn show_level_select(
+    mut commands: Commands,
+    campaign: Res<Campaign>,
+    limits_setting: Res<LimitsSetting>,
+    global_stats: Res<GlobalStats>,
+    mut difficulty_cache: ResMut<DifficultyCache>,
+) {
+    for (level, campaign_level) in (1..).zip(campaign.levels()) {
+        difficulty_cache
+            .0
+            .entry(level)
+            .or_insert_with(|| estimate_difficulty(&campaign_level.layout));
+    }
+    let suggestion = suggest_level(&global_stats.recent, &difficulty_cache.0);
+
+    commands
+        .spawn((
+            LevelSelectMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Select a level",
+                TextStyle {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+            for row in 0..campaign.level_count() as usize {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(8.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn((
+                            LevelThumbnail(row),
+                            ImageBundle {
+                                style: Style {
+                                    height: Val::Px(THUMBNAIL_HEIGHT),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                        ));
+                        parent.spawn((
+                            LevelRow(row),
+                            ListEntry::default(),
+                            TextBundle::from_section(
+                                "",
+                                TextStyle {
+                                    font_size: 20.0,
+                                    ..default()
+                                },
+                            ),
+                        ));
+                    });
+            }
+            parent.spawn((
+                LevelViewer {
+                    layout: campaign
+                        .level(1)
+                        .map(|level| level.layout.clone())
+                        .unwrap_or_default(),
+                    tile_size: PREVIEW_TILE_SIZE,
+                },
+                NodeBundle {
+                    style: Style {
+                        margin: UiRect::vertical(Val::Px(8.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                LevelDetails,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center),
+            ));
+            if let Some(suggestion) = suggestion {
+                parent.spawn(TextBundle::from_section(
+                    format!(
+                        "You might enjoy Level {}, {}",
+                        suggestion.level, suggestion.reason
+                    ),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::CYAN,
+                        ..default()
+                    },
+                ));
+            }
+            parent.spawn(TextBundle::from_section(
+                "Up/Down: choose  Tab: sort  Enter: play  C: challenges  P: daily\n\
+                T: tutorial  B: bundled pack  D: download pack\n\
+                A: credits  S: stats  I: import code  U: custom levels",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                LimitsText,
+                TextBundle::from_section(
+                    limits_text(&limits_setting),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+
+    commands.insert_resource(LevelSelectState {
+        levels: (1..=campaign.level_count()).collect(),
+        selected: 0,
+        sort_by_difficulty: false,
+    });
+}
+
+fn hide_level_select(
+    mut commands: Commands,
+    level_select_menu_query: Query<Entity, With<LevelSelectMenu>>,
+) {
+    for entity in level_select_menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_level_select_input(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    difficulty_cache: Res<DifficultyCache>,
+    mut level_select_state: ResMut<LevelSelectState>,
+    mut packs: Packs,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let level_count = level_select_state.levels.len();
+    if level_count == 0 {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        level_select_state.selected = (level_select_state.selected + level_count - 1) % level_count;
+    } else if keyboard_input.just_pressed(KeyCode::Down) {
+        level_select_state.selected = (level_select_state.selected + 1) % level_count;
+    } else if keyboard_input.just_pressed(KeyCode::Tab) {
+        let selected_level = level_select_state.levels[level_select_state.selected];
+        level_select_state.sort_by_difficulty = !level_select_state.sort_by_difficulty;
+        if level_select_state.sort_by_difficulty {
+            level_select_state.levels.sort_by(|a, b| {
+                let score = |level| difficulty_cache.0.get(level).map(Difficulty::score);
+                score(a).partial_cmp(&score(b)).unwrap()
+            });
+        } else {
+            level_select_state.levels.sort();
+        }
+        level_select_state.selected = level_select_state
+            .levels
+            .iter()
+            .position(|level| *level == selected_level)
+            .unwrap();
+    } else if keyboard_input.just_pressed(KeyCode::C) {
+        game_state.set(GameState::Challenges);
+    } else if keyboard_input.just_pressed(KeyCode::A) {
+        game_state.set(GameState::Credits);
+    } else if keyboard_input.just_pressed(KeyCode::S) {
+        game_state.set(GameState::Stats);
+    } else if keyboard_input.just_pressed(KeyCode::B) && !packs.bundled.packs.is_empty() {
+        let index = packs.bundled.next % packs.bundled.packs.len();
+        packs.bundled.next += 1;
+        commands.insert_resource(packs.bundled.packs[index].clone());
+        next_level_writer.send(NextLevelEvent(1));
+        game_state.set(GameState::Playing);
+    } else if keyboard_input.just_pressed(KeyCode::Return) {
+        commands.insert_resource(LevelPack::campaign(&packs.campaign));
+        next_level_writer.send(NextLevelEvent(
+            level_select_state.levels[level_select_state.selected],
+        ));
+        game_state.set(GameState::Playing);
+    }
+}
+
+fn toggle_limits(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut limits_setting: ResMut<LimitsSetting>,
+    mut limits_text_query: Query<&mut Text, With<LimitsText>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::L) {
+        return;
+    }
+    limits_setting.enabled = !limits_setting.enabled;
+    for mut text in &mut limits_text_query {
+        text.sections[0].value = limits_text(&limits_setting);
+    }
+}
+
+fn update_level_rows(
+    campaign: Res<Campaign>,
+    difficulty_cache: Res<DifficultyCache>,
+    medals: Medals,
+    level_select_state: Res<LevelSelectState>,
+    mut row_query: Query<(&LevelRow, &mut Text, &mut ListEntry)>,
+    mut details_query: Query<&mut Text, (With<LevelDetails>, Without<LevelRow>)>,
+    mut viewer_query: Query<&mut LevelViewer>,
+) {
+    if !level_select_state.is_changed() {
+        return;
+    }
+    let selected_level = level_select_state.levels.get(level_select_state.selected);
+    let selected_campaign_level = selected_level.and_then(|level| campaign.level(*level));
+    let metadata = selected_campaign_level
+        .map(|level| level.metadata.clone())
+        .unwrap_or_default();
+    for mut text in &mut details_query {
+        text.sections[0].value = match metadata.hint.as_str() {
+            "" => format!("by {}", metadata.author),
+            hint => format!("by {}\nHint: {}", metadata.author, hint),
+        };
+    }
+    if let (Ok(mut viewer), Some(layout)) = (
+        viewer_query.get_single_mut(),
+        selected_campaign_level.map(|level| level.layout.clone()),
+    ) {
+        viewer.layout = layout;
+    }
+
+    for (row, mut text, mut entry) in &mut row_query {
+        let Some(level) = level_select_state.levels.get(row.0) else {
+            continue;
+        };
+        let difficulty_text = match difficulty_cache.0.get(level) {
+            Some(difficulty) => match difficulty.solution_length {
+                Some(solution_length) => format!(
+                    "{} ({} moves, {} blocks, {:.1} branching)",
+                    difficulty.label(),
+                    solution_length,
+                    difficulty.blocks,
+                    difficulty.branching_factor,
+                ),
+                None => difficulty.label().to_string(),
+            },
+            None => "Unknown".to_string(),
+        };
+
+        let metadata = campaign
+            .level(*level)
+            .map(|level| level.metadata.clone())
+            .unwrap_or_default();
+        let medal_text = match (medals.medal_case.0.get(level), metadata.par) {
+            (Some(medal), Some(par)) => format!("  {} (par {})", medal.name(), par),
+            _ if medals.skipped_levels.0.contains(level) => "  skipped".to_string(),
+            (None, Some(par)) => format!("  par {}", par),
+            _ => String::new(),
+        };
+        let title = match metadata.title.as_str() {
+            "" => String::new(),
+            title => format!(": {}", title),
+        };
+        text.sections[0].value = format!(
+            "Level {}{} - {}{}",
+            level, title, difficulty_text, medal_text
+        );
+        entry.selected = row.0 == level_select_state.selected;
+        text.sections[0].style.color = if entry.selected {
+            Color::YELLOW
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+fn update_level_thumbnails(
+    campaign: Res<Campaign>,
+    level_select_state: Res<LevelSelectState>,
+    mut thumbnail_cache: ResMut<ThumbnailCache>,
+    mut images: ResMut<Assets<Image>>,
+    mut thumbnail_query: Query<(&LevelThumbnail, &mut UiImage)>,
+) {
+    if !level_select_state.is_changed() {
+        return;
+    }
+    for (thumbnail, mut ui_image) in &mut thumbnail_query {
+        let Some(campaign_level) = level_select_state
+            .levels
+            .get(thumbnail.0)
+            .and_then(|level| campaign.level(*level))
+        else {
+            continue;
+        };
+        ui_image.texture = thumbnail_cache.thumbnail(&campaign_level.layout, &mut images);
+    }
+}
+
+impl Plugin for LevelSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DifficultyCache::default())
+            .insert_resource(LevelSelectState::default())
+            .insert_resource(BundledPacks {
+                packs: load_bundled_packs(Path::new(PACKS_DIRECTORY)),
+                next: 0,
+            })
+            .add_systems(
+                OnEnter(GameState::LevelSelect),
+                (unload_level, show_level_select),
+            )
+            .add_systems(OnExit(GameState::LevelSelect), hide_level_select)
+            .add_systems(
+                Update,
+                (
+                    handle_level_select_input.run_if(quit_prompt_closed),
+                    toggle_limits.run_if(quit_prompt_closed),
+                    update_level_rows.after(handle_level_select_input),
+                    update_level_thumbnails.after(handle_level_select_input),
+                )
+                    .run_if(in_state(GameState::LevelSelect)),
+            );
+    }
+}
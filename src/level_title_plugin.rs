@@ -0,0 +1,213 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    play_plugin::{BoardChangedEvent, LevelLoadedEvent, LevelPack, LevelState, LevelUnloadedEvent},
+    GameState, GAME_TITLE,
+};
+
+const TITLE_SECONDS: f32 = 3.0;
+
+// Names the level for a moment as it loads, gives its hint in the pause menu, and keeps it
+// and the move count in the window title.
+pub struct LevelTitlePlugin;
+
+#[derive(Component)]
+struct LevelHint;
+
+#[derive(Component)]
+struct LevelTitle {
+    level: i32,
+    timer: Timer,
+}
+
+fn show_level_title(
+    mut commands: Commands,
+    level_pack: Res<LevelPack>,
+    mut level_loaded_reader: EventReader<LevelLoadedEvent>,
+) {
+    let Some(level_loaded) = level_loaded_reader.read().last() else {
+        return;
+    };
+    let metadata = level_pack.metadata(level_loaded.level);
+    if metadata.title.is_empty() {
+        return;
+    }
+    let byline = match (metadata.author.as_str(), metadata.difficulty.as_str()) {
+        ("", "") => String::new(),
+        (author, "") => format!("\nby {}", author),
+        ("", difficulty) => format!("\n{}", difficulty),
+        (author, difficulty) => format!("\nby {} - {}", author, difficulty),
+    };
+
+    commands
+        .spawn((
+            LevelTitle {
+                level: level_loaded.level,
+                timer: Timer::from_seconds(TITLE_SECONDS, TimerMode::Once),
+            },
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    top: Val::Px(40.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_sections([
+                    TextSection::new(
+                        metadata.title,
+                        TextStyle {
+                            font_size: 24.0,
+                            ..default()
+                        },
+                    ),
+                    TextSection::new(
+                        byline,
+                        TextStyle {
+                            font_size: 16.0,
+                            color: Color::GRAY,
+                            ..default()
+                        },
+                    ),
+                ])
+                .with_text_alignment(TextAlignment::Center),
+            );
+        });
+}
+
+fn hide_level_title(
+    mut commands: Commands,
+    mut level_unloaded_reader: EventReader<LevelUnloadedEvent>,
+    level_title_query: Query<(Entity, &LevelTitle)>,
+) {
+    for level_unloaded in level_unloaded_reader.read() {
+        for (entity, level_title) in level_title_query.iter() {
+            if level_title.level == level_unloaded.0 {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn fade_level_title(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut level_title_query: Query<(Entity, &mut LevelTitle, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (entity, mut level_title, children) in &mut level_title_query {
+        level_title.timer.tick(time.delta());
+        if level_title.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let alpha = level_title.timer.remaining_secs().min(1.0);
+        let mut texts = text_query.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            for section in &mut text.sections {
+                section.style.color.set_a(alpha);
+            }
+        }
+    }
+}
+
+fn show_level_hint(mut commands: Commands, level_state: Res<LevelState>) {
+    if level_state.metadata.hint.is_empty() {
+        return;
+    }
+    commands.spawn((
+        LevelHint,
+        TextBundle::from_section(
+            format!("Hint: {}", level_state.metadata.hint),
+            TextStyle {
+                font_size: 16.0,
+                color: Color::CYAN,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        ZIndex::Global(11),
+    ));
+}
+
+fn hide_level_hint(mut commands: Commands, level_hint_query: Query<Entity, With<LevelHint>>) {
+    for entity in level_hint_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn window_title(level_pack: &LevelPack, level: i32, moves: usize) -> String {
+    let title = level_pack.metadata(level).title;
+    let name = if title.is_empty() {
+        format!("Level {}", level)
+    } else {
+        format!("Level {}: {}", level, title)
+    };
+    format!(
+        "{} - {} {} - {} moves",
+        GAME_TITLE, level_pack.name, name, moves
+    )
+}
+
+// Streamers' viewers, and anyone checking which pack is loaded, can read it off the taskbar.
+fn update_window_title(
+    level_pack: Res<LevelPack>,
+    level_state: Res<LevelState>,
+    mut loaded_level: Local<Option<i32>>,
+    mut level_loaded_reader: EventReader<LevelLoadedEvent>,
+    mut level_unloaded_reader: EventReader<LevelUnloadedEvent>,
+    mut board_changed_reader: EventReader<BoardChangedEvent>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let mut changed = board_changed_reader.read().count() > 0 || level_state.is_changed();
+    for level_unloaded in level_unloaded_reader.read() {
+        if *loaded_level == Some(level_unloaded.0) {
+            *loaded_level = None;
+            changed = true;
+        }
+    }
+    if let Some(level_loaded) = level_loaded_reader.read().last() {
+        *loaded_level = Some(level_loaded.level);
+        changed = true;
+    }
+    let Ok(mut window) = window_query.get_single_mut() else {
+        return;
+    };
+    if !changed {
+        return;
+    }
+    let title = match *loaded_level {
+        Some(level) => window_title(&level_pack, level, level_state.moves.len()),
+        None => GAME_TITLE.to_string(),
+    };
+    if window.title != title {
+        window.title = title;
+    }
+}
+
+impl Plugin for LevelTitlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Paused), show_level_hint)
+            .add_systems(OnExit(GameState::Paused), hide_level_hint)
+            .add_systems(
+                Update,
+                (
+                    hide_level_title,
+                    show_level_title.after(hide_level_title),
+                    fade_level_title,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(Update, update_window_title);
+    }
+}
@@ -0,0 +1,202 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    sokoban_core::flood_fill,
+    tiles::{
+        block_color, door_color, exit_color, heavy_color, ice_color, mirror_color, one_way_color,
+        pickup_color, pit_color, switch_color, teleporter_color,
+    },
+    Position,
+};
+
+// Draws a level into a UI node without any of the gameplay systems, for menus that want a
+// live preview of a board.
+pub struct LevelViewerPlugin;
+
+#[derive(Component)]
+pub struct LevelViewer {
+    pub layout: Vec<Vec<i32>>,
+    pub tile_size: f32,
+}
+
+// Set once the board has been drawn, it is drawn again when the viewer changes.
+#[derive(Component)]
+struct ViewerBuilt;
+
+// Everything the viewer spawned, so it can be cleared before redrawing.
+#[derive(Component)]
+struct ViewerPart;
+
+#[derive(Clone)]
+pub enum ViewerTile {
+    Texture(&'static str),
+    Color(Color),
+}
+
+// Floor is drawn everywhere the player can walk, or everywhere if there's no player.
+pub fn floor_positions(layout: &[Vec<i32>]) -> HashSet<Position> {
+    let tile_at = |position: Position| {
+        let row = usize::try_from(position.y).ok()?;
+        let column = usize::try_from(position.x).ok()?;
+        layout.get(row)?.get(column).copied()
+    };
+    let positions = layout.iter().enumerate().flat_map(|(y, row)| {
+        (0..row.len()).map(move |x| Position {
+            x: x as i32,
+            y: y as i32,
+        })
+    });
+
+    let Some(start) = positions
+        .clone()
+        .find(|position| tile_at(*position).is_some_and(|tile| tile < 16 && tile & 1 != 0))
+    else {
+        return positions
+            .filter(|position| tile_at(*position).is_some_and(|tile| tile != 8))
+            .collect();
+    };
+
+    flood_fill([start], |position| {
+        tile_at(position).is_some_and(|tile| tile != 8)
+    })
+    .into_iter()
+    .collect()
+}
+
+// Back to front, what's drawn on a single tile.
+pub fn viewer_tiles(
+    layout: &[Vec<i32>],
+    floors: &HashSet<Position>,
+) -> Vec<(Position, ViewerTile)> {
+    let mut tiles = Vec::new();
+    for (y, row) in layout.iter().enumerate() {
+        for (x, tile) in row.iter().enumerate() {
+            let position = Position {
+                x: x as i32,
+                y: y as i32,
+            };
+            if floors.contains(&position) {
+                tiles.push((position, ViewerTile::Texture("floor.png")));
+            }
+            match tile {
+                16 => tiles.push((position, ViewerTile::Color(switch_color()))),
+                32 => tiles.push((position, ViewerTile::Color(door_color()))),
+                128 => tiles.push((position, ViewerTile::Color(ice_color()))),
+                256..=259 => tiles.push((position, ViewerTile::Color(one_way_color()))),
+                512..=1023 => tiles.push((position, ViewerTile::Color(pickup_color()))),
+                1024 => tiles.push((position, ViewerTile::Color(pit_color()))),
+                2048 => tiles.push((position, ViewerTile::Color(mirror_color()))),
+                8192 => tiles.push((position, ViewerTile::Color(exit_color()))),
+                16384 => tiles.push((position, ViewerTile::Color(heavy_color()))),
+                4097..=4111 => tiles.push((
+                    position,
+                    ViewerTile::Color(block_color((tile - 4096) as u8)),
+                )),
+                4113..=4127 => tiles.push((position, ViewerTile::Texture("goal.png"))),
+                64..=127 => tiles.push((position, ViewerTile::Color(teleporter_color(tile - 64)))),
+                _ if *tile < 16 => {
+                    for (flag, texture) in [
+                        (4, "goal.png"),
+                        (8, "wall.png"),
+                        (2, "block.png"),
+                        (1, "player.png"),
+                    ] {
+                        if tile & flag != 0 {
+                            tiles.push((position, ViewerTile::Texture(texture)));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    tiles
+}
+
+fn spawn_node_tiles(
+    parent: &mut ChildBuilder,
+    asset_server: &AssetServer,
+    tiles: Vec<(Position, ViewerTile)>,
+    tile_size: f32,
+) {
+    for (position, tile) in tiles {
+        let style = Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(position.x as f32 * tile_size),
+            top: Val::Px(position.y as f32 * tile_size),
+            width: Val::Px(tile_size),
+            height: Val::Px(tile_size),
+            ..default()
+        };
+        match tile {
+            ViewerTile::Texture(texture) => {
+                parent.spawn((
+                    ViewerPart,
+                    ImageBundle {
+                        style,
+                        image: asset_server.load(texture).into(),
+                        ..default()
+                    },
+                ));
+            }
+            ViewerTile::Color(color) => {
+                parent.spawn((
+                    ViewerPart,
+                    NodeBundle {
+                        style,
+                        background_color: color.into(),
+                        ..default()
+                    },
+                ));
+            }
+        }
+    }
+}
+
+fn build_level_viewers(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    viewer_query: Query<(Entity, Ref<LevelViewer>, Has<ViewerBuilt>)>,
+    part_query: Query<(Entity, &Parent), With<ViewerPart>>,
+) {
+    for (entity, viewer, built) in &viewer_query {
+        if built && !viewer.is_changed() {
+            continue;
+        }
+        for (part, parent) in &part_query {
+            if parent.get() == entity {
+                commands.entity(part).despawn_recursive();
+            }
+        }
+
+        let width = viewer.layout.iter().map(|row| row.len()).max().unwrap_or(0) as f32;
+        let height = viewer.layout.len() as f32;
+        let tiles = viewer_tiles(&viewer.layout, &floor_positions(&viewer.layout));
+        commands
+            .entity(entity)
+            .insert(ViewerBuilt)
+            .with_children(|parent| {
+                parent
+                    .spawn((
+                        ViewerPart,
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Px(width * viewer.tile_size),
+                                height: Val::Px(height * viewer.tile_size),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ))
+                    .with_children(|board| {
+                        spawn_node_tiles(board, &asset_server, tiles, viewer.tile_size);
+                    });
+            });
+    }
+}
+
+impl Plugin for LevelViewerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, build_level_viewers);
+    }
+}
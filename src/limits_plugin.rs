@@ -0,0 +1,252 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    campaign_plugin::Campaign,
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{
+        LevelLoadedEvent, LevelPack, LevelState, MoveHistory, NextLevelEvent, CAMPAIGN_PACK,
+    },
+    GameState,
+};
+
+// Optional rules where each campaign level has to be solved within a number of moves or
+// before a countdown runs out.
+pub struct LimitsPlugin;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum LevelLimit {
+    Moves(usize),
+    Seconds(f32),
+}
+
+// Limits are off unless turned on from level select.
+#[derive(Resource, Default)]
+pub struct LimitsSetting {
+    pub enabled: bool,
+}
+
+// The limit on the level being played and how far into it the player is.
+#[derive(Resource, Default)]
+struct LimitRun {
+    level: i32,
+    limit: Option<LevelLimit>,
+    elapsed: f32,
+}
+
+#[derive(Resource, Default)]
+struct LimitFailure {
+    level: i32,
+    reason: &'static str,
+}
+
+#[derive(Component)]
+struct LimitText;
+
+#[derive(Component)]
+struct LimitFailedMenu;
+
+pub fn limits_text(limits_setting: &LimitsSetting) -> String {
+    let state = if limits_setting.enabled { "on" } else { "off" };
+    format!("L: move and time limits ({})", state)
+}
+
+fn start_limit_run(
+    limits_setting: Res<LimitsSetting>,
+    campaign: Res<Campaign>,
+    level_pack: Res<LevelPack>,
+    mut level_loaded_reader: EventReader<LevelLoadedEvent>,
+    mut limit_run: ResMut<LimitRun>,
+) {
+    let Some(level_loaded) = level_loaded_reader.read().last() else {
+        return;
+    };
+    let limited = limits_setting.enabled && level_pack.name == CAMPAIGN_PACK;
+    *limit_run = LimitRun {
+        level: level_loaded.level,
+        limit: campaign
+            .level(level_loaded.level)
+            .and_then(|level| level.limit)
+            .filter(|_| limited),
+        elapsed: 0.0,
+    };
+}
+
+fn check_limit(
+    time: Res<Time>,
+    level_state: Res<LevelState>,
+    move_history: Res<MoveHistory>,
+    mut limit_run: ResMut<LimitRun>,
+    mut limit_failure: ResMut<LimitFailure>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if level_state.is_solved() {
+        return;
+    }
+    let reason = match limit_run.limit {
+        None => return,
+        Some(LevelLimit::Moves(moves)) if move_history.len() >= moves => "Out of moves!",
+        Some(LevelLimit::Moves(_)) => return,
+        Some(LevelLimit::Seconds(seconds)) => {
+            limit_run.elapsed += time.delta_seconds();
+            if limit_run.elapsed < seconds {
+                return;
+            }
+            "Out of time!"
+        }
+    };
+    *limit_failure = LimitFailure {
+        level: limit_run.level,
+        reason,
+    };
+    // Nothing more to check until the retry has loaded.
+    limit_run.limit = None;
+    game_state.set(GameState::LevelFailed);
+}
+
+fn show_limit(mut commands: Commands) {
+    commands.spawn((
+        LimitText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+fn hide_limit(mut commands: Commands, limit_text_query: Query<Entity, With<LimitText>>) {
+    for entity in limit_text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn update_limit_text(
+    limit_run: Res<LimitRun>,
+    move_history: Res<MoveHistory>,
+    mut limit_text_query: Query<&mut Text, With<LimitText>>,
+) {
+    for mut text in &mut limit_text_query {
+        let section = &mut text.sections[0];
+        (section.value, section.style.color) = match limit_run.limit {
+            None => (String::new(), Color::WHITE),
+            Some(LevelLimit::Moves(moves)) => {
+                let left = moves.saturating_sub(move_history.len());
+                let color = if left <= 3 {
+                    Color::ORANGE
+                } else {
+                    Color::WHITE
+                };
+                (format!("Moves left: {}", left), color)
+            }
+            Some(LevelLimit::Seconds(seconds)) => {
+                let left = (seconds - limit_run.elapsed).max(0.0);
+                let color = if left <= 10.0 {
+                    Color::ORANGE
+                } else {
+                    Color::WHITE
+                };
+                (format!("Time left: {:.0}s", left.ceil()), color)
+            }
+        };
+    }
+}
+
+fn show_limit_failed(mut commands: Commands, limit_failure: Res<LimitFailure>) {
+    commands
+        .spawn((
+            LimitFailedMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                limit_failure.reason,
+                TextStyle {
+                    font_size: 32.0,
+                    color: Color::RED,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                "R: retry  L: level select",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn hide_limit_failed(
+    mut commands: Commands,
+    limit_failed_menu_query: Query<Entity, With<LimitFailedMenu>>,
+) {
+    for entity in limit_failed_menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_limit_failed_input(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    limit_failure: Res<LimitFailure>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::R) {
+        keyboard_input.reset(KeyCode::R);
+        next_level_writer.send(NextLevelEvent(limit_failure.level));
+        game_state.set(GameState::Playing);
+    } else if keyboard_input.just_pressed(KeyCode::L) {
+        keyboard_input.reset(KeyCode::L);
+        game_state.set(GameState::LevelSelect);
+    }
+}
+
+impl Plugin for LimitsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LimitsSetting::default())
+            .insert_resource(LimitRun::default())
+            .insert_resource(LimitFailure::default())
+            .add_systems(OnEnter(GameState::Playing), show_limit)
+            .add_systems(OnExit(GameState::Playing), hide_limit)
+            .add_systems(OnEnter(GameState::LevelFailed), show_limit_failed)
+            .add_systems(OnExit(GameState::LevelFailed), hide_limit_failed)
+            .add_systems(
+                Update,
+                (
+                    start_limit_run,
+                    check_limit.after(start_limit_run),
+                    update_limit_text.after(check_limit),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                handle_limit_failed_input
+                    .run_if(in_state(GameState::LevelFailed))
+                    .run_if(quit_prompt_closed),
+            );
+    }
+}
@@ -1,16 +1,133 @@
+mod accessibility_plugin;
+mod autosave_plugin;
+mod background_plugin;
+mod bundle;
+mod campaign_plugin;
+mod challenge_plugin;
+mod clip_plugin;
+mod credits_plugin;
+mod custom_levels_plugin;
+mod daily_plugin;
+mod debug_plugin;
+mod demo_plugin;
+mod difficulty;
+mod display_plugin;
+mod download_plugin;
 mod edit_plugin;
+mod enemy_plugin;
+mod energy_plugin;
+mod fragile_plugin;
+mod generator;
+mod ghost_plugin;
+mod goal_progress_plugin;
+#[cfg(feature = "headless")]
+mod headless;
+mod hint_plugin;
+mod hot_reload_plugin;
+mod invariants_plugin;
+mod juice_plugin;
+mod keys_plugin;
+mod level_code;
+mod level_complete_plugin;
+mod level_data;
+mod level_select_plugin;
+mod level_title_plugin;
+mod level_viewer_plugin;
+mod limits_plugin;
+mod medals_plugin;
+mod mouse_plugin;
+mod notes_plugin;
+mod onboarding_plugin;
+mod pause_plugin;
 mod play_plugin;
+mod practice_plugin;
+mod preview_plugin;
+mod profile;
+mod recommend;
+#[cfg(feature = "recorder")]
+mod recorder_plugin;
+mod replay_plugin;
+mod save_dialog_plugin;
+mod screenshot_plugin;
+mod share_plugin;
+mod sokoban_core;
+mod solver;
+mod speedrun_plugin;
+mod stats_plugin;
+mod storage;
+mod test_solve_plugin;
+mod theme_plugin;
+mod thumbnail_plugin;
+mod tilemap_plugin;
 mod tiles;
+mod touch_plugin;
+mod transition_plugin;
+mod tutorial_plugin;
 
+use accessibility_plugin::AccessibilityPlugin;
+use autosave_plugin::AutosavePlugin;
+use background_plugin::BackgroundPlugin;
 use bevy::{
     prelude::*,
-    sprite::Anchor,
     utils::{HashMap, HashSet},
     window::WindowResolution,
 };
+use campaign_plugin::CampaignPlugin;
+use challenge_plugin::ChallengePlugin;
+use clip_plugin::ClipPlugin;
+use credits_plugin::CreditsPlugin;
+use custom_levels_plugin::CustomLevelsPlugin;
+use daily_plugin::DailyPlugin;
+use debug_plugin::DebugPlugin;
+use demo_plugin::DemoPlugin;
+use display_plugin::DisplayPlugin;
+use download_plugin::DownloadPlugin;
 use edit_plugin::EditPlugin;
-use play_plugin::{LevelState, NextLevelEvent, PlayPlugin, Player, UndoStack};
-use tiles::spawn_floor;
+use enemy_plugin::{Enemy, EnemyPlugin};
+use energy_plugin::EnergyPlugin;
+use fragile_plugin::{Crumble, FragilePlugin};
+use ghost_plugin::GhostPlugin;
+use goal_progress_plugin::GoalProgressPlugin;
+use hint_plugin::HintPlugin;
+use hot_reload_plugin::HotReloadPlugin;
+use invariants_plugin::InvariantsPlugin;
+use juice_plugin::JuicePlugin;
+use keys_plugin::KeysPlugin;
+use level_complete_plugin::LevelCompletePlugin;
+use level_data::{spawn_level, LevelData, LevelMetadata};
+use level_select_plugin::LevelSelectPlugin;
+use level_title_plugin::LevelTitlePlugin;
+use level_viewer_plugin::LevelViewerPlugin;
+use limits_plugin::LimitsPlugin;
+use medals_plugin::MedalsPlugin;
+use mouse_plugin::MousePlugin;
+use notes_plugin::NotesPlugin;
+use onboarding_plugin::OnboardingPlugin;
+use pause_plugin::PausePlugin;
+use play_plugin::{
+    Block, Facing, Goal, Heavy, InputBuffer, LevelLoadedEvent, LevelState, Mirror, MoveHistory,
+    NextLevelEvent, Patrol, PlayPlugin, Player, STARTING_ENERGY,
+};
+use practice_plugin::PracticePlugin;
+use preview_plugin::PreviewPlugin;
+use profile::Profile;
+#[cfg(feature = "recorder")]
+use recorder_plugin::RecorderPlugin;
+use replay_plugin::{verify_share_code, ReplayPlugin};
+use save_dialog_plugin::SaveDialogPlugin;
+use screenshot_plugin::ScreenshotPlugin;
+use share_plugin::SharePlugin;
+use sokoban_core::{Position, Tile};
+use speedrun_plugin::SpeedrunPlugin;
+use stats_plugin::StatsPlugin;
+use test_solve_plugin::TestSolvePlugin;
+use theme_plugin::ThemePlugin;
+use thumbnail_plugin::ThumbnailPlugin;
+use tilemap_plugin::TilemapPlugin;
+use tiles::{spawn_decoration, ONE_WAY_DIRECTIONS, STARTING_FACING};
+use touch_plugin::TouchPlugin;
+use transition_plugin::TransitionPlugin;
+use tutorial_plugin::TutorialPlugin;
 
 #[derive(States, Default, Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum GameState {
@@ -19,24 +136,25 @@ pub enum GameState {
     Playing,
     Editing,
     Paused,
+    LevelSelect,
+    Challenges,
+    Credits,
+    Stats,
+    ImportCode,
+    CustomLevels,
+    PracticeSelect,
+    LevelComplete,
+    ChapterComplete,
+    LevelFailed,
 }
 
 pub const TILE_SIZE: f32 = 16.0;
 
-#[derive(Component, Copy, Clone, Eq, Hash, PartialEq, Debug)]
-pub struct Position {
-    x: i32,
-    y: i32,
-}
+// The window title, with the level being played added on.
+pub const GAME_TITLE: &str = "Sokoban!";
 
+// Where a grid position sits in the world, one tile per `TILE_SIZE`.
 impl Position {
-    fn add(&self, x: i32, y: i32) -> Position {
-        Position {
-            x: self.x + x,
-            y: self.y + y,
-        }
-    }
-
     fn from_translation(translation: Vec3) -> Position {
         Position {
             x: (translation.x / TILE_SIZE) as i32,
@@ -53,231 +171,230 @@ impl Position {
     }
 }
 
-#[derive(Clone, PartialEq)]
-pub enum Obstacle {
-    Block,
-    Wall,
-}
-
-pub fn level_one() -> Vec<Vec<i32>> {
-    vec![
-        vec![8, 8, 8, 8, 8, 8],
-        vec![8, 4, 0, 2, 1, 8],
-        vec![8, 8, 8, 0, 0, 8],
-        vec![0, 0, 8, 8, 8, 8],
-    ]
-}
-
-pub fn level_two() -> Vec<Vec<i32>> {
-    vec![
-        vec![8, 8, 8, 0, 8, 8, 8, 8],
-        vec![8, 4, 8, 8, 8, 2, 1, 8],
-        vec![8, 2, 0, 0, 0, 0, 2, 8],
-        vec![8, 0, 0, 0, 2, 0, 0, 8],
-        vec![8, 8, 8, 8, 8, 8, 8, 8],
-    ]
-}
+// Everything spawned for a level, despawned when the level is unloaded.
+#[derive(Component)]
+pub struct LevelEntity;
 
-pub fn level_three() -> Vec<Vec<i32>> {
-    vec![
-        vec![0, 8, 8, 8, 8, 8, 8, 8, 8, 8, 0],
-        vec![8, 8, 0, 0, 0, 0, 0, 0, 0, 8, 8],
-        vec![8, 4, 2, 2, 0, 0, 2, 0, 2, 1, 8],
-        vec![8, 2, 2, 0, 0, 0, 2, 2, 2, 2, 8],
-        vec![8, 0, 0, 0, 0, 0, 0, 0, 2, 2, 8],
-        vec![8, 2, 0, 0, 0, 0, 0, 0, 0, 0, 8],
-        vec![8, 8, 0, 0, 0, 0, 0, 0, 0, 8, 8],
-        vec![0, 8, 8, 8, 8, 8, 8, 8, 8, 8, 0],
-    ]
-}
+#[derive(Component)]
+pub struct MainCamera;
 
-pub fn level_four() -> Vec<Vec<i32>> {
-    vec![
-        vec![8, 8, 8, 0, 0],
-        vec![8, 1, 8, 8, 0],
-        vec![8, 4, 0, 8, 8],
-        vec![8, 2, 0, 0, 8],
-        vec![8, 0, 0, 0, 8],
-        vec![8, 8, 8, 8, 8],
-    ]
+#[derive(Clone, PartialEq, Debug)]
+pub enum Obstacle {
+    // Blocks carry their color, they only count on a goal of the same color.
+    Block(u8),
+    // An open pit, filled in by pushing a block into it.
+    Pit,
 }
 
-fn get_floor_positions(
-    player_position: Position,
-    obstacles: HashMap<Position, (Entity, Obstacle)>,
-) -> Vec<Position> {
-    fn is_not_wall(obstacle: Option<(Entity, Obstacle)>) -> bool {
-        obstacle.is_none() || obstacle.unwrap().1 == Obstacle::Block
-    }
-
-    let mut visited = HashSet::default();
-    let mut to_visit = vec![player_position];
-
-    while !to_visit.is_empty() {
-        let current_position = to_visit.pop().unwrap();
-        if visited.contains(&current_position) {
-            continue;
-        }
-        visited.insert(current_position);
-
-        let up_position = current_position.add(0, 1);
-        if is_not_wall(obstacles.get(&up_position).cloned()) {
-            to_visit.push(up_position);
-        }
-        let down_position = current_position.add(0, -1);
-        if is_not_wall(obstacles.get(&down_position).cloned()) {
-            to_visit.push(down_position);
-        }
-        let right_position = current_position.add(1, 0);
-        if is_not_wall(obstacles.get(&right_position).cloned()) {
-            to_visit.push(right_position);
-        }
-        let left_position = current_position.add(-1, 0);
-        if is_not_wall(obstacles.get(&left_position).cloned()) {
-            to_visit.push(left_position);
+// FNV-1a, so the hash is stable across builds and platforms.
+pub fn level_hash(level_layout: &[Vec<i32>]) -> u32 {
+    let mut hash: u32 = 0x811c9dc5;
+    for row in level_layout {
+        for tile in row.iter().chain([&-1]) {
+            hash ^= *tile as u32;
+            hash = hash.wrapping_mul(0x01000193);
         }
     }
-
-    visited.into_iter().collect()
+    hash
 }
 
 fn level_setup(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
     level: i32,
     level_layout: Vec<Vec<i32>>,
+    metadata: LevelMetadata,
 ) {
-    let last_row_index = level_layout.len() as i32;
-    let last_col_index = level_layout.first().unwrap().len() as i32;
-    let camera_position = Vec3::new(
-        last_col_index as f32 * TILE_SIZE / 2.0,
-        -(last_row_index as f32 * TILE_SIZE) / 2.0,
-        1000.0,
-    );
-
-    commands.spawn(Camera2dBundle {
-        transform: Transform {
-            translation: camera_position,
-            scale: Vec3::new(0.5, 0.5, 1.0),
-            ..default()
-        },
-        ..default()
-    });
-
+    let mut walls = HashSet::default();
     let mut obstacles = HashMap::default();
     let mut goals = HashMap::default();
-    let mut player_position = None;
-
-    let wall_texture: Handle<Image> = asset_server.load("wall.png");
-    let goal_texture: Handle<Image> = asset_server.load("goal.png");
-    let block_texture: Handle<Image> = asset_server.load("block.png");
-    let player_texture: Handle<Image> = asset_server.load("player.png");
+    let mut switches = HashSet::default();
+    let mut doors = HashMap::default();
+    let mut teleporter_pairs: HashMap<i32, Vec<Position>> = HashMap::default();
+    let mut portal_pairs: HashMap<i32, Vec<Position>> = HashMap::default();
+    let mut ice = HashSet::default();
+    let mut one_way = HashMap::default();
+    let mut pickups = HashMap::default();
+    let mut players = HashMap::default();
+    // The first player in reading order starts with control.
+    let mut active_player = None;
+    let mut mirror_position = None;
+    let mut exit = None;
+    let mut enemies = HashMap::default();
+    let mut keys = HashMap::default();
+    let mut locked_doors = HashMap::default();
+    let mut fragile = HashMap::default();
 
-    for (row_index, row) in level_layout.iter().enumerate() {
-        for (col_index, col) in row.iter().enumerate() {
-            match col {
-                1 => {
-                    player_position = Some(Position {
-                        x: col_index as i32,
-                        y: row_index as i32,
-                    });
-                    commands.spawn((
-                        Player {
-                            is_moving: false,
-                            move_timer: Timer::from_seconds(0.3, TimerMode::Once),
-                        },
-                        SpriteBundle {
-                            sprite: Sprite {
-                                anchor: Anchor::TopLeft,
-                                ..default()
-                            },
-                            texture: player_texture.clone(),
-                            transform: Transform::from_translation(
-                                player_position.unwrap().to_translation(),
-                            ),
-                            ..default()
-                        },
-                    ));
-                }
-                2 => {
-                    let position = Position {
-                        x: col_index as i32,
-                        y: row_index as i32,
-                    };
-
-                    let block_id = commands
-                        .spawn(SpriteBundle {
-                            sprite: Sprite {
-                                anchor: Anchor::TopLeft,
-                                ..default()
-                            },
-                            texture: block_texture.clone(),
-                            transform: Transform::from_translation(position.to_translation()),
-                            ..default()
-                        })
-                        .id();
-                    obstacles.insert(
-                        Position {
-                            x: col_index as i32,
-                            y: row_index as i32,
-                        },
-                        (block_id, Obstacle::Block),
-                    );
-                }
-                4 => {
-                    let position = Position {
-                        x: col_index as i32,
-                        y: row_index as i32,
-                    };
-
-                    let goal_id = commands
-                        .spawn(SpriteBundle {
-                            sprite: Sprite {
-                                anchor: Anchor::TopLeft,
-                                ..default()
-                            },
-                            texture: goal_texture.clone(),
-                            transform: Transform::from_translation(position.to_translation_z(0.5)),
-                            ..default()
-                        })
-                        .id();
-                    goals.insert(position, goal_id);
-                }
-                8 => {
-                    let position = Position {
-                        x: col_index as i32,
-                        y: row_index as i32,
-                    };
-
-                    let wall_id = commands
-                        .spawn(SpriteBundle {
-                            sprite: Sprite {
-                                anchor: Anchor::TopLeft,
-                                ..default()
-                            },
-                            texture: wall_texture.clone(),
-                            transform: Transform::from_translation(position.to_translation()),
-                            ..default()
-                        })
-                        .id();
-                    obstacles.insert(position, (wall_id, Obstacle::Wall));
-                }
-                0 | _ => {}
+    let level_data = LevelData::from_layout(&level_layout);
+    for (position, tile, entity) in spawn_level(&mut commands, &level_data) {
+        match tile {
+            Tile::Player => {
+                commands.entity(entity).insert((
+                    Player {
+                        is_moving: false,
+                        move_timer: Timer::from_seconds(0.3, TimerMode::Once),
+                    },
+                    Facing(STARTING_FACING),
+                ));
+                players.insert(entity, position);
+                active_player.get_or_insert(entity);
+            }
+            Tile::Block(color) => {
+                commands.entity(entity).insert(Block);
+                obstacles.insert(position, (entity, Obstacle::Block(color)));
+            }
+            Tile::HeavyBlock => {
+                commands.entity(entity).insert((Block, Heavy));
+                obstacles.insert(position, (entity, Obstacle::Block(0)));
+            }
+            Tile::Goal(color) => {
+                commands.entity(entity).insert(Goal);
+                goals.insert(position, (entity, color));
             }
+            Tile::Wall => {
+                walls.insert(position);
+            }
+            Tile::Switch => {
+                switches.insert(position);
+            }
+            Tile::Door => {
+                doors.insert(position, entity);
+            }
+            Tile::Teleporter(pair) => {
+                teleporter_pairs.entry(pair).or_default().push(position);
+            }
+            Tile::Ice => {
+                ice.insert(position);
+            }
+            Tile::OneWay(direction) => {
+                one_way.insert(position, ONE_WAY_DIRECTIONS[direction]);
+            }
+            Tile::Pickup(energy) => {
+                pickups.insert(position, (entity, energy));
+            }
+            Tile::Pit => {
+                obstacles.insert(position, (entity, Obstacle::Pit));
+            }
+            Tile::Mirror => {
+                commands.entity(entity).insert(Mirror);
+                mirror_position = Some(position);
+            }
+            Tile::Exit => {
+                exit = Some(position);
+            }
+            Tile::Enemy(direction) => {
+                commands.entity(entity).insert(Enemy);
+                enemies.insert(
+                    entity,
+                    Patrol {
+                        position,
+                        step: ONE_WAY_DIRECTIONS[direction],
+                    },
+                );
+            }
+            Tile::Key(color) => {
+                keys.insert(position, (entity, color));
+            }
+            Tile::LockedDoor(color) => {
+                locked_doors.insert(position, (entity, color));
+            }
+            Tile::Fragile => {
+                commands.entity(entity).insert(Crumble::default());
+                fragile.insert(position, entity);
+            }
+            Tile::Portal(pair) => {
+                portal_pairs.entry(pair).or_default().push(position);
+            }
+            Tile::Empty => {}
         }
     }
+    for (x, y, decoration) in &metadata.decorations {
+        commands.spawn((
+            LevelEntity,
+            spawn_decoration(Position { x: *x, y: *y }, *decoration),
+        ));
+    }
 
-    for floor_position in get_floor_positions(player_position.unwrap(), obstacles.clone()) {
-        commands.spawn(spawn_floor(&asset_server, floor_position));
+    let mut teleporters = HashMap::default();
+    for positions in teleporter_pairs.values() {
+        if let [first, second] = positions[..] {
+            teleporters.insert(first, second);
+            teleporters.insert(second, first);
+        }
+    }
+    let mut portals = HashMap::default();
+    for positions in portal_pairs.values() {
+        if let [first, second] = positions[..] {
+            portals.insert(first, second);
+            portals.insert(second, first);
+        }
     }
 
     commands.insert_resource(LevelState {
         current_level: level,
-        obstacles: obstacles,
-        goals: goals,
-        player_position: player_position.unwrap(),
+        layout: level_layout,
+        metadata,
+        walls,
+        obstacles,
+        goals,
+        switches,
+        doors,
+        teleporters,
+        portals,
+        ice,
+        one_way,
+        energy: (!pickups.is_empty()).then_some(STARTING_ENERGY),
+        pickups,
+        collected: Vec::new(),
+        filled_pits: Vec::new(),
+        players,
+        active_player: active_player.unwrap(),
+        mirror_position,
+        exit,
+        enemies,
+        enemy_turns: Vec::new(),
+        caught: false,
+        keys,
+        locked_doors,
+        inventory: Vec::new(),
+        taken_keys: Vec::new(),
+        unlocked_doors: Vec::new(),
+        fragile,
+        broken: Vec::new(),
+        strained: Default::default(),
+        given_way: Vec::new(),
+        moves: String::new(),
     });
-    commands.insert_resource(UndoStack(Vec::default()));
+    commands.insert_resource(MoveHistory::default());
+    commands.insert_resource(InputBuffer::default());
+}
+
+fn setup_camera(mut commands: Commands) {
+    commands.spawn((
+        MainCamera,
+        Camera2dBundle {
+            transform: Transform::from_scale(Vec3::new(0.5, 0.5, 1.0))
+                .with_translation(Vec3::new(0.0, 0.0, 1000.0)),
+            // Sees a little past the floor at 0, for the background behind it.
+            ..Camera2dBundle::new_with_far(1010.0)
+        },
+    ));
+}
+
+fn center_camera(
+    mut level_loaded_reader: EventReader<LevelLoadedEvent>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    let Some(level_loaded) = level_loaded_reader.read().last() else {
+        return;
+    };
+    let Ok(mut camera_transform) = camera_query.get_single_mut() else {
+        return;
+    };
+    camera_transform.translation = Vec3::new(
+        level_loaded.width as f32 * TILE_SIZE / 2.0,
+        -(level_loaded.height as f32 * TILE_SIZE) / 2.0,
+        1000.0,
+    );
 }
 
 fn start_playing(
@@ -288,35 +405,117 @@ fn start_playing(
     game_state.set(GameState::Playing);
 }
 
-fn unpause_game(
-    mut keyboard_input: ResMut<Input<KeyCode>>,
-    mut game_state: ResMut<NextState<GameState>>,
-) {
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        keyboard_input.reset(KeyCode::Space);
-        game_state.set(GameState::Playing);
+fn main() {
+    if let Some(code) = std::env::args().skip_while(|arg| arg != "--verify").nth(1) {
+        match verify_share_code(&code) {
+            Ok(replay) => println!(
+                "Verified: level {} solved in {} moves and {} pushes",
+                replay.level, replay.moves, replay.pushes
+            ),
+            Err(error) => println!("Could not verify share code: {}", error),
+        }
+        return;
+    }
+    #[cfg(feature = "headless")]
+    if let [level, moves] = &std::env::args()
+        .skip_while(|arg| arg != "--simulate")
+        .skip(1)
+        .take(2)
+        .collect::<Vec<_>>()[..]
+    {
+        let result = level
+            .parse()
+            .map_err(|_| format!("Bad level number {}", level))
+            .and_then(|level| headless::simulate_campaign_level(level, moves));
+        match result {
+            Ok(summary) => println!("{}", summary),
+            Err(error) => println!("Could not simulate: {}", error),
+        }
+        return;
+    }
+    // Run while packaging, a non-zero exit keeps broken packs from shipping.
+    if std::env::args().any(|arg| arg == "--bundle-packs") {
+        match bundle::bundle_packs(std::path::Path::new(bundle::PACKS_DIRECTORY)) {
+            Ok(manifest) => println!("Bundled {} packs", manifest.packs.len()),
+            Err(problems) => {
+                for problem in problems {
+                    eprintln!("{}", problem);
+                }
+                std::process::exit(1);
+            }
+        }
+        return;
     }
-}
 
-fn main() {
-    App::new()
-        .add_plugins(
-            DefaultPlugins
-                .set(ImagePlugin::default_nearest())
-                .set(WindowPlugin {
-                    primary_window: Some(Window {
-                        title: "Sokoban!".to_string(),
-                        resolution: WindowResolution::new(500.0, 500.0),
-                        ..default()
-                    }),
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(ImagePlugin::default_nearest())
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: GAME_TITLE.to_string(),
+                    resolution: WindowResolution::new(500.0, 500.0),
                     ..default()
                 }),
-        )
-        .add_systems(Update, bevy::window::close_on_esc)
-        .add_state::<GameState>()
-        .add_systems(Update, start_playing.run_if(in_state(GameState::Startup)))
-        .add_systems(Update, unpause_game.run_if(in_state(GameState::Paused)))
-        .add_plugins(PlayPlugin)
-        .add_plugins(EditPlugin)
-        .run();
+                ..default()
+            }),
+    )
+    .add_state::<GameState>()
+    .add_systems(Startup, setup_camera)
+    .add_systems(Update, center_camera)
+    .add_systems(Update, start_playing.run_if(in_state(GameState::Startup)))
+    .insert_resource(Profile::default())
+    .add_plugins(PlayPlugin)
+    .add_plugins(CampaignPlugin)
+    .add_plugins(LevelCompletePlugin)
+    .add_plugins(MedalsPlugin)
+    .add_plugins(EnergyPlugin)
+    .add_plugins(EnemyPlugin)
+    .add_plugins(KeysPlugin)
+    .add_plugins(FragilePlugin)
+    .add_plugins(GoalProgressPlugin)
+    .add_plugins(HintPlugin)
+    .add_plugins(DemoPlugin)
+    .add_plugins(EditPlugin)
+    .add_plugins(LevelViewerPlugin)
+    .add_plugins(LevelSelectPlugin)
+    .add_plugins(ChallengePlugin)
+    .add_plugins(DailyPlugin)
+    .add_plugins(CreditsPlugin)
+    .add_plugins(DownloadPlugin)
+    .add_plugins(PausePlugin)
+    .add_plugins(PracticePlugin)
+    .add_plugins(PreviewPlugin)
+    .add_plugins(NotesPlugin)
+    .add_plugins(MousePlugin)
+    .add_plugins(TouchPlugin)
+    .add_plugins(OnboardingPlugin)
+    .add_plugins(TutorialPlugin)
+    .add_plugins(ReplayPlugin)
+    .add_plugins(ClipPlugin)
+    .add_plugins(ThumbnailPlugin)
+    .add_plugins(StatsPlugin)
+    .add_plugins(TransitionPlugin)
+    .add_plugins(InvariantsPlugin)
+    .add_plugins(JuicePlugin)
+    .add_plugins(ThemePlugin)
+    .add_plugins(TilemapPlugin)
+    .add_plugins(BackgroundPlugin)
+    .add_plugins(DisplayPlugin)
+    .add_plugins(LimitsPlugin)
+    .add_plugins(SpeedrunPlugin)
+    .add_plugins(GhostPlugin)
+    .add_plugins(SharePlugin)
+    .add_plugins(CustomLevelsPlugin)
+    .add_plugins(LevelTitlePlugin)
+    .add_plugins(SaveDialogPlugin)
+    .add_plugins(TestSolvePlugin)
+    .add_plugins(HotReloadPlugin)
+    .add_plugins(AutosavePlugin)
+    .add_plugins(AccessibilityPlugin)
+    .add_plugins(ScreenshotPlugin)
+    .add_plugins(DebugPlugin);
+    #[cfg(feature = "recorder")]
+    app.add_plugins(RecorderPlugin);
+    app.run();
 }
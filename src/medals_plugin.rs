@@ -0,0 +1,218 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    play_plugin::{LevelCompletedEvent, LevelPack, LevelState, NextLevelEvent, CAMPAIGN_PACK},
+    profile::Profile,
+    GameState,
+};
+
+const MEDALS_FILE: &str = "medals.ron";
+const SKIPS_FILE: &str = "skips.ron";
+const SKIP_KEY: KeyCode = KeyCode::S;
+// Stars spent to skip a level.
+const SKIP_COST: usize = 3;
+
+pub struct MedalsPlugin;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Medal {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+impl Medal {
+    // Gold for matching par, silver for staying within half as many moves again,
+    // bronze for any solve.
+    pub fn for_moves(moves: usize, par: usize) -> Self {
+        if moves <= par {
+            Medal::Gold
+        } else if moves <= par + par.div_ceil(2) {
+            Medal::Silver
+        } else {
+            Medal::Bronze
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Medal::Bronze => "Bronze",
+            Medal::Silver => "Silver",
+            Medal::Gold => "Gold",
+        }
+    }
+
+    // What the medal is worth towards skipping levels.
+    pub fn stars(&self) -> usize {
+        match self {
+            Medal::Bronze => 1,
+            Medal::Silver => 2,
+            Medal::Gold => 3,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Medal::Bronze => Color::rgb(0.8, 0.5, 0.2),
+            Medal::Silver => Color::SILVER,
+            Medal::Gold => Color::GOLD,
+        }
+    }
+}
+
+// The best medal won on each campaign level.
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct MedalCase(pub BTreeMap<i32, Medal>);
+
+// Campaign levels skipped by spending stars. They can still be played, and a medal won on
+// one later shows in place of the skip.
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct SkippedLevels(pub BTreeSet<i32>);
+
+#[derive(SystemParam)]
+pub struct Medals<'w> {
+    pub medal_case: Res<'w, MedalCase>,
+    pub skipped_levels: Res<'w, SkippedLevels>,
+}
+
+impl Medals<'_> {
+    // Stars earned from medals that haven't been spent on skips.
+    pub fn spare_stars(&self) -> usize {
+        let earned: usize = self.medal_case.0.values().map(Medal::stars).sum();
+        earned.saturating_sub(self.skipped_levels.0.len() * SKIP_COST)
+    }
+
+    // Only campaign levels without a medal yet can be skipped.
+    fn can_skip(&self, level_pack: &LevelPack, level: i32) -> bool {
+        level_pack.name == CAMPAIGN_PACK
+            && !self.medal_case.0.contains_key(&level)
+            && !self.skipped_levels.0.contains(&level)
+            && self.spare_stars() >= SKIP_COST
+    }
+}
+
+#[derive(Component)]
+struct SkipText;
+
+fn load_medals(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(profile.load::<MedalCase>(MEDALS_FILE));
+    commands.insert_resource(profile.load::<SkippedLevels>(SKIPS_FILE));
+}
+
+fn award_medals(
+    profile: Res<Profile>,
+    level_pack: Res<LevelPack>,
+    mut medal_case: ResMut<MedalCase>,
+    mut level_completed_reader: EventReader<LevelCompletedEvent>,
+) {
+    for level_completed in level_completed_reader.read() {
+        if level_pack.name != CAMPAIGN_PACK {
+            continue;
+        }
+        let Some(par) = level_pack.metadata(level_completed.level).par else {
+            continue;
+        };
+        let medal = Medal::for_moves(level_completed.moves.len(), par);
+        let best = medal_case.0.entry(level_completed.level).or_insert(medal);
+        if medal > *best {
+            *best = medal;
+        }
+        profile.save(MEDALS_FILE, &*medal_case);
+    }
+}
+
+// Moves on as if the level was won, without a medal for it.
+fn skip_level(
+    keyboard_input: Res<Input<KeyCode>>,
+    profile: Res<Profile>,
+    level_pack: Res<LevelPack>,
+    level_state: Res<LevelState>,
+    mut skipped_levels: ResMut<SkippedLevels>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard_input.just_pressed(SKIP_KEY) {
+        return;
+    }
+    let level = level_state.current_level;
+    skipped_levels.0.insert(level);
+    profile.save(SKIPS_FILE, &*skipped_levels);
+    if level_pack.level(level + 1).is_some() {
+        next_level_writer.send(NextLevelEvent(level + 1));
+    } else {
+        game_state.set(level_pack.return_state);
+    }
+}
+
+fn can_skip(medals: Medals, level_pack: Res<LevelPack>, level_state: Res<LevelState>) -> bool {
+    medals.can_skip(&level_pack, level_state.current_level)
+}
+
+fn show_skip_text(mut commands: Commands) {
+    commands.spawn((
+        SkipText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::GOLD,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(100.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+fn hide_skip_text(mut commands: Commands, text_query: Query<Entity, With<SkipText>>) {
+    for entity in text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn update_skip_text(
+    medals: Medals,
+    level_pack: Res<LevelPack>,
+    level_state: Res<LevelState>,
+    mut text_query: Query<&mut Text, With<SkipText>>,
+) {
+    let message = if medals.can_skip(&level_pack, level_state.current_level) {
+        format!(
+            "S: skip this level for {} of your {} stars",
+            SKIP_COST,
+            medals.spare_stars()
+        )
+    } else {
+        String::new()
+    };
+    for mut text in &mut text_query {
+        if text.sections[0].value != message {
+            text.sections[0].value = message.clone();
+        }
+    }
+}
+
+impl Plugin for MedalsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MedalCase::default())
+            .insert_resource(SkippedLevels::default())
+            .add_systems(Startup, load_medals)
+            .add_systems(OnEnter(GameState::Playing), show_skip_text)
+            .add_systems(OnExit(GameState::Playing), hide_skip_text)
+            .add_systems(
+                Update,
+                (
+                    award_medals,
+                    (skip_level.run_if(can_skip), update_skip_text)
+                        .run_if(in_state(GameState::Playing)),
+                ),
+            );
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, sprite::Anchor, utils::HashMap, window::PrimaryWindow};
+
+use crate::{
+    play_plugin::{InputBuffer, LevelState, Player},
+    GameState, LevelEntity, MainCamera, Obstacle, Position, TILE_SIZE,
+};
+
+pub struct MousePlugin;
+
+#[derive(Resource, Default)]
+pub struct MouseMode {
+    pub enabled: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct HoveredTile(pub Option<Position>);
+
+// Shortest walks from the player to every reachable tile, rebuilt when the player
+// moves or the board changes.
+#[derive(Resource, Default)]
+pub struct WalkPaths {
+    from: Option<Position>,
+    parents: HashMap<Position, Position>,
+}
+
+impl WalkPaths {
+    fn rebuild(&mut self, level_state: &LevelState) {
+        let start = level_state.player_position();
+        let width = level_state
+            .layout
+            .iter()
+            .map(|row| row.len())
+            .max()
+            .unwrap_or(0) as i32;
+        let height = level_state.layout.len() as i32;
+
+        self.from = Some(start);
+        self.parents.clear();
+        let mut to_visit = VecDeque::from([start]);
+        while let Some(position) = to_visit.pop_front() {
+            for (x, y) in [(0, -1), (0, 1), (-1, 0), (1, 0)] {
+                let next = position.add(x, y);
+                if next.x < 0 || next.y < 0 || next.x >= width || next.y >= height {
+                    continue;
+                }
+                if next == start
+                    || self.parents.contains_key(&next)
+                    || level_state.is_blocked(&next)
+                    || level_state.mirror_position == Some(next)
+                    || level_state.is_idle_player(&next)
+                {
+                    continue;
+                }
+                self.parents.insert(next, position);
+                to_visit.push_back(next);
+            }
+        }
+    }
+
+    // The tiles walked through to reach `to`, not including the start.
+    pub fn path_to(&self, to: Position) -> Option<Vec<Position>> {
+        let start = self.from?;
+        let mut path = vec![to];
+        let mut current = to;
+        while let Some(parent) = self.parents.get(&current) {
+            if *parent == start {
+                path.reverse();
+                return Some(path);
+            }
+            path.push(*parent);
+            current = *parent;
+        }
+        None
+    }
+}
+
+// The steps that walk `path`, starting next to `from`.
+fn path_steps(from: Position, path: &[Position]) -> Vec<(i32, i32)> {
+    let mut previous = from;
+    path.iter()
+        .map(|position| {
+            let step = (position.x - previous.x, position.y - previous.y);
+            previous = *position;
+            step
+        })
+        .collect()
+}
+
+#[derive(Component)]
+struct PathPreview;
+
+fn toggle_mouse_mode(keyboard_input: Res<Input<KeyCode>>, mut mouse_mode: ResMut<MouseMode>) {
+    if keyboard_input.just_pressed(KeyCode::M) {
+        mouse_mode.enabled = !mouse_mode.enabled;
+    }
+}
+
+pub fn cursor_tile(
+    window_query: &Query<&Window, With<PrimaryWindow>>,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> Option<Position> {
+    let cursor = window_query.get_single().ok()?.cursor_position()?;
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+    let world = camera.viewport_to_world_2d(camera_transform, cursor)?;
+    Some(Position {
+        x: (world.x / TILE_SIZE).floor() as i32,
+        y: (-world.y / TILE_SIZE).floor() as i32,
+    })
+}
+
+fn update_hovered_tile(
+    mouse_mode: Res<MouseMode>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut hovered_tile: ResMut<HoveredTile>,
+) {
+    let hovered = if mouse_mode.enabled {
+        cursor_tile(&window_query, &camera_query)
+    } else {
+        None
+    };
+    if hovered_tile.0 != hovered {
+        hovered_tile.0 = hovered;
+    }
+}
+
+fn update_walk_paths(level_state: Res<LevelState>, mut walk_paths: ResMut<WalkPaths>) {
+    if walk_paths.from != Some(level_state.player_position()) || level_state.is_changed() {
+        walk_paths.rebuild(&level_state);
+    }
+}
+
+// A block being dragged, and once released every tile it should be on until the
+// pushes are done.
+#[derive(Resource, Default)]
+struct BlockDrag {
+    grabbed: Option<Position>,
+    pushing: Option<(Entity, Vec<Position>)>,
+}
+
+// The steps to take and the tiles the block is pushed across.
+type PushPlan = (Vec<(i32, i32)>, Vec<Position>);
+
+// Pushing a block `count` tiles in a straight line only works if the player can
+// get behind it first and every tile it crosses is plain floor. Working back from
+// the far end, each tile has to take the block, and the one behind it the player.
+fn plan_push_line(
+    level_state: &LevelState,
+    walk_paths: &WalkPaths,
+    block: Position,
+    step: (i32, i32),
+    count: i32,
+) -> Option<PushPlan> {
+    let from = walk_paths.from?;
+    let line: Vec<Position> = (1..=count)
+        .map(|distance| block.add(step.0 * distance, step.1 * distance))
+        .collect();
+    for (index, tile) in line.iter().enumerate().rev() {
+        let is_last = index + 1 == line.len();
+        let is_free = !level_state.is_blocked(tile) || (is_last && level_state.is_open_pit(tile));
+        if !is_free
+            || level_state.is_idle_player(tile)
+            || level_state.mirror_position == Some(*tile)
+            || (!is_last
+                && (level_state.ice.contains(tile) || level_state.teleporters.contains_key(tile)))
+            || !level_state.allows_move(tile.add(-step.0, -step.1), step)
+        {
+            return None;
+        }
+    }
+
+    let stand = block.add(-step.0, -step.1);
+    let path = if stand == from {
+        Vec::new()
+    } else {
+        walk_paths.path_to(stand)?
+    };
+    let mut steps = path_steps(from, &path);
+    steps.extend(std::iter::repeat_n(step, count as usize));
+    Some((steps, line))
+}
+
+// Clicking a floor tile walks there. Clicking a block pushes it once from whichever
+// side is closest, dragging it along a row or column pushes it all the way.
+fn click_to_move(
+    mouse_input: Res<Input<MouseButton>>,
+    hovered_tile: Res<HoveredTile>,
+    walk_paths: Res<WalkPaths>,
+    level_state: Res<LevelState>,
+    player_query: Query<&Player>,
+    mut input_buffer: ResMut<InputBuffer>,
+    mut block_drag: ResMut<BlockDrag>,
+) {
+    let Some(tile) = hovered_tile.0 else {
+        return;
+    };
+    if player_query
+        .get(level_state.active_player)
+        .is_ok_and(|player| player.is_moving)
+    {
+        return;
+    }
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        if let Some(path) = walk_paths.path_to(tile) {
+            input_buffer.walk(path_steps(walk_paths.from.unwrap_or(tile), &path));
+        } else if matches!(
+            level_state.obstacles.get(&tile),
+            Some((_, Obstacle::Block(_)))
+        ) {
+            block_drag.grabbed = Some(tile);
+        }
+        return;
+    }
+    if !mouse_input.just_released(MouseButton::Left) {
+        return;
+    }
+    let Some(block) = block_drag.grabbed.take() else {
+        return;
+    };
+    let Some((block_entity, _)) = level_state.obstacles.get(&block) else {
+        return;
+    };
+
+    let plan = if tile == block {
+        [(0, -1), (0, 1), (-1, 0), (1, 0)]
+            .into_iter()
+            .filter_map(|step| plan_push_line(&level_state, &walk_paths, block, step, 1))
+            .min_by_key(|(steps, _)| steps.len())
+    } else if tile.x == block.x || tile.y == block.y {
+        let step = ((tile.x - block.x).signum(), (tile.y - block.y).signum());
+        let count = (tile.x - block.x).abs() + (tile.y - block.y).abs();
+        plan_push_line(&level_state, &walk_paths, block, step, count)
+    } else {
+        None
+    };
+    if let Some((steps, mut line)) = plan {
+        input_buffer.walk(steps);
+        line.push(block);
+        block_drag.pushing = Some((*block_entity, line));
+    }
+}
+
+// Stops a drag's pushes as soon as the block ends up somewhere off the planned line,
+// like the mirror shoving it sideways.
+fn follow_block_drag(
+    level_state: Res<LevelState>,
+    mut input_buffer: ResMut<InputBuffer>,
+    mut block_drag: ResMut<BlockDrag>,
+) {
+    if !level_state.is_changed() {
+        return;
+    }
+    let Some((block_entity, line)) = &block_drag.pushing else {
+        return;
+    };
+    if !input_buffer.is_walking() {
+        block_drag.pushing = None;
+        return;
+    }
+    let on_line = level_state
+        .obstacles
+        .iter()
+        .any(|(position, (entity, _))| entity == block_entity && line.contains(position));
+    if !on_line {
+        input_buffer.walk([]);
+        block_drag.pushing = None;
+    }
+}
+
+fn draw_path_preview(
+    mut commands: Commands,
+    hovered_tile: Res<HoveredTile>,
+    walk_paths: Res<WalkPaths>,
+    preview_query: Query<Entity, With<PathPreview>>,
+) {
+    if !hovered_tile.is_changed() && !walk_paths.is_changed() {
+        return;
+    }
+    for entity in preview_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let Some(path) = hovered_tile.0.and_then(|tile| walk_paths.path_to(tile)) else {
+        return;
+    };
+    let Some(destination) = path.last() else {
+        return;
+    };
+
+    commands.spawn((
+        LevelEntity,
+        PathPreview,
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.3, 0.8, 1.0, 0.4),
+                custom_size: Some(Vec2::splat(TILE_SIZE)),
+                anchor: Anchor::TopLeft,
+                ..default()
+            },
+            transform: Transform::from_translation(destination.to_translation_z(0.8)),
+            ..default()
+        },
+    ));
+
+    // A dot on every tile and one between each pair makes a dotted line.
+    let centre = Vec3::new(TILE_SIZE / 2.0, -TILE_SIZE / 2.0, 0.0);
+    let mut previous = walk_paths
+        .from
+        .map(|from| from.to_translation_z(0.9) + centre);
+    for position in &path {
+        let dot = position.to_translation_z(0.9) + centre;
+        let dots = match previous {
+            Some(previous) => vec![(previous + dot) / 2.0, dot],
+            None => vec![dot],
+        };
+        for translation in dots {
+            commands.spawn((
+                LevelEntity,
+                PathPreview,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgb(0.3, 0.8, 1.0),
+                        custom_size: Some(Vec2::splat(2.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(translation),
+                    ..default()
+                },
+            ));
+        }
+        previous = Some(dot);
+    }
+}
+
+impl Plugin for MousePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MouseMode::default())
+            .insert_resource(HoveredTile::default())
+            .insert_resource(WalkPaths::default())
+            .insert_resource(BlockDrag::default())
+            .add_systems(
+                Update,
+                (
+                    toggle_mouse_mode,
+                    update_hovered_tile.after(toggle_mouse_mode),
+                    update_walk_paths,
+                    click_to_move
+                        .after(update_hovered_tile)
+                        .after(update_walk_paths),
+                    follow_block_drag.after(click_to_move),
+                    draw_path_preview
+                        .after(update_hovered_tile)
+                        .after(update_walk_paths),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
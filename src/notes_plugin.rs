@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    campaign_plugin::Campaign,
+    level_hash,
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{LevelLoadedEvent, LevelState, LevelUnloadedEvent},
+    profile::Profile,
+    GameState,
+};
+
+const NOTES_FILE: &str = "notes.ron";
+const MAX_NOTE_LENGTH: usize = 120;
+
+pub struct NotesPlugin;
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+// Keyed by level hash so notes follow the layout rather than its slot in a pack.
+pub struct LevelNotes(pub BTreeMap<u32, String>);
+
+#[derive(Resource, Default)]
+pub struct NoteEditor {
+    pub editing: bool,
+    buffer: String,
+}
+
+#[derive(Component)]
+struct NotePanel;
+
+#[derive(Component)]
+struct LevelNoteText {
+    level: i32,
+    timer: Timer,
+}
+
+fn note_text_style() -> TextStyle {
+    TextStyle {
+        font_size: 16.0,
+        ..default()
+    }
+}
+
+fn load_notes(mut commands: Commands, profile: Res<Profile>, campaign: Res<Campaign>) {
+    let mut level_notes = profile.load::<LevelNotes>(NOTES_FILE);
+    if campaign.rekey_by_level_hash(&mut level_notes.0) {
+        profile.save(NOTES_FILE, &level_notes);
+    }
+    commands.insert_resource(level_notes);
+}
+
+fn show_note_panel(mut commands: Commands) {
+    commands.spawn((
+        NotePanel,
+        TextBundle::from_section("", note_text_style()).with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+fn hide_note_panel(
+    mut commands: Commands,
+    mut note_editor: ResMut<NoteEditor>,
+    note_panel_query: Query<Entity, With<NotePanel>>,
+) {
+    note_editor.editing = false;
+    for entity in note_panel_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn edit_note(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut character_reader: EventReader<ReceivedCharacter>,
+    profile: Res<Profile>,
+    level_state: Res<LevelState>,
+    mut level_notes: ResMut<LevelNotes>,
+    mut note_editor: ResMut<NoteEditor>,
+) {
+    let typed: String = character_reader.read().map(|event| event.char).collect();
+
+    if !note_editor.editing {
+        if keyboard_input.just_pressed(KeyCode::N) {
+            note_editor.editing = true;
+            note_editor.buffer = level_notes
+                .0
+                .get(&level_hash(&level_state.layout))
+                .cloned()
+                .unwrap_or_default();
+        }
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        note_editor.editing = false;
+        let note = note_editor.buffer.trim().to_string();
+        let hash = level_hash(&level_state.layout);
+        if note.is_empty() {
+            level_notes.0.remove(&hash);
+        } else {
+            level_notes.0.insert(hash, note);
+        }
+        profile.save(NOTES_FILE, &*level_notes);
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        note_editor.buffer.pop();
+    }
+    for character in typed.chars().filter(|c| !c.is_control()) {
+        if note_editor.buffer.chars().count() < MAX_NOTE_LENGTH {
+            note_editor.buffer.push(character);
+        }
+    }
+}
+
+fn update_note_panel(
+    level_state: Res<LevelState>,
+    level_notes: Res<LevelNotes>,
+    note_editor: Res<NoteEditor>,
+    mut note_panel_query: Query<&mut Text, With<NotePanel>>,
+) {
+    let Ok(mut text) = note_panel_query.get_single_mut() else {
+        return;
+    };
+    text.sections[0].value = if note_editor.editing {
+        format!("Note: {}_  (Enter to save)", note_editor.buffer)
+    } else {
+        match level_notes.0.get(&level_hash(&level_state.layout)) {
+            Some(note) => format!("Note: {}", note),
+            None => "No note for this level".to_string(),
+        }
+    };
+}
+
+fn show_level_note(
+    mut commands: Commands,
+    level_notes: Res<LevelNotes>,
+    mut level_loaded_reader: EventReader<LevelLoadedEvent>,
+) {
+    let Some(level_loaded) = level_loaded_reader.read().last() else {
+        return;
+    };
+    let Some(note) = level_notes.0.get(&level_loaded.hash) else {
+        return;
+    };
+
+    commands.spawn((
+        LevelNoteText {
+            level: level_loaded.level,
+            timer: Timer::from_seconds(5.0, TimerMode::Once),
+        },
+        TextBundle::from_section(format!("Note: {}", note), note_text_style()).with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+fn hide_level_note(
+    mut commands: Commands,
+    mut level_unloaded_reader: EventReader<LevelUnloadedEvent>,
+    level_note_query: Query<(Entity, &LevelNoteText)>,
+) {
+    for level_unloaded in level_unloaded_reader.read() {
+        for (entity, level_note) in level_note_query.iter() {
+            if level_note.level == level_unloaded.0 {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+fn fade_level_note(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut level_note_query: Query<(Entity, &mut LevelNoteText, &mut Text)>,
+) {
+    for (entity, mut level_note, mut text) in &mut level_note_query {
+        level_note.timer.tick(time.delta());
+        if level_note.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let alpha = level_note.timer.remaining_secs().min(1.0);
+        text.sections[0].style.color.set_a(alpha);
+    }
+}
+
+impl Plugin for NotesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LevelNotes::default())
+            .insert_resource(NoteEditor::default())
+            .add_systems(Startup, load_notes)
+            .add_systems(OnEnter(GameState::Paused), show_note_panel)
+            .add_systems(OnExit(GameState::Paused), hide_note_panel)
+            .add_systems(
+                Update,
+                (
+                    edit_note.run_if(quit_prompt_closed),
+                    update_note_panel.after(edit_note),
+                )
+                    .run_if(in_state(GameState::Paused)),
+            )
+            .add_systems(
+                Update,
+                (
+                    hide_level_note,
+                    show_level_note.after(hide_level_note),
+                    fade_level_note,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
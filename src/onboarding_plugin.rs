@@ -0,0 +1,160 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    play_plugin::{HistoryEvent, LevelState, NextLevelEvent},
+    profile::Profile,
+    GameState,
+};
+
+const ONBOARDING_FILE: &str = "onboarding.ron";
+
+pub struct OnboardingPlugin;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum OnboardingStep {
+    Move,
+    Undo,
+    Restart,
+    LevelSelect,
+}
+
+impl OnboardingStep {
+    fn prompt(&self) -> &'static str {
+        match self {
+            OnboardingStep::Move => "Use the arrow keys to walk and push blocks onto the goals.",
+            OnboardingStep::Undo => "Made a mistake? Press U to undo your last move.",
+            OnboardingStep::Restart => "Stuck? Press R to restart the level.",
+            OnboardingStep::LevelSelect => "Press L to pick any level from the level select.",
+        }
+    }
+}
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+struct OnboardingProgress {
+    completed: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct Onboarding {
+    step: Option<OnboardingStep>,
+}
+
+#[derive(Component)]
+struct OnboardingPrompt;
+
+fn load_onboarding(mut commands: Commands, profile: Res<Profile>) {
+    let progress = profile.load::<OnboardingProgress>(ONBOARDING_FILE);
+    if !progress.completed {
+        commands.insert_resource(Onboarding {
+            step: Some(OnboardingStep::Move),
+        });
+    }
+}
+
+fn finish_onboarding(onboarding: &mut Onboarding, profile: &Profile) {
+    onboarding.step = None;
+    profile.save(ONBOARDING_FILE, &OnboardingProgress { completed: true });
+}
+
+fn advance_onboarding(
+    keyboard_input: Res<Input<KeyCode>>,
+    profile: Res<Profile>,
+    level_state: Res<LevelState>,
+    mut onboarding: ResMut<Onboarding>,
+    mut history_reader: EventReader<HistoryEvent>,
+    mut next_level_reader: EventReader<NextLevelEvent>,
+) {
+    let undone = history_reader
+        .read()
+        .any(|history_event| !matches!(history_event, HistoryEvent::Redo));
+    let restarted = next_level_reader
+        .read()
+        .any(|next_level| next_level.0 == level_state.current_level);
+    let Some(step) = onboarding.step else {
+        return;
+    };
+
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        finish_onboarding(&mut onboarding, &profile);
+        return;
+    }
+
+    onboarding.step = match step {
+        OnboardingStep::Move if !level_state.moves.is_empty() => Some(OnboardingStep::Undo),
+        OnboardingStep::Undo if undone => Some(OnboardingStep::Restart),
+        OnboardingStep::Restart if restarted => Some(OnboardingStep::LevelSelect),
+        _ => return,
+    };
+}
+
+fn complete_onboarding(profile: Res<Profile>, mut onboarding: ResMut<Onboarding>) {
+    if onboarding.step == Some(OnboardingStep::LevelSelect) {
+        finish_onboarding(&mut onboarding, &profile);
+    }
+}
+
+fn update_onboarding_prompt(
+    mut commands: Commands,
+    onboarding: Res<Onboarding>,
+    prompt_query: Query<Entity, With<OnboardingPrompt>>,
+) {
+    if !onboarding.is_changed() {
+        return;
+    }
+    for entity in prompt_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(step) = onboarding.step else {
+        return;
+    };
+
+    commands
+        .spawn((
+            OnboardingPrompt,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.0),
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                step.prompt(),
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::YELLOW,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                "Tab: skip tutorial",
+                TextStyle {
+                    font_size: 14.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+        });
+}
+
+impl Plugin for OnboardingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Onboarding::default())
+            .add_systems(Startup, load_onboarding)
+            .add_systems(OnEnter(GameState::LevelSelect), complete_onboarding)
+            .add_systems(
+                Update,
+                (
+                    advance_onboarding.run_if(in_state(GameState::Playing)),
+                    update_onboarding_prompt.after(advance_onboarding),
+                ),
+            );
+    }
+}
@@ -0,0 +1,761 @@
+use bevy::{app::AppExit, ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    accessibility_plugin::MenuOption,
+    edit_plugin::EditingState,
+    ghost_plugin::{ghost_text, GhostSetting},
+    notes_plugin::NoteEditor,
+    play_plugin::{
+        FixedTimestepSetting, LevelState, MoveHistory, TurnBeforeMovingSetting, UndoGranularity,
+        UndoLimit,
+    },
+    practice_plugin::PracticeSession,
+    profile::Profile,
+    speedrun_plugin::{speedrun_text, SpeedrunSetting},
+    stats_plugin::SessionStats,
+    theme_plugin::{available_skins, Skin},
+    tiles::Theme,
+    GameState,
+};
+
+const MANY_MOVES: usize = 20;
+const SESSION_SUMMARY_FILE: &str = "session_summary.ron";
+
+pub struct PausePlugin;
+
+#[derive(Resource, Default)]
+pub struct QuitPrompt {
+    pub open: bool,
+    // Confirmed, showing the session summary before closing.
+    pub showing_summary: bool,
+}
+
+#[derive(Resource, Serialize, Deserialize)]
+pub struct SessionSummarySetting {
+    pub enabled: bool,
+}
+
+impl Default for SessionSummarySetting {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+// Assist mode: holding Shift with an arrow shows where the move would leave things.
+#[derive(Resource, Default)]
+pub struct MovePreviewSetting {
+    pub enabled: bool,
+}
+
+// How strong screen shake and landing thuds are.
+#[derive(Resource, Default, Clone, Copy, PartialEq)]
+pub enum JuiceIntensity {
+    Off,
+    Subtle,
+    #[default]
+    Full,
+}
+
+impl JuiceIntensity {
+    pub fn scale(&self) -> f32 {
+        match self {
+            JuiceIntensity::Off => 0.0,
+            JuiceIntensity::Subtle => 0.5,
+            JuiceIntensity::Full => 1.0,
+        }
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            JuiceIntensity::Off => JuiceIntensity::Subtle,
+            JuiceIntensity::Subtle => JuiceIntensity::Full,
+            JuiceIntensity::Full => JuiceIntensity::Off,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            JuiceIntensity::Off => "off",
+            JuiceIntensity::Subtle => "subtle",
+            JuiceIntensity::Full => "full",
+        }
+    }
+}
+
+#[derive(SystemParam)]
+struct QuitContext<'w> {
+    game_state: Res<'w, State<GameState>>,
+    level_state: Res<'w, LevelState>,
+    move_history: Res<'w, MoveHistory>,
+    editing_state: Option<Res<'w, EditingState>>,
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component)]
+struct UndoGranularityText;
+
+#[derive(Component)]
+struct UndoLimitText;
+
+#[derive(Component)]
+struct SessionSummaryText;
+
+#[derive(Component)]
+struct MovePreviewText;
+
+#[derive(Component)]
+struct FixedTimestepText;
+
+#[derive(Component)]
+struct TurnBeforeMovingText;
+
+#[derive(Component)]
+struct SpeedrunSettingText;
+
+#[derive(Component)]
+struct GhostText;
+
+#[derive(Component)]
+struct JuiceText;
+
+#[derive(Component)]
+struct ThemeText;
+
+#[derive(Component)]
+struct SkinText;
+
+// The settings for how far back undo goes.
+#[derive(SystemParam)]
+struct UndoSettings<'w> {
+    undo_granularity: Res<'w, UndoGranularity>,
+    undo_limit: Res<'w, UndoLimit>,
+}
+
+// The settings for how moves play out.
+#[derive(SystemParam)]
+struct MoveSettings<'w> {
+    move_preview_setting: Res<'w, MovePreviewSetting>,
+    fixed_timestep_setting: Res<'w, FixedTimestepSetting>,
+    turn_setting: Res<'w, TurnBeforeMovingSetting>,
+}
+
+// The settings for how the game looks.
+#[derive(SystemParam)]
+struct LookSettings<'w> {
+    juice_intensity: Res<'w, JuiceIntensity>,
+    theme: Res<'w, Theme>,
+    skin: Res<'w, Skin>,
+    ghost_setting: Res<'w, GhostSetting>,
+}
+
+#[derive(Component)]
+struct QuitPromptPanel;
+
+pub fn quit_prompt_closed(quit_prompt: Res<QuitPrompt>) -> bool {
+    !quit_prompt.open
+}
+
+fn undo_granularity_text(undo_granularity: UndoGranularity) -> String {
+    format!("G: undo {}", undo_granularity.label())
+}
+
+fn undo_limit_text(undo_limit: UndoLimit) -> String {
+    format!("U: undo history ({})", undo_limit.label())
+}
+
+fn session_summary_text(setting: &SessionSummarySetting) -> String {
+    let state = if setting.enabled { "on" } else { "off" };
+    format!("Q: session summary on quit ({})", state)
+}
+
+fn move_preview_text(setting: &MovePreviewSetting) -> String {
+    let state = if setting.enabled { "on" } else { "off" };
+    format!("A: Shift + arrow previews moves ({})", state)
+}
+
+fn fixed_timestep_text(setting: &FixedTimestepSetting) -> String {
+    let state = if setting.enabled { "on" } else { "off" };
+    format!("F: fixed timestep movement ({})", state)
+}
+
+fn turn_before_moving_text(setting: &TurnBeforeMovingSetting) -> String {
+    let state = if setting.enabled { "on" } else { "off" };
+    format!("O: turn before moving ({})", state)
+}
+
+fn juice_text(juice_intensity: JuiceIntensity) -> String {
+    format!("J: screen shake and thuds ({})", juice_intensity.label())
+}
+
+fn theme_text(theme: Theme) -> String {
+    format!("C: colors ({})", theme.label())
+}
+
+fn skin_text(skin: &Skin) -> String {
+    format!(
+        "K: sprites ({})",
+        skin.name.as_deref().unwrap_or("built in")
+    )
+}
+
+fn show_pause_menu(
+    mut commands: Commands,
+    practice_session: Res<PracticeSession>,
+    undo_settings: UndoSettings,
+    session_summary_setting: Res<SessionSummarySetting>,
+    move_settings: MoveSettings,
+    speedrun_setting: Res<SpeedrunSetting>,
+    look_settings: LookSettings,
+) {
+    let practice_hint = if practice_session.is_active() {
+        "P: end practice"
+    } else {
+        "P: practice a region"
+    };
+
+    commands
+        .spawn((
+            PauseMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Paused",
+                TextStyle {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                format!(
+                    "Space: resume  N: edit level note  {}  Esc: quit\n\
+                    Up/Down and Enter: change a setting",
+                    practice_hint
+                ),
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                UndoGranularityText,
+                MenuOption(KeyCode::G),
+                TextBundle::from_section(
+                    undo_granularity_text(*undo_settings.undo_granularity),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn((
+                UndoLimitText,
+                MenuOption(KeyCode::U),
+                TextBundle::from_section(
+                    undo_limit_text(*undo_settings.undo_limit),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn((
+                SessionSummaryText,
+                MenuOption(KeyCode::Q),
+                TextBundle::from_section(
+                    session_summary_text(&session_summary_setting),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn((
+                MovePreviewText,
+                MenuOption(KeyCode::A),
+                TextBundle::from_section(
+                    move_preview_text(&move_settings.move_preview_setting),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn((
+                FixedTimestepText,
+                MenuOption(KeyCode::F),
+                TextBundle::from_section(
+                    fixed_timestep_text(&move_settings.fixed_timestep_setting),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn((
+                TurnBeforeMovingText,
+                MenuOption(KeyCode::O),
+                TextBundle::from_section(
+                    turn_before_moving_text(&move_settings.turn_setting),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn((
+                SpeedrunSettingText,
+                MenuOption(KeyCode::S),
+                TextBundle::from_section(
+                    speedrun_text(&speedrun_setting),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn((
+                GhostText,
+                MenuOption(KeyCode::H),
+                TextBundle::from_section(
+                    ghost_text(&look_settings.ghost_setting),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn((
+                JuiceText,
+                MenuOption(KeyCode::J),
+                TextBundle::from_section(
+                    juice_text(*look_settings.juice_intensity),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn((
+                ThemeText,
+                MenuOption(KeyCode::C),
+                TextBundle::from_section(
+                    theme_text(*look_settings.theme),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn((
+                SkinText,
+                MenuOption(KeyCode::K),
+                TextBundle::from_section(
+                    skin_text(&look_settings.skin),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+fn hide_pause_menu(mut commands: Commands, pause_menu_query: Query<Entity, With<PauseMenu>>) {
+    for entity in pause_menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn unpause_game(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    note_editor: Res<NoteEditor>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if note_editor.editing {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        keyboard_input.reset(KeyCode::Space);
+        game_state.set(GameState::Playing);
+    }
+}
+
+fn cycle_undo_granularity(
+    keyboard_input: Res<Input<KeyCode>>,
+    note_editor: Res<NoteEditor>,
+    mut undo_granularity: ResMut<UndoGranularity>,
+    mut granularity_text_query: Query<&mut Text, With<UndoGranularityText>>,
+) {
+    if note_editor.editing || !keyboard_input.just_pressed(KeyCode::G) {
+        return;
+    }
+    *undo_granularity = undo_granularity.next();
+    for mut text in &mut granularity_text_query {
+        text.sections[0].value = undo_granularity_text(*undo_granularity);
+    }
+}
+
+fn cycle_undo_limit(
+    keyboard_input: Res<Input<KeyCode>>,
+    note_editor: Res<NoteEditor>,
+    mut undo_limit: ResMut<UndoLimit>,
+    mut limit_text_query: Query<&mut Text, With<UndoLimitText>>,
+) {
+    if note_editor.editing || !keyboard_input.just_pressed(KeyCode::U) {
+        return;
+    }
+    *undo_limit = undo_limit.next();
+    for mut text in &mut limit_text_query {
+        text.sections[0].value = undo_limit_text(*undo_limit);
+    }
+}
+
+fn load_session_summary_setting(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(profile.load::<SessionSummarySetting>(SESSION_SUMMARY_FILE));
+}
+
+fn toggle_session_summary(
+    keyboard_input: Res<Input<KeyCode>>,
+    profile: Res<Profile>,
+    note_editor: Res<NoteEditor>,
+    mut session_summary_setting: ResMut<SessionSummarySetting>,
+    mut summary_text_query: Query<&mut Text, With<SessionSummaryText>>,
+) {
+    if note_editor.editing || !keyboard_input.just_pressed(KeyCode::Q) {
+        return;
+    }
+    session_summary_setting.enabled = !session_summary_setting.enabled;
+    profile.save(SESSION_SUMMARY_FILE, &*session_summary_setting);
+    for mut text in &mut summary_text_query {
+        text.sections[0].value = session_summary_text(&session_summary_setting);
+    }
+}
+
+fn toggle_move_preview(
+    keyboard_input: Res<Input<KeyCode>>,
+    note_editor: Res<NoteEditor>,
+    mut move_preview_setting: ResMut<MovePreviewSetting>,
+    mut preview_text_query: Query<&mut Text, With<MovePreviewText>>,
+) {
+    if note_editor.editing || !keyboard_input.just_pressed(KeyCode::A) {
+        return;
+    }
+    move_preview_setting.enabled = !move_preview_setting.enabled;
+    for mut text in &mut preview_text_query {
+        text.sections[0].value = move_preview_text(&move_preview_setting);
+    }
+}
+
+fn toggle_fixed_timestep(
+    keyboard_input: Res<Input<KeyCode>>,
+    note_editor: Res<NoteEditor>,
+    mut fixed_timestep_setting: ResMut<FixedTimestepSetting>,
+    mut fixed_timestep_text_query: Query<&mut Text, With<FixedTimestepText>>,
+) {
+    if note_editor.editing || !keyboard_input.just_pressed(KeyCode::F) {
+        return;
+    }
+    fixed_timestep_setting.enabled = !fixed_timestep_setting.enabled;
+    for mut text in &mut fixed_timestep_text_query {
+        text.sections[0].value = fixed_timestep_text(&fixed_timestep_setting);
+    }
+}
+
+fn toggle_turn_before_moving(
+    keyboard_input: Res<Input<KeyCode>>,
+    note_editor: Res<NoteEditor>,
+    mut turn_setting: ResMut<TurnBeforeMovingSetting>,
+    mut turn_text_query: Query<&mut Text, With<TurnBeforeMovingText>>,
+) {
+    if note_editor.editing || !keyboard_input.just_pressed(KeyCode::O) {
+        return;
+    }
+    turn_setting.enabled = !turn_setting.enabled;
+    for mut text in &mut turn_text_query {
+        text.sections[0].value = turn_before_moving_text(&turn_setting);
+    }
+}
+
+fn toggle_speedrun(
+    keyboard_input: Res<Input<KeyCode>>,
+    note_editor: Res<NoteEditor>,
+    mut speedrun_setting: ResMut<SpeedrunSetting>,
+    mut speedrun_text_query: Query<&mut Text, With<SpeedrunSettingText>>,
+) {
+    if note_editor.editing || !keyboard_input.just_pressed(KeyCode::S) {
+        return;
+    }
+    speedrun_setting.enabled = !speedrun_setting.enabled;
+    for mut text in &mut speedrun_text_query {
+        text.sections[0].value = speedrun_text(&speedrun_setting);
+    }
+}
+
+fn toggle_ghost(
+    keyboard_input: Res<Input<KeyCode>>,
+    note_editor: Res<NoteEditor>,
+    mut ghost_setting: ResMut<GhostSetting>,
+    mut ghost_text_query: Query<&mut Text, With<GhostText>>,
+) {
+    if note_editor.editing || !keyboard_input.just_pressed(KeyCode::H) {
+        return;
+    }
+    ghost_setting.enabled = !ghost_setting.enabled;
+    for mut text in &mut ghost_text_query {
+        text.sections[0].value = ghost_text(&ghost_setting);
+    }
+}
+
+fn cycle_juice(
+    keyboard_input: Res<Input<KeyCode>>,
+    note_editor: Res<NoteEditor>,
+    mut juice_intensity: ResMut<JuiceIntensity>,
+    mut juice_text_query: Query<&mut Text, With<JuiceText>>,
+) {
+    if note_editor.editing || !keyboard_input.just_pressed(KeyCode::J) {
+        return;
+    }
+    *juice_intensity = juice_intensity.next();
+    for mut text in &mut juice_text_query {
+        text.sections[0].value = juice_text(*juice_intensity);
+    }
+}
+
+fn cycle_theme(
+    keyboard_input: Res<Input<KeyCode>>,
+    note_editor: Res<NoteEditor>,
+    mut theme: ResMut<Theme>,
+    mut theme_text_query: Query<&mut Text, With<ThemeText>>,
+) {
+    if note_editor.editing || !keyboard_input.just_pressed(KeyCode::C) {
+        return;
+    }
+    *theme = theme.next();
+    for mut text in &mut theme_text_query {
+        text.sections[0].value = theme_text(*theme);
+    }
+}
+
+// Steps through the skins in assets/skins, then back to the built in sprites.
+fn cycle_skin(
+    keyboard_input: Res<Input<KeyCode>>,
+    note_editor: Res<NoteEditor>,
+    mut skin: ResMut<Skin>,
+    mut skin_text_query: Query<&mut Text, With<SkinText>>,
+) {
+    if note_editor.editing || !keyboard_input.just_pressed(KeyCode::K) {
+        return;
+    }
+    let skins = available_skins();
+    let next = match &skin.name {
+        None => skins.first().cloned(),
+        Some(name) => skins
+            .iter()
+            .position(|skin| skin == name)
+            .and_then(|index| skins.get(index + 1))
+            .cloned(),
+    };
+    *skin = Skin::named(next);
+    for mut text in &mut skin_text_query {
+        text.sections[0].value = skin_text(&skin);
+    }
+}
+
+impl QuitContext<'_> {
+    fn warnings(&self) -> Vec<String> {
+        let game_state = self.game_state.get();
+        let mut warnings = Vec::new();
+        if *game_state == GameState::Editing
+            && self
+                .editing_state
+                .as_ref()
+                .is_some_and(|e| e.has_unsaved_changes())
+        {
+            warnings.push("Your unsaved level will be lost.".to_string());
+        }
+        if *game_state == GameState::Paused && self.move_history.len() >= MANY_MOVES {
+            warnings.push(format!(
+                "You are {} moves into level {}.",
+                self.move_history.len(),
+                self.level_state.current_level
+            ));
+        }
+        warnings
+    }
+}
+
+fn handle_quit_input(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    note_editor: Res<NoteEditor>,
+    session_summary_setting: Res<SessionSummarySetting>,
+    mut quit_prompt: ResMut<QuitPrompt>,
+    mut app_exit_writer: EventWriter<AppExit>,
+) {
+    if note_editor.editing {
+        return;
+    }
+
+    if quit_prompt.showing_summary {
+        if keyboard_input.any_just_pressed([KeyCode::Return, KeyCode::Escape]) {
+            app_exit_writer.send(AppExit);
+        }
+    } else if quit_prompt.open && keyboard_input.just_pressed(KeyCode::Return) {
+        if session_summary_setting.enabled {
+            quit_prompt.showing_summary = true;
+        } else {
+            app_exit_writer.send(AppExit);
+        }
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        keyboard_input.reset(KeyCode::Escape);
+        quit_prompt.open = !quit_prompt.open;
+    }
+}
+
+fn update_quit_prompt(
+    mut commands: Commands,
+    quit_prompt: Res<QuitPrompt>,
+    quit_context: QuitContext,
+    session_stats: SessionStats,
+    quit_prompt_query: Query<Entity, With<QuitPromptPanel>>,
+) {
+    if !quit_prompt.is_changed() {
+        return;
+    }
+    for entity in quit_prompt_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !quit_prompt.open {
+        return;
+    }
+
+    let (title, lines, hint) = if quit_prompt.showing_summary {
+        (
+            "Thanks for playing!",
+            session_stats.summary(),
+            "Enter: quit",
+        )
+    } else {
+        (
+            "Quit the game?",
+            quit_context.warnings(),
+            "Enter: quit  Esc: cancel",
+        )
+    };
+    let line_color = if quit_prompt.showing_summary {
+        Color::WHITE
+    } else {
+        Color::ORANGE
+    };
+    commands
+        .spawn((
+            QuitPromptPanel,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                z_index: ZIndex::Global(20),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+            for line in lines {
+                parent.spawn(TextBundle::from_section(
+                    line,
+                    TextStyle {
+                        font_size: 16.0,
+                        color: line_color,
+                        ..default()
+                    },
+                ));
+            }
+            parent.spawn(TextBundle::from_section(
+                hint,
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+        });
+}
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(QuitPrompt::default())
+            .insert_resource(SessionSummarySetting::default())
+            .insert_resource(MovePreviewSetting::default())
+            .insert_resource(JuiceIntensity::default())
+            .add_systems(Startup, load_session_summary_setting)
+            .add_systems(OnEnter(GameState::Paused), show_pause_menu)
+            .add_systems(OnExit(GameState::Paused), hide_pause_menu)
+            .add_systems(
+                Update,
+                (
+                    unpause_game,
+                    cycle_undo_granularity,
+                    cycle_undo_limit,
+                    toggle_session_summary,
+                    toggle_move_preview,
+                    toggle_fixed_timestep,
+                    toggle_turn_before_moving,
+                    toggle_speedrun,
+                    toggle_ghost,
+                    cycle_juice,
+                    cycle_theme,
+                    cycle_skin,
+                )
+                    .run_if(in_state(GameState::Paused))
+                    .run_if(quit_prompt_closed),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_quit_input.run_if(not(in_state(GameState::Playing))),
+                    update_quit_prompt.after(handle_quit_input),
+                ),
+            );
+    }
+}
@@ -1,137 +1,1564 @@
 use crate::{
-    level_four, level_one, level_setup, level_three, level_two, GameState, Obstacle, Position,
+    campaign_plugin::Campaign,
+    demo_plugin::Demo,
+    level_complete_plugin::complete_level,
+    level_data::LevelMetadata,
+    level_hash, level_setup,
+    pause_plugin::MovePreviewSetting,
+    preview_plugin::is_previewing,
+    sokoban_core::Tile,
+    tiles::{facing_marker_translation, FacingMarker, Theme},
+    transition_plugin::transition_idle,
+    GameState, LevelEntity, Obstacle, Position, TILE_SIZE,
 };
-use bevy::{prelude::*, utils::HashMap};
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use std::{collections::VecDeque, time::Duration};
+
+pub struct PlayPlugin;
+
+pub const STARTING_ENERGY: i32 = 20;
+
+// Where an enemy is and the way it's patrolling.
+#[derive(Clone, Copy)]
+pub struct Patrol {
+    pub position: Position,
+    pub step: (i32, i32),
+}
+
+// The enemies and whether anyone was caught before a move, so undo can walk them back.
+#[derive(Clone)]
+pub struct EnemyTurn {
+    pub enemies: HashMap<Entity, Patrol>,
+    pub caught: bool,
+    pub move_index: usize,
+}
+
+#[derive(Resource, Clone)]
+pub struct LevelState {
+    pub current_level: i32,
+    pub layout: Vec<Vec<i32>>,
+    pub metadata: LevelMetadata,
+    // Walls never move, so they're kept apart from the blocks and pits moves change.
+    pub walls: HashSet<Position>,
+    pub obstacles: HashMap<Position, (Entity, Obstacle)>,
+    // Each goal and the color of block it wants.
+    pub goals: HashMap<Position, (Entity, u8)>,
+    pub switches: HashSet<Position>,
+    pub doors: HashMap<Position, Entity>,
+    // Each teleporter mapped to its partner.
+    pub teleporters: HashMap<Position, Position>,
+    // Each portal mapped to its twin. Unlike teleporters nothing stops on a portal, it
+    // comes out the far side of the twin still going the same way.
+    pub portals: HashMap<Position, Position>,
+    pub ice: HashSet<Position>,
+    // One-way tiles and the direction they can be crossed in.
+    pub one_way: HashMap<Position, (i32, i32)>,
+    // Energy pickups still on the board and how much each is worth.
+    pub pickups: HashMap<Position, (Entity, i32)>,
+    // Picked up pickups and the move they were picked up on, so undo can put them back.
+    pub collected: Vec<(Position, (Entity, i32), usize)>,
+    // Pits filled in so far: the pit, the block that filled it and the move it happened on.
+    pub filled_pits: Vec<(Position, Entity, (Entity, Obstacle), usize)>,
+    // Only levels with pickups have an energy budget.
+    pub energy: Option<i32>,
+    // Every player avatar, input goes to `active_player`.
+    pub players: HashMap<Entity, Position>,
+    pub active_player: Entity,
+    // The mirrored second character, on levels that have one.
+    pub mirror_position: Option<Position>,
+    // Levels with an exit are only won once the player is standing on it too.
+    pub exit: Option<Position>,
+    pub enemies: HashMap<Entity, Patrol>,
+    // One for each move that had enemies about.
+    pub enemy_turns: Vec<EnemyTurn>,
+    // Set once an enemy touches a player, nothing moves again until that's undone.
+    pub caught: bool,
+    // Keys still on the board and locked doors still shut, with their colors.
+    pub keys: HashMap<Position, (Entity, u8)>,
+    pub locked_doors: HashMap<Position, (Entity, u8)>,
+    // The colors of the keys being carried.
+    pub inventory: Vec<u8>,
+    // Keys taken and doors unlocked, with the move it happened on so undo can put them back.
+    pub taken_keys: Vec<(Position, (Entity, u8), usize)>,
+    pub unlocked_doors: Vec<(Position, (Entity, u8), usize)>,
+    // Fragile floor still standing. Once the player steps off it, it becomes an open pit
+    // with the same entity, and goes on the list of broken tiles with the move it broke on.
+    pub fragile: HashMap<Position, Entity>,
+    pub broken: Vec<(Position, Entity, usize)>,
+    // Heavy blocks pushed once without giving, the next push moves them.
+    pub strained: HashSet<Entity>,
+    // Strained heavy blocks that have since given and the move they gave on.
+    pub given_way: Vec<(Entity, usize)>,
+    pub moves: String,
+}
+
+// Remove default implementation and use resource_exists run condition
+impl Default for LevelState {
+    fn default() -> Self {
+        Self {
+            current_level: Default::default(),
+            layout: Default::default(),
+            metadata: Default::default(),
+            walls: Default::default(),
+            obstacles: Default::default(),
+            goals: Default::default(),
+            switches: Default::default(),
+            doors: Default::default(),
+            teleporters: Default::default(),
+            portals: Default::default(),
+            ice: Default::default(),
+            one_way: Default::default(),
+            pickups: Default::default(),
+            collected: Default::default(),
+            filled_pits: Default::default(),
+            energy: None,
+            players: Default::default(),
+            active_player: Entity::PLACEHOLDER,
+            mirror_position: None,
+            exit: None,
+            enemies: Default::default(),
+            enemy_turns: Default::default(),
+            caught: false,
+            keys: Default::default(),
+            locked_doors: Default::default(),
+            inventory: Vec::new(),
+            taken_keys: Vec::new(),
+            unlocked_doors: Vec::new(),
+            fragile: Default::default(),
+            broken: Vec::new(),
+            strained: Default::default(),
+            given_way: Vec::new(),
+            moves: String::new(),
+        }
+    }
+}
+
+impl LevelState {
+    // Goals with a block of their color on them.
+    pub fn covered_goals(&self) -> usize {
+        self.goals
+            .iter()
+            .filter(|&(goal_position, (_, goal_color))| {
+                matches!(
+                    self.obstacles.get(goal_position),
+                    Some((_, Obstacle::Block(color))) if color == goal_color
+                )
+            })
+            .count()
+    }
+
+    pub fn is_solved(&self) -> bool {
+        !self.caught
+            && self.covered_goals() == self.goals.len()
+            && self
+                .exit
+                .is_none_or(|exit| self.players.values().any(|player| *player == exit))
+    }
+
+    pub fn player_position(&self) -> Position {
+        self.players
+            .get(&self.active_player)
+            .copied()
+            .unwrap_or(Position { x: 0, y: 0 })
+    }
+
+    // Whether one of the players not taking this turn stands on `position`.
+    pub fn is_idle_player(&self, position: &Position) -> bool {
+        self.players
+            .iter()
+            .any(|(entity, player)| *entity != self.active_player && player == position)
+    }
+
+    fn is_occupied(&self, position: &Position) -> bool {
+        self.walls.contains(position)
+            || self.obstacles.contains_key(position)
+            || self.players.values().any(|player| player == position)
+            || self.mirror_position == Some(*position)
+            || self.is_enemy(position)
+    }
+
+    pub fn is_enemy(&self, position: &Position) -> bool {
+        self.enemies
+            .values()
+            .any(|patrol| patrol.position == *position)
+    }
+
+    // Doors stay open while anything stands on a switch.
+    pub fn doors_open(&self) -> bool {
+        self.switches.iter().any(|switch| self.is_occupied(switch))
+    }
+
+    // A door can't close on whatever is standing in it.
+    pub fn is_door_closed(&self, position: &Position) -> bool {
+        self.doors.contains_key(position) && !self.doors_open() && !self.is_occupied(position)
+    }
+
+    // Players can still walk into an enemy, and get caught for it.
+    pub fn is_blocked(&self, position: &Position) -> bool {
+        self.walls.contains(position)
+            || self.obstacles.contains_key(position)
+            || self.is_door_closed(position)
+            || self.is_enemy(position)
+            || self.locked_doors.contains_key(position)
+            || self.portals.contains_key(position)
+    }
+
+    // Whether the player has a key for the locked door at `position`, if there is one.
+    pub fn can_unlock(&self, position: &Position) -> bool {
+        self.locked_doors
+            .get(position)
+            .is_some_and(|(_, color)| self.inventory.contains(color))
+    }
+
+    // Where something landing on `entry` ends up, if the partner teleporter is free.
+    // `also_occupied` is where the other half of the move will be standing.
+    pub fn teleport_exit(&self, entry: Position, also_occupied: Position) -> Option<Position> {
+        let exit = *self.teleporters.get(&entry)?;
+        (!self.walls.contains(&exit)
+            && !self.obstacles.contains_key(&exit)
+            && exit != also_occupied
+            && self.mirror_position != Some(exit)
+            && !self.is_idle_player(&exit))
+        .then_some(exit)
+    }
+
+    // The tile a step from `from` lands on, and where it comes out if that's a portal.
+    pub fn portal_step(
+        &self,
+        from: Position,
+        (step_x, step_y): (i32, i32),
+    ) -> (Position, Option<Position>) {
+        let to = from.add(step_x, step_y);
+        let exit = self.portals.get(&to).map(|twin| twin.add(step_x, step_y));
+        (to, exit)
+    }
+
+    // One-way tiles can only be entered and left along their arrow.
+    pub fn allows_move(&self, from: Position, step: (i32, i32)) -> bool {
+        let to = from.add(step.0, step.1);
+        [from, to].iter().all(|position| {
+            self.one_way
+                .get(position)
+                .is_none_or(|arrow| *arrow == step)
+        })
+    }
+
+    pub fn is_open_pit(&self, position: &Position) -> bool {
+        matches!(self.obstacles.get(position), Some((_, Obstacle::Pit)))
+    }
+
+    pub fn is_out_of_energy(&self) -> bool {
+        self.energy.is_some_and(|energy| energy <= 0)
+    }
+
+    fn push_block(&mut self, from: Position, to: Position) {
+        if let Some(obstacle) = self.obstacles.remove(&from) {
+            if self.strained.remove(&obstacle.0) {
+                self.given_way.push((obstacle.0, self.moves.len()));
+            }
+            // A block pushed into a pit fills it in, leaving floor behind.
+            match self.obstacles.remove(&to) {
+                Some((pit_entity, Obstacle::Pit)) => {
+                    self.filled_pits
+                        .push((to, pit_entity, obstacle, self.moves.len()));
+                }
+                _ => {
+                    self.obstacles.insert(to, obstacle);
+                }
+            }
+        }
+    }
+
+    // Expects `moves` to already have the move popped.
+    fn unpush_block(&mut self, from: Position, to: Position) {
+        let filled = self.filled_pits.pop_if(|(pit_position, _, _, move_index)| {
+            *pit_position == to && *move_index == self.moves.len()
+        });
+        if let Some((pit_position, pit_entity, block, _)) = filled {
+            self.obstacles
+                .insert(pit_position, (pit_entity, Obstacle::Pit));
+            self.obstacles.insert(from, block);
+        } else if let Some(obstacle) = self.obstacles.remove(&to) {
+            self.obstacles.insert(from, obstacle);
+        }
+        // A heavy block that gave on this move is left strained again.
+        let Some(&(block_entity, _)) = self.obstacles.get(&from) else {
+            return;
+        };
+        let gave = self.given_way.pop_if(|(entity, move_index)| {
+            *entity == block_entity && *move_index == self.moves.len()
+        });
+        if gave.is_some() {
+            self.strained.insert(block_entity);
+        }
+    }
+
+    // Once the player has moved, each enemy steps along its patrol, turning back when the
+    // way on is blocked. Enemies are taken in reading order so they always move the same.
+    fn step_enemies(&mut self) {
+        let mut order: Vec<_> = self.enemies.keys().copied().collect();
+        order.sort_by_key(|entity| {
+            let position = self.enemies[entity].position;
+            (position.y, position.x)
+        });
+        for entity in order {
+            let Patrol {
+                position,
+                step: (step_x, step_y),
+            } = self.enemies[&entity];
+            let next = [(step_x, step_y), (-step_x, -step_y)]
+                .into_iter()
+                .map(|step| Patrol {
+                    position: position.add(step.0, step.1),
+                    step,
+                })
+                .find(|next| {
+                    !self.is_blocked(&next.position) && self.mirror_position != Some(next.position)
+                });
+            if let Some(next) = next {
+                self.enemies.insert(entity, next);
+            }
+        }
+        let caught = self.players.values().any(|player| self.is_enemy(player));
+        self.caught |= caught;
+    }
+
+    pub fn apply(&mut self, record: &MoveRecord) {
+        self.active_player = record.player;
+        self.players.insert(record.player, record.player_to);
+        if let Some((_, from, to)) = record.pushed {
+            self.push_block(from, to);
+        }
+        // Straining against a heavy block isn't stepping off.
+        let stepped_off = record.player_to != record.player_from;
+        if let Some(entity) = stepped_off
+            .then(|| self.fragile.remove(&record.player_from))
+            .flatten()
+        {
+            self.obstacles
+                .insert(record.player_from, (entity, Obstacle::Pit));
+            self.broken
+                .push((record.player_from, entity, self.moves.len()));
+        }
+        if let Some(mirror) = record.mirror {
+            self.mirror_position = Some(mirror.to);
+            if let Some((_, from, to)) = mirror.pushed {
+                self.push_block(from, to);
+            }
+        }
+        if let Some(energy) = &mut self.energy {
+            *energy -= record.energy_cost();
+            if let Some(pickup) = self.pickups.remove(&record.player_to) {
+                *energy += pickup.1;
+                self.collected
+                    .push((record.player_to, pickup, self.moves.len()));
+            }
+        }
+        // Walking into a locked door uses up a key to open it.
+        if let Some(door) = self.locked_doors.remove(&record.player_to) {
+            if let Some(index) = self.inventory.iter().position(|color| *color == door.1) {
+                self.inventory.remove(index);
+            }
+            self.unlocked_doors
+                .push((record.player_to, door, self.moves.len()));
+        }
+        if let Some(key) = self.keys.remove(&record.player_to) {
+            self.inventory.push(key.1);
+            self.taken_keys
+                .push((record.player_to, key, self.moves.len()));
+        }
+        // Nobody went anywhere on a strain, so the enemies wait too.
+        if !self.enemies.is_empty() && !record.is_strain() {
+            self.enemy_turns.push(EnemyTurn {
+                enemies: self.enemies.clone(),
+                caught: self.caught,
+                move_index: self.moves.len(),
+            });
+            // Walking into an enemy is as bad as it walking into you.
+            self.caught |= self.is_enemy(&record.player_to);
+            self.step_enemies();
+        }
+        if let Some(block_entity) = record.strained {
+            self.strained.insert(block_entity);
+        }
+        self.moves.push(record.lurd());
+    }
+
+    // Control goes back to whoever made the move.
+    pub fn revert(&mut self, record: &MoveRecord) {
+        self.active_player = record.player;
+        self.players.insert(record.player, record.player_from);
+        self.moves.pop();
+        if let Some(block_entity) = record.strained {
+            self.strained.remove(&block_entity);
+        }
+        if let Some(mirror) = record.mirror {
+            self.mirror_position = Some(mirror.from);
+            if let Some((_, from, to)) = mirror.pushed {
+                self.unpush_block(from, to);
+            }
+        }
+        if let Some((_, from, to)) = record.pushed {
+            self.unpush_block(from, to);
+        }
+        if let Some(energy) = &mut self.energy {
+            *energy += record.energy_cost();
+            if let Some((position, pickup, _)) = self
+                .collected
+                .pop_if(|(_, _, move_index)| *move_index == self.moves.len())
+            {
+                *energy -= pickup.1;
+                self.pickups.insert(position, pickup);
+            }
+        }
+        if let Some(enemy_turn) = self
+            .enemy_turns
+            .pop_if(|enemy_turn| enemy_turn.move_index == self.moves.len())
+        {
+            self.enemies = enemy_turn.enemies;
+            self.caught = enemy_turn.caught;
+        }
+        if let Some((position, key, _)) = self
+            .taken_keys
+            .pop_if(|(_, _, move_index)| *move_index == self.moves.len())
+        {
+            if let Some(index) = self.inventory.iter().rposition(|color| *color == key.1) {
+                self.inventory.remove(index);
+            }
+            self.keys.insert(position, key);
+        }
+        if let Some((position, door, _)) = self
+            .unlocked_doors
+            .pop_if(|(_, _, move_index)| *move_index == self.moves.len())
+        {
+            self.inventory.push(door.1);
+            self.locked_doors.insert(position, door);
+        }
+        // Anything that fell in since has been undone already.
+        if let Some((position, entity, _)) = self
+            .broken
+            .pop_if(|(_, _, move_index)| *move_index == self.moves.len())
+        {
+            self.obstacles.remove(&position);
+            self.fragile.insert(position, entity);
+        }
+    }
+}
+
+// The levels being played through, numbered from 1. Finishing the last one
+// returns to `return_state`.
+#[derive(Resource, Clone)]
+pub struct LevelPack {
+    pub name: String,
+    pub levels: Vec<Vec<Vec<i32>>>,
+    // Alongside `levels`, levels past the end of it have none.
+    pub metadata: Vec<LevelMetadata>,
+    pub return_state: GameState,
+}
+
+pub const CAMPAIGN_PACK: &str = "Campaign";
+
+impl LevelPack {
+    pub fn campaign(campaign: &Campaign) -> Self {
+        Self {
+            name: CAMPAIGN_PACK.to_string(),
+            levels: campaign
+                .levels()
+                .map(|level| level.layout.clone())
+                .collect(),
+            metadata: campaign
+                .levels()
+                .map(|level| level.metadata.clone())
+                .collect(),
+            return_state: GameState::LevelSelect,
+        }
+    }
+
+    pub fn metadata(&self, level: i32) -> LevelMetadata {
+        usize::try_from(level - 1)
+            .ok()
+            .and_then(|index| self.metadata.get(index).cloned())
+            .unwrap_or_default()
+    }
+
+    pub fn level(&self, level: i32) -> Option<Vec<Vec<i32>>> {
+        let index = usize::try_from(level - 1).ok()?;
+        self.levels.get(index).cloned()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct MirrorMove {
+    pub from: Position,
+    pub to: Position,
+    pub pushed: Option<(Entity, Position, Position)>,
+}
+
+#[derive(Clone, Copy)]
+pub struct MoveRecord {
+    pub player: Entity,
+    pub direction: (i32, i32),
+    pub player_from: Position,
+    pub player_to: Position,
+    pub pushed: Option<(Entity, Position, Position)>,
+    // Set when the mirror managed to take its step this turn.
+    pub mirror: Option<MirrorMove>,
+    // A heavy block the player or mirror pushed that didn't give yet.
+    pub strained: Option<Entity>,
+}
+
+impl MoveRecord {
+    // The player's own push against a heavy block that didn't give, the player stays put.
+    pub fn is_strain(&self) -> bool {
+        self.strained.is_some() && self.player_to == self.player_from
+    }
+
+    pub fn energy_cost(&self) -> i32 {
+        if self.is_strain() {
+            0
+        } else if self.pushed.is_some() {
+            2
+        } else {
+            1
+        }
+    }
+
+    // Moves are recorded in LURD notation, uppercase when a block was pushed. A strain is
+    // written as the push it was, replaying it strains the block again rather than walking.
+    pub fn lurd(&self) -> char {
+        let direction = match self.direction {
+            (0, -1) => 'u',
+            (0, 1) => 'd',
+            (-1, 0) => 'l',
+            _ => 'r',
+        };
+        if self.pushed.is_some() || self.is_strain() {
+            direction.to_ascii_uppercase()
+        } else {
+            direction
+        }
+    }
+}
+
+// Every record reverts exactly, so predicted moves in online co-op could be rolled back
+// through here and the authoritative order replayed on top once a network transport exists.
+#[derive(Resource, Default, Clone)]
+pub struct MoveHistory {
+    pub undo: Vec<MoveRecord>,
+    pub redo: Vec<MoveRecord>,
+    // Moves made too long ago to undo under the `UndoLimit`, still counted as made.
+    forgotten: usize,
+}
+
+impl MoveHistory {
+    pub fn len(&self) -> usize {
+        self.forgotten + self.undo.len()
+    }
+
+    pub fn push(&mut self, record: MoveRecord, undo_limit: UndoLimit) {
+        self.redo.clear();
+        self.remember(record, undo_limit);
+    }
+
+    // Takes back the last undone move, kept to the same limit as new moves.
+    pub fn redo(&mut self, undo_limit: UndoLimit) -> Option<MoveRecord> {
+        let record = self.redo.pop()?;
+        self.remember(record, undo_limit);
+        Some(record)
+    }
+
+    fn remember(&mut self, record: MoveRecord, undo_limit: UndoLimit) {
+        self.undo.push(record);
+        if let UndoLimit::LastMoves(limit) = undo_limit {
+            let excess = self.undo.len().saturating_sub(limit);
+            self.undo.drain(..excess);
+            self.forgotten += excess;
+        }
+    }
+}
+
+// How many moves back undo can go, so marathon sessions on huge levels don't keep every
+// move they've ever made.
+#[derive(Resource, Default, Clone, Copy, PartialEq)]
+pub enum UndoLimit {
+    #[default]
+    Unlimited,
+    LastMoves(usize),
+}
+
+impl UndoLimit {
+    const CHOICES: [UndoLimit; 3] = [
+        UndoLimit::Unlimited,
+        UndoLimit::LastMoves(1000),
+        UndoLimit::LastMoves(100),
+    ];
+
+    pub fn label(&self) -> String {
+        match self {
+            UndoLimit::Unlimited => "unlimited".to_string(),
+            UndoLimit::LastMoves(moves) => format!("last {} moves", moves),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::CHOICES
+            .iter()
+            .position(|choice| *choice == self)
+            .map_or(0, |index| index + 1);
+        Self::CHOICES[index % Self::CHOICES.len()]
+    }
+}
+
+// How far a single undo steps back.
+#[derive(Resource, Default, Clone, Copy, PartialEq)]
+pub enum UndoGranularity {
+    #[default]
+    Move,
+    Push,
+    Block,
+}
+
+impl UndoGranularity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            UndoGranularity::Move => "per move",
+            UndoGranularity::Push => "per push",
+            UndoGranularity::Block => "per block",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            UndoGranularity::Move => UndoGranularity::Push,
+            UndoGranularity::Push => UndoGranularity::Block,
+            UndoGranularity::Block => UndoGranularity::Move,
+        }
+    }
+}
+
+#[derive(Event)]
+pub enum HistoryEvent {
+    Undo,
+    UndoToLastPush,
+    Redo,
+}
+
+#[derive(Resource)]
+pub struct MovementTuning {
+    pub walk_seconds: f32,
+    pub push_seconds: f32,
+    // How far a pushed block stretches along the push, as a fraction of its size.
+    pub push_squash: f32,
+    pub repeat_delay_seconds: f32,
+    pub repeat_tiles_per_second: f32,
+    // Each tile slid across ice.
+    pub slide_seconds: f32,
+    // Each move played backwards while rewind is held.
+    pub rewind_seconds: f32,
+    // Plays moves as fast as the frames allow, for catching up on saved moves.
+    pub fast_forward: bool,
+}
+
+impl Default for MovementTuning {
+    fn default() -> Self {
+        Self {
+            walk_seconds: 0.3,
+            push_seconds: 0.4,
+            push_squash: 0.15,
+            repeat_delay_seconds: 0.25,
+            repeat_tiles_per_second: 3.0,
+            slide_seconds: 0.12,
+            rewind_seconds: 0.12,
+            fast_forward: false,
+        }
+    }
+}
+
+// A press in a new direction only turns the player, the next one moves it.
+#[derive(Resource, Default)]
+pub struct TurnBeforeMovingSetting {
+    pub enabled: bool,
+}
+
+// Steps moves on a fixed clock instead of once a frame, so replays and simulations play
+// out the same on any machine.
+#[derive(Resource, Default)]
+pub struct FixedTimestepSetting {
+    pub enabled: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct InputBuffer {
+    moves: VecDeque<(i32, i32)>,
+    // Set while the queue holds a clicked walk, any other input cancels it.
+    walking: bool,
+}
+
+const INPUT_BUFFER_SIZE: usize = 2;
+
+impl InputBuffer {
+    pub fn queue(&mut self, movement: (i32, i32)) {
+        if self.walking {
+            self.moves.clear();
+            self.walking = false;
+        }
+        if self.moves.len() < INPUT_BUFFER_SIZE {
+            self.moves.push_back(movement);
+        }
+    }
+
+    // Replaces whatever is queued with a whole walk, which isn't held to the buffer size.
+    pub fn walk(&mut self, moves: impl IntoIterator<Item = (i32, i32)>) {
+        self.moves = moves.into_iter().collect();
+        self.walking = !self.moves.is_empty();
+    }
+
+    pub fn is_walking(&self) -> bool {
+        self.walking
+    }
+}
+
+#[derive(Resource, Default)]
+struct HeldMove {
+    key: Option<KeyCode>,
+    timer: Timer,
+}
+
+#[derive(Resource)]
+struct UndoRepeat {
+    timer: Timer,
+}
+
+impl Default for UndoRepeat {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(UNDO_REPEAT_DELAY, TimerMode::Once),
+        }
+    }
+}
+
+const UNDO_REPEAT_DELAY: f32 = 0.4;
+const UNDO_REPEAT_MIN_DELAY: f32 = 0.05;
+const UNDO_REPEAT_ACCELERATION: f32 = 0.75;
+
+#[derive(Event)]
+pub struct NextLevelEvent(pub i32);
+
+// Sent once the screen is covered, see `TransitionState`.
+#[derive(Event)]
+pub struct LoadLevelEvent(pub i32);
+
+#[derive(Event)]
+pub struct LevelLoadedEvent {
+    pub level: i32,
+    pub hash: u32,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Event)]
+pub struct LevelUnloadedEvent(pub i32);
+
+#[derive(Event)]
+struct MoveFinishedEvent(MoveRecord);
+
+// A step the active player wants to take, from the keyboard or anywhere else.
+#[derive(Event)]
+pub struct MoveRequested {
+    pub direction: (i32, i32),
+}
+
+// What the rules made of a `MoveRequested`. A heavy block that doesn't give yet is
+// both `pushed` and `blocked`.
+#[derive(Event, Clone, Copy)]
+pub struct MoveResolved {
+    pub direction: (i32, i32),
+    pub pushed: Option<Entity>,
+    pub blocked: bool,
+}
+
+// Sent by anything that rewrites `LevelState`, naming itself so broken invariants
+// can be traced back to it.
+#[derive(Event)]
+pub struct BoardChangedEvent {
+    pub source: &'static str,
+}
+
+#[derive(Event)]
+pub struct LevelCompletedEvent {
+    pub level: i32,
+    pub moves: String,
+}
+
+#[derive(Event)]
+pub struct PackCompletedEvent {
+    pub name: String,
+}
+
+#[derive(SystemParam)]
+struct LevelEventWriters<'w> {
+    unloaded: EventWriter<'w, LevelUnloadedEvent>,
+    loaded: EventWriter<'w, LevelLoadedEvent>,
+}
+
+// Reports the level as completed once it's solved, unless the demo has been watched on it.
+#[derive(SystemParam)]
+struct CompletionWriter<'w> {
+    demo: Res<'w, Demo>,
+    level_completed: EventWriter<'w, LevelCompletedEvent>,
+}
+
+impl CompletionWriter<'_> {
+    fn send_if_solved(&mut self, level_state: &LevelState) {
+        if level_state.is_solved() && !self.demo.is_watched() {
+            self.level_completed.send(LevelCompletedEvent {
+                level: level_state.current_level,
+                moves: level_state.moves.clone(),
+            });
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Player {
+    pub is_moving: bool,
+    pub move_timer: Timer,
+}
+
+// The way a player last stepped or turned.
+#[derive(Component)]
+pub struct Facing(pub (i32, i32));
+
+// Steps the opposite way to the player every turn.
+#[derive(Component)]
+pub struct Mirror;
+
+// Every block, heavy ones included, for systems that want them without going through
+// `LevelState`. Blocks that fill a pit keep it.
+#[derive(Component)]
+pub struct Block;
+
+#[derive(Component)]
+pub struct Goal;
+
+// Heavy blocks only give on every second push, the first just strains against them.
+// Which are strained is kept in `LevelState` so undo and replays see it.
+#[derive(Component)]
+pub struct Heavy;
+
+// Rattles a block in place when a push didn't move it.
+#[derive(Component)]
+pub struct Shake {
+    origin: Vec3,
+    timer: Timer,
+}
+
+const SHAKE_SECONDS: f32 = 0.25;
+
+impl Shake {
+    fn new(position: Position) -> Self {
+        Self {
+            origin: position.to_translation(),
+            timer: Timer::from_seconds(SHAKE_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+// Whether a push on `block_entity` moves it, shaking heavy blocks that don't give yet.
+fn push_gives(
+    commands: &mut Commands,
+    level_state: &LevelState,
+    heavy_query: &Query<(), With<Heavy>>,
+    block_entity: Entity,
+    block_position: Position,
+) -> bool {
+    if !heavy_query.contains(block_entity) || level_state.strained.contains(&block_entity) {
+        return true;
+    }
+    commands
+        .entity(block_entity)
+        .insert(Shake::new(block_position));
+    false
+}
+
+#[derive(Component)]
+pub struct Moving {
+    // Where the move began, `from` moves on with each tile slid across ice.
+    start: Position,
+    from: Position,
+    to: Position,
+    step: (i32, i32),
+    teleport_to: Option<Position>,
+    sliding: bool,
+    // On the player, a heavy block the mirror strained against this turn.
+    strained: Option<Entity>,
+    // Set when undo, redo or rewind is easing things through the history, nothing is
+    // recorded when it lands.
+    replaying: bool,
+}
+
+impl Moving {
+    fn new(from: Position, step: (i32, i32), teleport_to: Option<Position>) -> Self {
+        Self {
+            start: from,
+            from,
+            to: from.add(step.0, step.1),
+            step,
+            teleport_to,
+            sliding: false,
+            strained: None,
+            replaying: false,
+        }
+    }
+
+    // Straight from one tile to another, however the moves in between got there.
+    fn between(from: Position, to: Position) -> Self {
+        Self {
+            start: from,
+            from,
+            to,
+            step: (to.x - from.x, to.y - from.y),
+            teleport_to: None,
+            sliding: false,
+            strained: None,
+            replaying: true,
+        }
+    }
+}
+
+fn handle_undo_input(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut history_writer: EventWriter<HistoryEvent>,
+    mut undo_repeat: ResMut<UndoRepeat>,
+    player_query: Query<&Player>,
+) {
+    if player_query.iter().any(|player| player.is_moving) {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::U) {
+        if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            history_writer.send(HistoryEvent::UndoToLastPush);
+        } else {
+            history_writer.send(HistoryEvent::Undo);
+        }
+        undo_repeat.timer = Timer::from_seconds(UNDO_REPEAT_DELAY, TimerMode::Once);
+        return;
+    }
+
+    // Holding undo repeats it, getting faster the longer the key is held.
+    if keyboard_input.pressed(KeyCode::U) {
+        undo_repeat.timer.tick(time.delta());
+        if undo_repeat.timer.finished() {
+            history_writer.send(HistoryEvent::Undo);
+            let next_delay = (undo_repeat.timer.duration().as_secs_f32()
+                * UNDO_REPEAT_ACCELERATION)
+                .max(UNDO_REPEAT_MIN_DELAY);
+            undo_repeat.timer = Timer::from_seconds(next_delay, TimerMode::Once);
+        }
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Y) {
+        history_writer.send(HistoryEvent::Redo);
+    }
+}
+
+pub const MOVE_KEYS: [(KeyCode, (i32, i32)); 4] = [
+    (KeyCode::Up, (0, -1)),
+    (KeyCode::Down, (0, 1)),
+    (KeyCode::Left, (-1, 0)),
+    (KeyCode::Right, (1, 0)),
+];
+
+// Presses made while a move is animating are queued and played once it finishes.
+// Holding a key repeats it at the tuned rate, only the most recently pressed key
+// repeats so holding two arrows doesn't zig-zag.
+fn buffer_input(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    tuning: Res<MovementTuning>,
+    mut held_move: ResMut<HeldMove>,
+    mut input_buffer: ResMut<InputBuffer>,
+    move_preview_setting: Res<MovePreviewSetting>,
+) {
+    // Arrows only preview while the assist key is held.
+    if is_previewing(&keyboard_input, &move_preview_setting) {
+        held_move.key = None;
+        return;
+    }
+    for (key, movement) in MOVE_KEYS {
+        if keyboard_input.just_pressed(key) {
+            input_buffer.queue(movement);
+            held_move.key = Some(key);
+            held_move.timer = Timer::from_seconds(tuning.repeat_delay_seconds, TimerMode::Once);
+        }
+    }
+
+    let Some(key) = held_move.key else {
+        return;
+    };
+    if !keyboard_input.pressed(key) {
+        held_move.key = None;
+        return;
+    }
+    held_move.timer.tick(time.delta());
+    if !held_move.timer.finished() {
+        return;
+    }
+    // Only top up an empty buffer so letting go stops straight away.
+    if input_buffer.moves.is_empty() {
+        let movement = MOVE_KEYS
+            .iter()
+            .find(|(move_key, _)| *move_key == key)
+            .map(|(_, movement)| *movement);
+        input_buffer.moves.extend(movement);
+    }
+    held_move.timer = Timer::from_seconds(1.0 / tuning.repeat_tiles_per_second, TimerMode::Once);
+}
+
+// Whether the mirror can take `step`, and the block it pushes if so. Tiles the
+// player's half of the turn passes through are off limits. The mirror doesn't use
+// teleporters.
+fn mirror_step(
+    level_state: &LevelState,
+    step: (i32, i32),
+    claimed: &[Position],
+) -> Option<Option<(Entity, Position)>> {
+    let from = level_state.mirror_position?;
+    let to = from.add(step.0, step.1);
+    if claimed.contains(&to)
+        || level_state.is_idle_player(&to)
+        || level_state.is_enemy(&to)
+        || !level_state.allows_move(from, step)
+    {
+        return None;
+    }
+    // The mirror carries no keys, and doesn't use portals either.
+    if level_state.walls.contains(&to)
+        || level_state.locked_doors.contains_key(&to)
+        || level_state.portals.contains_key(&to)
+    {
+        return None;
+    }
+    match level_state.obstacles.get(&to) {
+        Some((_, Obstacle::Pit)) => None,
+        Some((block_entity, Obstacle::Block(_))) => {
+            let block_to = to.add(step.0, step.1);
+            let is_free = !level_state.is_blocked(&block_to) || level_state.is_open_pit(&block_to);
+            (is_free
+                && !claimed.contains(&block_to)
+                && !level_state.is_idle_player(&block_to)
+                && level_state.allows_move(to, step))
+            .then_some(Some((*block_entity, to)))
+        }
+        None if level_state.is_door_closed(&to) => None,
+        None => Some(None),
+    }
+}
 
-pub struct PlayPlugin;
+pub struct PlannedPush {
+    pub block: Entity,
+    pub from: Position,
+    pub to: Position,
+    pub teleport_to: Option<Position>,
+}
 
-#[derive(Resource, Clone)]
-pub struct LevelState {
-    pub current_level: i32,
-    pub obstacles: HashMap<Position, (Entity, Obstacle)>,
-    pub goals: HashMap<Position, Entity>,
-    pub player_position: Position,
+// The first step of a move by the active player, before any ice slides.
+pub struct PlannedMove {
+    pub player_to: Position,
+    pub teleport_to: Option<Position>,
+    pub push: Option<PlannedPush>,
 }
 
-// Remove default implementation and use resource_exists run condition
-impl Default for LevelState {
-    fn default() -> Self {
-        Self {
-            current_level: Default::default(),
-            obstacles: Default::default(),
-            goals: Default::default(),
-            player_position: Position { x: 0, y: 0 },
-        }
+impl PlannedMove {
+    pub fn player_end(&self) -> Position {
+        self.teleport_to.unwrap_or(self.player_to)
     }
 }
 
-#[derive(Resource, Deref, DerefMut, Default)]
-pub struct UndoStack(pub Vec<LevelState>);
+impl PlannedPush {
+    pub fn end(&self) -> Position {
+        self.teleport_to.unwrap_or(self.to)
+    }
+}
 
-#[derive(Event)]
-struct UndoEvent;
+// Where a step by the active player would take it and any block it pushes, or
+// `None` if the step isn't allowed. Heavy blocks are planned as if they give.
+pub fn plan_move(level_state: &LevelState, (move_x, move_y): (i32, i32)) -> Option<PlannedMove> {
+    let player_position = level_state.player_position();
+    // Through a portal, the player comes out past its twin and the checks are made there.
+    let (entry, portal_exit) = level_state.portal_step(player_position, (move_x, move_y));
+    let move_to = portal_exit.unwrap_or(entry);
+    if level_state.is_out_of_energy()
+        || level_state.caught
+        || !level_state.allows_move(player_position, (move_x, move_y))
+        || level_state.mirror_position == Some(move_to)
+        || level_state.is_idle_player(&move_to)
+    {
+        return None;
+    }
 
-#[derive(Event)]
-pub struct NextLevelEvent(pub i32);
+    if level_state.walls.contains(&move_to)
+        || level_state.portals.contains_key(&move_to)
+        || (level_state.locked_doors.contains_key(&move_to) && !level_state.can_unlock(&move_to))
+    {
+        return None;
+    }
+    let push = match level_state.obstacles.get(&move_to) {
+        Some((_, Obstacle::Pit)) => return None,
+        Some((block_entity, Obstacle::Block(_))) => {
+            let (block_entry, block_portal_exit) =
+                level_state.portal_step(move_to, (move_x, move_y));
+            let block_move_to = block_portal_exit.unwrap_or(block_entry);
+            if (level_state.is_blocked(&block_move_to) && !level_state.is_open_pit(&block_move_to))
+                || level_state.mirror_position == Some(block_move_to)
+                || level_state.is_idle_player(&block_move_to)
+                || !level_state.allows_move(move_to, (move_x, move_y))
+            {
+                return None;
+            }
+            Some(PlannedPush {
+                block: *block_entity,
+                from: move_to,
+                to: block_entry,
+                teleport_to: block_portal_exit
+                    .or_else(|| level_state.teleport_exit(block_entry, move_to)),
+            })
+        }
+        None if level_state.is_door_closed(&move_to) => return None,
+        None => None,
+    };
 
-#[derive(Component)]
-pub struct Player {
-    pub is_moving: bool,
-    pub move_timer: Timer,
+    // The player can't teleport on top of the block it pushed.
+    let block_end = push.as_ref().map_or(move_to, PlannedPush::end);
+    Some(PlannedMove {
+        player_to: entry,
+        teleport_to: portal_exit.or_else(|| level_state.teleport_exit(entry, block_end)),
+        push,
+    })
 }
 
-#[derive(Component)]
-struct Moving {
-    from: Position,
-    to: Position,
+// V hands control to the next player, in reading order. Tab is left to skipping the tutorial.
+fn switch_player(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut level_state: ResMut<LevelState>,
+    mut input_buffer: ResMut<InputBuffer>,
+    player_query: Query<&Player>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::V)
+        || level_state.players.len() < 2
+        || player_query.iter().any(|player| player.is_moving)
+    {
+        return;
+    }
+    let mut players: Vec<(Entity, Position)> = level_state
+        .players
+        .iter()
+        .map(|(entity, position)| (*entity, *position))
+        .collect();
+    players.sort_by_key(|(_, position)| (position.y, position.x));
+    let current = players
+        .iter()
+        .position(|(entity, _)| *entity == level_state.active_player)
+        .unwrap_or(0);
+    level_state.active_player = players[(current + 1) % players.len()].0;
+    input_buffer.moves.clear();
 }
 
+// Turns the next buffered step into a request, the rules decide what comes of it.
 fn handle_input(
-    mut commands: Commands,
     keyboard_input: Res<Input<KeyCode>>,
-    mut undo_writer: EventWriter<UndoEvent>,
+    turn_setting: Res<TurnBeforeMovingSetting>,
+    mut input_buffer: ResMut<InputBuffer>,
     level_state: Res<LevelState>,
-    mut player_query: Query<(Entity, &mut Player)>,
+    player_query: Query<&Player>,
+    mut facing_query: Query<&mut Facing>,
+    mut move_requested_writer: EventWriter<MoveRequested>,
 ) {
-    let Some((player_entity, mut player)) = player_query.iter_mut().next() else {
+    let Ok(player) = player_query.get(level_state.active_player) else {
         return;
     };
     if player.is_moving {
         return;
     }
 
-    if keyboard_input.just_pressed(KeyCode::U) {
-        undo_writer.send(UndoEvent);
+    if keyboard_input.any_pressed([KeyCode::U, KeyCode::Y, REWIND_KEY]) {
+        input_buffer.moves.clear();
         return;
     }
 
-    let mut movement: Option<(i32, i32)> = None;
-    if keyboard_input.pressed(KeyCode::Up) {
-        movement = Some((0, -1));
-    } else if keyboard_input.pressed(KeyCode::Down) {
-        movement = Some((0, 1));
-    } else if keyboard_input.pressed(KeyCode::Left) {
-        movement = Some((-1, 0));
-    } else if keyboard_input.pressed(KeyCode::Right) {
-        movement = Some((1, 0));
+    let walking = input_buffer.walking;
+    let Some(direction) = input_buffer.moves.pop_front() else {
+        return;
+    };
+    if input_buffer.moves.is_empty() {
+        input_buffer.walking = false;
+    }
+    if let Ok(mut facing) = facing_query.get_mut(level_state.active_player) {
+        let turned = facing.0 != direction;
+        facing.0 = direction;
+        // A clicked walk sets off straight away.
+        if turned && turn_setting.enabled && !walking {
+            return;
+        }
     }
+    move_requested_writer.send(MoveRequested { direction });
+}
 
-    let Some((move_x, move_y)) = movement else {
-        return;
+// Checks a step against the level and sets everything it moves going.
+fn resolve_move(
+    commands: &mut Commands,
+    level_state: &LevelState,
+    player_query: &mut Query<&mut Player>,
+    mirror_query: &Query<Entity, With<Mirror>>,
+    heavy_query: &Query<(), With<Heavy>>,
+    move_finished_writer: &mut EventWriter<MoveFinishedEvent>,
+    (move_x, move_y): (i32, i32),
+) -> MoveResolved {
+    let blocked = MoveResolved {
+        direction: (move_x, move_y),
+        pushed: None,
+        blocked: true,
     };
-    let move_to = level_state.player_position.add(move_x, move_y);
-
-    match level_state.obstacles.get(&move_to) {
-        Some((_, Obstacle::Wall)) => return,
-        Some((block_entity, Obstacle::Block)) => {
-            let block_move_to = move_to.add(move_x, move_y);
-            if level_state.obstacles.contains_key(&block_move_to) {
-                return;
+    let Ok(mut player) = player_query.get_mut(level_state.active_player) else {
+        return blocked;
+    };
+    let Some(planned) = plan_move(level_state, (move_x, move_y)) else {
+        return blocked;
+    };
+
+    // Every tile the player's half of the turn passes through, kept clear for the mirror.
+    let mut claimed = vec![planned.player_to];
+    if let Some(push) = &planned.push {
+        if !push_gives(commands, level_state, heavy_query, push.block, push.from) {
+            // Nothing moves, but the strain is recorded as a push that went nowhere so undo
+            // and replays see it too.
+            let player_position = level_state.player_position();
+            move_finished_writer.send(MoveFinishedEvent(MoveRecord {
+                player: level_state.active_player,
+                direction: (move_x, move_y),
+                player_from: player_position,
+                player_to: player_position,
+                pushed: None,
+                mirror: None,
+                strained: Some(push.block),
+            }));
+            return MoveResolved {
+                pushed: Some(push.block),
+                ..blocked
+            };
+        }
+        claimed.push(push.to);
+        claimed.extend(push.teleport_to);
+        commands.entity(push.block).insert(Moving::new(
+            push.from,
+            (move_x, move_y),
+            push.teleport_to,
+        ));
+    }
+    let resolved = MoveResolved {
+        direction: (move_x, move_y),
+        pushed: planned.push.as_ref().map(|push| push.block),
+        blocked: false,
+    };
+
+    player.is_moving = true;
+    claimed.extend(planned.teleport_to);
+    let mut player_moving = Moving::new(
+        level_state.player_position(),
+        (move_x, move_y),
+        planned.teleport_to,
+    );
+
+    let mirror_direction = (-move_x, -move_y);
+    if let (Ok(mirror_entity), Some(mirror_position), Some(mirror_pushes)) = (
+        mirror_query.get_single(),
+        level_state.mirror_position,
+        mirror_step(level_state, mirror_direction, &claimed),
+    ) {
+        match mirror_pushes {
+            Some((block_entity, block_position))
+                if !push_gives(
+                    commands,
+                    level_state,
+                    heavy_query,
+                    block_entity,
+                    block_position,
+                ) =>
+            {
+                // The mirror stays put, it's recorded with the player's move.
+                player_moving.strained = Some(block_entity);
             }
-            commands.entity(*block_entity).insert(Moving {
-                from: move_to.clone(),
-                to: block_move_to,
-            });
+            _ => {
+                commands.entity(mirror_entity).insert(Moving::new(
+                    mirror_position,
+                    mirror_direction,
+                    None,
+                ));
+                if let Some((block_entity, block_position)) = mirror_pushes {
+                    commands.entity(block_entity).insert(Moving::new(
+                        block_position,
+                        mirror_direction,
+                        None,
+                    ));
+                }
+            }
+        }
+    }
+    commands
+        .entity(level_state.active_player)
+        .insert(player_moving);
+    resolved
+}
+
+// A strain on a heavy block is finished as soon as it's resolved, nothing has to land.
+#[derive(SystemParam)]
+struct MoveEventWriters<'w> {
+    resolved: EventWriter<'w, MoveResolved>,
+    finished: EventWriter<'w, MoveFinishedEvent>,
+}
+
+// One step a turn, the player is busy moving until it lands.
+fn resolve_moves(
+    mut commands: Commands,
+    level_state: Res<LevelState>,
+    mut player_query: Query<&mut Player>,
+    mirror_query: Query<Entity, With<Mirror>>,
+    heavy_query: Query<(), With<Heavy>>,
+    mut move_requested_reader: EventReader<MoveRequested>,
+    mut move_writers: MoveEventWriters,
+) {
+    let Some(move_requested) = move_requested_reader.read().last() else {
+        return;
+    };
+    let resolved = resolve_move(
+        &mut commands,
+        &level_state,
+        &mut player_query,
+        &mirror_query,
+        &heavy_query,
+        &mut move_writers.finished,
+        move_requested.direction,
+    );
+    move_writers.resolved.send(resolved);
+}
+
+// The rest of a walk makes no sense once a step of it is blocked.
+fn stop_blocked_walks(
+    mut input_buffer: ResMut<InputBuffer>,
+    mut move_resolved_reader: EventReader<MoveResolved>,
+) {
+    let walk_blocked = move_resolved_reader
+        .read()
+        .any(|move_resolved| move_resolved.blocked && move_resolved.pushed.is_none());
+    if walk_blocked && input_buffer.walking {
+        input_buffer.walk([]);
+    }
+}
+
+// Blocks moved by going through the history, each from where it was to where it went.
+type MovedBlocks = Vec<(Entity, Position, Position)>;
+
+fn undo_last(
+    level_state: &mut LevelState,
+    move_history: &mut MoveHistory,
+    moved_blocks: &mut MovedBlocks,
+) -> Option<MoveRecord> {
+    let record = move_history.undo.pop()?;
+    level_state.revert(&record);
+    moved_blocks.extend(record.pushed.map(|(entity, from, to)| (entity, to, from)));
+    moved_blocks.extend(
+        record
+            .mirror
+            .and_then(|mirror| mirror.pushed)
+            .map(|(entity, from, to)| (entity, to, from)),
+    );
+    move_history.redo.push(record);
+    Some(record)
+}
+
+// Rewind walking moves until a push has been undone too.
+fn undo_to_last_push(
+    level_state: &mut LevelState,
+    move_history: &mut MoveHistory,
+    moved_blocks: &mut MovedBlocks,
+) {
+    while let Some(record) = undo_last(level_state, move_history, moved_blocks) {
+        if record.pushed.is_some() {
+            break;
         }
-        _ => {}
     }
+}
+
+type WalkerFilter = Or<(With<Player>, With<Mirror>)>;
+
+// The board and the moves that got it there, rewound and replayed together.
+#[derive(SystemParam)]
+struct History<'w> {
+    level_state: ResMut<'w, LevelState>,
+    move_history: ResMut<'w, MoveHistory>,
+    undo_limit: Res<'w, UndoLimit>,
+}
+
+const REWIND_KEY: KeyCode = KeyCode::Back;
 
+// Holding rewind plays moves backwards one after another, easing everything back to
+// where it came from at an even pace.
+fn rewind_held(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    history: History,
+    mut input_buffer: ResMut<InputBuffer>,
+    mut player_query: Query<&mut Player>,
+    mirror_query: Query<Entity, With<Mirror>>,
+    mut board_changed_writer: EventWriter<BoardChangedEvent>,
+) {
+    if !keyboard_input.pressed(REWIND_KEY) || player_query.iter().any(|player| player.is_moving) {
+        return;
+    }
+    let History {
+        mut level_state,
+        mut move_history,
+        ..
+    } = history;
+    input_buffer.walk([]);
+    let Some(record) = undo_last(&mut level_state, &mut move_history, &mut Vec::new()) else {
+        return;
+    };
+    board_changed_writer.send(BoardChangedEvent { source: "rewind" });
+    let Ok(mut player) = player_query.get_mut(record.player) else {
+        return;
+    };
     player.is_moving = true;
-    commands.entity(player_entity).insert(Moving {
-        from: level_state.player_position.clone(),
-        to: move_to,
-    });
+    player.move_timer.reset();
+    commands
+        .entity(record.player)
+        .insert(Moving::between(record.player_to, record.player_from));
+    if let (Ok(mirror_entity), Some(mirror)) = (mirror_query.get_single(), record.mirror) {
+        commands
+            .entity(mirror_entity)
+            .insert(Moving::between(mirror.to, mirror.from));
+    }
+    let pushes = record
+        .pushed
+        .into_iter()
+        .chain(record.mirror.and_then(|mirror| mirror.pushed));
+    for (block_entity, from, to) in pushes {
+        commands
+            .entity(block_entity)
+            .insert(Moving::between(to, from));
+    }
 }
 
 fn reset_state(
-    mut level_state: ResMut<LevelState>,
-    mut undo_stack: ResMut<UndoStack>,
-    mut undo_reader: EventReader<UndoEvent>,
-    player_query: Query<Entity, With<Player>>,
-    mut transform_query: Query<&mut Transform>,
+    mut commands: Commands,
+    undo_granularity: Res<UndoGranularity>,
+    history: History,
+    mut history_reader: EventReader<HistoryEvent>,
+    mut completion_writer: CompletionWriter,
+    walker_query: Query<(Entity, Has<Mirror>), WalkerFilter>,
+    mut player_query: Query<&mut Player>,
 ) {
-    if undo_reader.read().next().is_some() {
-        let Some(previous_state) = undo_stack.pop() else {
-            return;
-        };
-        *level_state = previous_state;
+    // Going through the history waits for whatever is moving to land.
+    if player_query.iter().any(|player| player.is_moving) {
+        history_reader.clear();
+        return;
+    }
+    let History {
+        mut level_state,
+        mut move_history,
+        undo_limit,
+    } = history;
+    let players_before = level_state.players.clone();
+    let mirror_before = level_state.mirror_position;
+    let mut moved_blocks = Vec::new();
+    for history_event in history_reader.read() {
+        match history_event {
+            HistoryEvent::Undo => match *undo_granularity {
+                UndoGranularity::Move => {
+                    undo_last(&mut level_state, &mut move_history, &mut moved_blocks);
+                }
+                UndoGranularity::Push => {
+                    undo_to_last_push(&mut level_state, &mut move_history, &mut moved_blocks);
+                }
+                UndoGranularity::Block => {
+                    // Rewind to just after the last push of a different block.
+                    let last_block = move_history
+                        .undo
+                        .iter()
+                        .rev()
+                        .find_map(|record| record.pushed.map(|(entity, _, _)| entity));
+                    while let Some(record) = move_history.undo.last() {
+                        let pushed_block = record.pushed.map(|(entity, _, _)| entity);
+                        if pushed_block.is_some() && pushed_block != last_block {
+                            break;
+                        }
+                        undo_last(&mut level_state, &mut move_history, &mut moved_blocks);
+                    }
+                }
+            },
+            HistoryEvent::UndoToLastPush => {
+                undo_to_last_push(&mut level_state, &mut move_history, &mut moved_blocks);
+            }
+            HistoryEvent::Redo => {
+                if let Some(record) = move_history.redo(*undo_limit) {
+                    level_state.apply(&record);
+                    moved_blocks.extend(record.pushed);
+                    moved_blocks.extend(record.mirror.and_then(|mirror| mirror.pushed));
+                    completion_writer.send_if_solved(&level_state);
+                }
+            }
+        }
+    }
 
-        let Some(player_entity) = player_query.iter().next() else {
-            return;
-        };
-        let Ok(mut player_transform) = transform_query.get_mut(player_entity) else {
-            return;
+    // Everything that went somewhere eases straight there, however many moves it took.
+    let mut eases: HashMap<Entity, (Position, Position)> = HashMap::default();
+    for (entity, is_mirror) in &walker_query {
+        let (before, after) = if is_mirror {
+            (mirror_before, level_state.mirror_position)
+        } else {
+            (
+                players_before.get(&entity).copied(),
+                level_state.players.get(&entity).copied(),
+            )
         };
-        player_transform.translation = level_state.player_position.to_translation();
-
-        for (position, (obstacle_entity, obstacle)) in level_state.obstacles.iter() {
-            let Obstacle::Block = obstacle else { continue };
-            let Ok(mut block_transform) = transform_query.get_mut(*obstacle_entity) else {
-                continue;
-            };
-            block_transform.translation = position.to_translation();
+        if let (Some(before), Some(after)) = (before, after) {
+            eases.insert(entity, (before, after));
         }
     }
+    for (block_entity, from, to) in moved_blocks {
+        eases
+            .entry(block_entity)
+            .and_modify(|(_, end)| *end = to)
+            .or_insert((from, to));
+    }
+    eases.retain(|_, (from, to)| from != to);
+    if eases.is_empty() {
+        return;
+    }
+    // The active player carries the timer, so it eases too even when it ends up where it was.
+    let player_position = level_state.player_position();
+    eases
+        .entry(level_state.active_player)
+        .or_insert((player_position, player_position));
+
+    for (entity, (from, to)) in eases {
+        commands
+            .entity(entity)
+            .remove::<Shake>()
+            .insert(Moving::between(from, to));
+    }
+    // Input stays locked until everything has eased into place.
+    if let Ok(mut player) = player_query.get_mut(level_state.active_player) {
+        player.is_moving = true;
+        player.move_timer.reset();
+    }
 }
 
 // Adapted from: https://github.com/godotengine/godot/blob/27b2260460ab478707d884a16429add5bb3375f1/scene/animation/easing_equations.h
@@ -147,79 +1574,463 @@ fn quad_ease_out_v(a: Vec3, b: Vec3, d: f32) -> Vec3 {
     )
 }
 
-fn move_objects(
+fn cubic_ease_in_out(x: f32, y: f32, d: f32) -> f32 {
+    let eased = if d < 0.5 {
+        4.0 * d * d * d
+    } else {
+        1.0 - (-2.0 * d + 2.0).powi(3) / 2.0
+    };
+    x + (y - x) * eased
+}
+
+fn cubic_ease_in_out_v(a: Vec3, b: Vec3, d: f32) -> Vec3 {
+    Vec3::new(
+        cubic_ease_in_out(a.x, b.x, d),
+        cubic_ease_in_out(a.y, b.y, d),
+        cubic_ease_in_out(a.z, b.z, d),
+    )
+}
+
+// Stretches a pushed block along the push and squashes it across, peaking mid-move.
+fn squash_stretch(moving: &Moving, amount: f32, d: f32) -> Vec3 {
+    let stretch = 1.0 + amount * (d * std::f32::consts::PI).sin();
+    let squash = 2.0 - stretch;
+    if moving.from.x != moving.to.x {
+        Vec3::new(stretch, squash, 1.0)
+    } else {
+        Vec3::new(squash, stretch, 1.0)
+    }
+}
+
+// Anything that finished on ice carries on a tile, the block ahead going first so
+// the player slides into the space it leaves.
+fn continue_slides(
+    level_state: &LevelState,
+    player_entity: Entity,
+    moving_query: &mut MovingQuery,
+) -> bool {
+    let mut occupied: Vec<Position> = moving_query
+        .iter()
+        .map(|(_, moving, _, _)| moving.to)
+        .collect();
+    // A mirror that couldn't take its step is still in the way, as are idle players.
+    if !moving_query.iter().any(|(_, _, _, is_mirror)| is_mirror) {
+        occupied.extend(level_state.mirror_position);
+    }
+    occupied.extend(
+        level_state
+            .players
+            .iter()
+            .filter(|(entity, _)| **entity != player_entity)
+            .map(|(_, position)| *position),
+    );
+    let mut sliding = false;
+    let mut movers: Vec<_> = moving_query.iter_mut().collect();
+    movers.sort_by_key(|(entity, _, _, is_mirror)| *entity == player_entity || *is_mirror);
+    for (entity, moving, _, is_mirror) in &mut movers {
+        let next = moving.to.add(moving.step.0, moving.step.1);
+        // Blocks can slide into a pit, the player and mirror stop at the edge.
+        let is_block = *entity != player_entity && !*is_mirror;
+        let is_open =
+            !level_state.is_blocked(&next) || (is_block && level_state.is_open_pit(&next));
+        let keeps_sliding = moving.teleport_to.is_none()
+            && level_state.ice.contains(&moving.to)
+            && level_state.allows_move(moving.to, moving.step)
+            && is_open
+            && !occupied.contains(&next);
+        moving.from = moving.to;
+        moving.sliding = keeps_sliding;
+        if keeps_sliding {
+            occupied.retain(|position| *position != moving.to);
+            occupied.push(next);
+            moving.to = next;
+            sliding = true;
+        }
+    }
+    sliding
+}
+
+type MovingQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Moving,
+        &'static mut Transform,
+        Has<Mirror>,
+    ),
+>;
+
+// How long the current leg of a move takes.
+fn move_seconds(tuning: &MovementTuning, moving_query: &MovingQuery, player_entity: Entity) -> f32 {
+    if tuning.fast_forward {
+        return 0.0;
+    }
+    let is_push = moving_query
+        .iter()
+        .any(|(entity, _, _, is_mirror)| entity != player_entity && !is_mirror);
+    if moving_query
+        .iter()
+        .any(|(_, moving, _, _)| moving.replaying)
+    {
+        tuning.rewind_seconds
+    } else if moving_query.iter().any(|(_, moving, _, _)| moving.sliding) {
+        tuning.slide_seconds
+    } else if is_push {
+        tuning.push_seconds
+    } else {
+        tuning.walk_seconds
+    }
+}
+
+// Ticks the move along and lands everything once it's done. Runs on `FixedUpdate` when
+// `FixedTimestepSetting` is on, so how a move plays out doesn't depend on the frame rate.
+fn advance_moves(
     time: Res<Time>,
+    tuning: Res<MovementTuning>,
+    level_state: Res<LevelState>,
     mut commands: Commands,
-    mut level_state: ResMut<LevelState>,
-    mut undo_stack: ResMut<UndoStack>,
     mut player_query: Query<(Entity, &mut Player)>,
-    mut moving_query: Query<(Entity, &Moving, &mut Transform)>,
-    mut next_level_writer: EventWriter<NextLevelEvent>,
+    mut moving_query: MovingQuery,
+    mut move_finished_writer: EventWriter<MoveFinishedEvent>,
 ) {
-    let Some((player_entity, mut player)) = player_query.iter_mut().next() else {
+    let Ok((player_entity, mut player)) = player_query.get_mut(level_state.active_player) else {
         return;
     };
-    if !player.is_moving {
+    // Whatever set the move going may not have had its `Moving` added yet.
+    if !player.is_moving || !moving_query.contains(player_entity) {
         return;
     }
 
+    let seconds = move_seconds(&tuning, &moving_query, player_entity);
+    player
+        .move_timer
+        .set_duration(Duration::from_secs_f32(seconds));
     player.move_timer.tick(time.delta());
     if !player.move_timer.finished() {
-        for (_entity, moving, mut transform) in &mut moving_query {
-            transform.translation = quad_ease_out_v(
-                moving.from.to_translation(),
-                moving.to.to_translation(),
-                player.move_timer.percent(),
-            );
-        }
-    } else {
-        player.move_timer.reset();
+        return;
+    }
+
+    player.move_timer.reset();
+    for (_, moving, mut transform, _) in &mut moving_query {
+        transform.translation = moving.to.to_translation();
+        transform.scale = Vec3::ONE;
+    }
+    // A step replayed from the history is already in it, there's nothing to record.
+    if moving_query
+        .iter()
+        .any(|(_, moving, _, _)| moving.replaying)
+    {
         player.is_moving = false;
-        undo_stack.push(level_state.clone());
-        for (entity, moving, mut transform) in &mut moving_query {
-            transform.translation = moving.to.to_translation();
+        for (entity, _, _, _) in &moving_query {
             commands.entity(entity).remove::<Moving>();
-            if entity == player_entity {
-                level_state.player_position = moving.to;
+        }
+        return;
+    }
+    if continue_slides(&level_state, player_entity, &mut moving_query) {
+        return;
+    }
+
+    player.is_moving = false;
+    let Ok((_, player_moving, _, _)) = moving_query.get(player_entity) else {
+        return;
+    };
+    let (player_from, direction, strained) = (
+        player_moving.start,
+        player_moving.step,
+        player_moving.strained,
+    );
+    let mut player_to = player_from;
+    let mut pushed = None;
+    let mut mirror_move = None;
+    let mut mirror_pushed = None;
+    for (entity, moving, mut transform, is_mirror) in &mut moving_query {
+        let end = moving.teleport_to.unwrap_or(moving.to);
+        transform.translation = end.to_translation();
+        commands.entity(entity).remove::<Moving>();
+        if entity == player_entity {
+            player_to = end;
+        } else if is_mirror {
+            mirror_move = Some((moving.start, end));
+        } else if moving.step == direction {
+            pushed = Some((entity, moving.start, end));
+        } else {
+            // The mirror always pushes the other way to the player.
+            mirror_pushed = Some((entity, moving.start, end));
+        }
+    }
+    move_finished_writer.send(MoveFinishedEvent(MoveRecord {
+        player: player_entity,
+        direction,
+        player_from,
+        player_to,
+        pushed,
+        mirror: mirror_move.map(|(from, to)| MirrorMove {
+            from,
+            to,
+            pushed: mirror_pushed,
+        }),
+        strained,
+    }));
+}
+
+// Eases everything in flight along its move. With a fixed timestep the time left over
+// since the last step is counted too, so drawing stays smooth between steps.
+fn tween_moves(
+    tuning: Res<MovementTuning>,
+    fixed_timestep_setting: Res<FixedTimestepSetting>,
+    fixed_time: Res<Time<Fixed>>,
+    level_state: Res<LevelState>,
+    player_query: Query<&Player>,
+    mut moving_query: MovingQuery,
+) {
+    let Ok(player) = player_query.get(level_state.active_player) else {
+        return;
+    };
+    if !player.is_moving {
+        return;
+    }
+
+    let player_entity = level_state.active_player;
+    let seconds = move_seconds(&tuning, &moving_query, player_entity);
+    let mut elapsed = player.move_timer.elapsed_secs();
+    if fixed_timestep_setting.enabled {
+        elapsed += fixed_time.delta_seconds() * fixed_time.overstep_percentage();
+    }
+    let d = if seconds > 0.0 {
+        (elapsed / seconds).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    let is_push = moving_query
+        .iter()
+        .any(|(entity, _, _, is_mirror)| entity != player_entity && !is_mirror);
+    let is_sliding = moving_query.iter().any(|(_, moving, _, _)| moving.sliding);
+    for (entity, moving, mut transform, is_mirror) in &mut moving_query {
+        let from = moving.from.to_translation();
+        let to = moving.to.to_translation();
+        if moving.replaying {
+            transform.translation = cubic_ease_in_out_v(from, to, d);
+            continue;
+        }
+        if is_sliding {
+            transform.translation = from.lerp(to, d);
+            continue;
+        }
+        if !is_push {
+            transform.translation = quad_ease_out_v(from, to, d);
+            continue;
+        }
+        transform.translation = cubic_ease_in_out_v(from, to, d);
+        if entity != player_entity && !is_mirror {
+            // Scale about the tile centre rather than the top left anchor.
+            let scale = squash_stretch(&moving, tuning.push_squash, d);
+            transform.scale = scale;
+            transform.translation.x += (1.0 - scale.x) * TILE_SIZE / 2.0;
+            transform.translation.y -= (1.0 - scale.y) * TILE_SIZE / 2.0;
+        }
+    }
+}
+
+fn fixed_timestep_on(fixed_timestep_setting: Res<FixedTimestepSetting>) -> bool {
+    fixed_timestep_setting.enabled
+}
+
+fn shake_blocks(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut shake_query: Query<(Entity, &mut Shake, &mut Transform)>,
+) {
+    for (entity, mut shake, mut transform) in &mut shake_query {
+        shake.timer.tick(time.delta());
+        if shake.timer.finished() {
+            transform.translation = shake.origin;
+            commands.entity(entity).remove::<Shake>();
+            continue;
+        }
+        let fade = 1.0 - shake.timer.percent();
+        let offset = (shake.timer.elapsed_secs() * 60.0).sin() * 1.5 * fade;
+        transform.translation = shake.origin + Vec3::new(offset, 0.0, 0.0);
+    }
+}
+
+fn record_moves(
+    undo_limit: Res<UndoLimit>,
+    mut level_state: ResMut<LevelState>,
+    mut move_history: ResMut<MoveHistory>,
+    mut move_finished_reader: EventReader<MoveFinishedEvent>,
+    mut completion_writer: CompletionWriter,
+    mut board_changed_writer: EventWriter<BoardChangedEvent>,
+) {
+    for move_finished in move_finished_reader.read() {
+        level_state.apply(&move_finished.0);
+        move_history.push(move_finished.0, *undo_limit);
+        board_changed_writer.send(BoardChangedEvent { source: "move" });
+        completion_writer.send_if_solved(&level_state);
+    }
+}
+
+// Players look the way they face, flipped when facing left and marked on the facing edge.
+fn show_facing(
+    mut facing_query: Query<(&Facing, &Children, &mut TextureAtlasSprite), Changed<Facing>>,
+    mut marker_query: Query<&mut Transform, With<FacingMarker>>,
+) {
+    for (facing, children, mut sprite) in &mut facing_query {
+        if facing.0 .0 != 0 {
+            sprite.flip_x = facing.0 .0 < 0;
+        }
+        for child in children {
+            if let Ok(mut transform) = marker_query.get_mut(*child) {
+                transform.translation = facing_marker_translation(facing.0);
             }
+        }
+    }
+}
 
-            let Some(obstacle) = level_state.obstacles.remove(&moving.from) else {
-                continue;
+fn update_doors(level_state: Res<LevelState>, mut visibility_query: Query<&mut Visibility>) {
+    if !level_state.is_changed() {
+        return;
+    }
+    for (position, door_entity) in &level_state.doors {
+        if let Ok(mut visibility) = visibility_query.get_mut(*door_entity) {
+            *visibility = if level_state.is_door_closed(position) {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
             };
-            level_state.obstacles.insert(moving.to, obstacle);
         }
+    }
+}
 
-        let has_won = level_state
-            .goals
-            .iter()
-            .all(|(goal_position, _)| level_state.obstacles.contains_key(goal_position));
-        if has_won {
-            next_level_writer.send(NextLevelEvent(level_state.current_level + 1));
+// Filled pits are hidden under the block that filled them, which sinks to floor level.
+// Idle players are dimmed so it's clear who moves next.
+fn highlight_active_player(
+    level_state: Res<LevelState>,
+    theme: Res<Theme>,
+    mut player_query: Query<(Entity, &mut TextureAtlasSprite), With<Player>>,
+) {
+    if !level_state.is_changed() && !theme.is_changed() {
+        return;
+    }
+    for (entity, mut sprite) in &mut player_query {
+        sprite.color = if level_state.players.len() < 2 || entity == level_state.active_player {
+            theme.tint(Tile::Player)
+        } else {
+            Color::rgb(0.45, 0.45, 0.5)
+        };
+    }
+}
+
+fn update_pits(
+    level_state: Res<LevelState>,
+    theme: Res<Theme>,
+    mut visibility_query: Query<&mut Visibility>,
+    mut sprite_query: Query<(&mut TextureAtlasSprite, &mut Transform)>,
+) {
+    if !level_state.is_changed() && !theme.is_changed() {
+        return;
+    }
+    let open_pits = level_state
+        .obstacles
+        .values()
+        .filter(|(_, obstacle)| *obstacle == Obstacle::Pit);
+    for (pit_entity, _) in open_pits {
+        if let Ok(mut visibility) = visibility_query.get_mut(*pit_entity) {
+            *visibility = Visibility::Inherited;
+        }
+    }
+    for (block_entity, obstacle) in level_state.obstacles.values() {
+        let Obstacle::Block(color) = obstacle else {
+            continue;
+        };
+        if let Ok((mut sprite, mut transform)) = sprite_query.get_mut(*block_entity) {
+            sprite.color = theme.tint(Tile::Block(*color));
+            transform.translation.z = 1.0;
+        }
+    }
+    for (_, pit_entity, (block_entity, _), _) in &level_state.filled_pits {
+        if let Ok(mut visibility) = visibility_query.get_mut(*pit_entity) {
+            *visibility = Visibility::Hidden;
+        }
+        if let Ok((mut sprite, mut transform)) = sprite_query.get_mut(*block_entity) {
+            sprite.color = Color::rgb(0.5, 0.45, 0.4);
+            transform.translation.z = 0.1;
         }
     }
 }
 
+fn despawn_level(
+    commands: &mut Commands,
+    level_entity_query: &Query<Entity, With<LevelEntity>>,
+    level_unloaded_writer: &mut EventWriter<LevelUnloadedEvent>,
+    level: i32,
+) {
+    if level_entity_query.is_empty() {
+        return;
+    }
+    for entity in level_entity_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    level_unloaded_writer.send(LevelUnloadedEvent(level));
+}
+
+pub fn unload_level(
+    mut commands: Commands,
+    level_state: Res<LevelState>,
+    level_entity_query: Query<Entity, With<LevelEntity>>,
+    mut level_unloaded_writer: EventWriter<LevelUnloadedEvent>,
+) {
+    despawn_level(
+        &mut commands,
+        &level_entity_query,
+        &mut level_unloaded_writer,
+        level_state.current_level,
+    );
+}
+
 fn load_next_level(
     mut commands: Commands,
-    almost_everything_query: Query<Entity, Without<Window>>,
-    asset_server: Res<AssetServer>,
-    mut next_level_reader: EventReader<NextLevelEvent>,
+    level_state: Res<LevelState>,
+    level_pack: Res<LevelPack>,
+    level_entity_query: Query<Entity, With<LevelEntity>>,
+    mut load_level_reader: EventReader<LoadLevelEvent>,
+    mut level_event_writers: LevelEventWriters,
 ) {
-    let Some(next_level) = next_level_reader.read().next() else {
+    let Some(next_level) = load_level_reader.read().next() else {
         return;
     };
-    for entity in almost_everything_query.iter() {
-        commands.entity(entity).despawn();
-    }
+    despawn_level(
+        &mut commands,
+        &level_entity_query,
+        &mut level_event_writers.unloaded,
+        level_state.current_level,
+    );
 
-    let next_level_layout = match next_level.0 {
-        1 => level_one(),
-        2 => level_two(),
-        3 => level_three(),
-        4 => level_four(),
-        _ => panic!("Level not found"),
+    let Some(next_level_layout) = level_pack.level(next_level.0) else {
+        panic!("Level not found");
     };
-    level_setup(commands, asset_server, next_level.0, next_level_layout);
+    level_event_writers.loaded.send(LevelLoadedEvent {
+        level: next_level.0,
+        hash: level_hash(&next_level_layout),
+        width: next_level_layout
+            .iter()
+            .map(|row| row.len())
+            .max()
+            .unwrap_or(0) as i32,
+        height: next_level_layout.len() as i32,
+    });
+    let metadata = level_pack.metadata(next_level.0);
+    level_setup(commands, next_level.0, next_level_layout, metadata);
+}
+
+fn restart_level(
+    keyboard_input: Res<Input<KeyCode>>,
+    level_state: Res<LevelState>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+) {
+    if keyboard_input.just_pressed(KeyCode::R) {
+        next_level_writer.send(NextLevelEvent(level_state.current_level));
+    }
 }
 
 fn pause_game(
@@ -229,29 +2040,82 @@ fn pause_game(
     if keyboard_input.just_pressed(KeyCode::Space) {
         keyboard_input.reset(KeyCode::Space);
         game_state.set(GameState::Paused);
-        return;
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        keyboard_input.reset(KeyCode::Escape);
+        game_state.set(GameState::Paused);
     } else if keyboard_input.just_pressed(KeyCode::E) {
         keyboard_input.reset(KeyCode::E);
         game_state.set(GameState::Editing);
+    } else if keyboard_input.just_pressed(KeyCode::L) {
+        keyboard_input.reset(KeyCode::L);
+        game_state.set(GameState::LevelSelect);
     }
 }
 
 impl Plugin for PlayPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<UndoEvent>()
+        app.add_event::<HistoryEvent>()
             .add_event::<NextLevelEvent>()
+            .add_event::<LoadLevelEvent>()
+            .add_event::<MoveFinishedEvent>()
+            .add_event::<MoveRequested>()
+            .add_event::<MoveResolved>()
+            .add_event::<LevelCompletedEvent>()
+            .add_event::<LevelLoadedEvent>()
+            .add_event::<BoardChangedEvent>()
+            .add_event::<LevelUnloadedEvent>()
+            .add_event::<PackCompletedEvent>()
+            .insert_resource(MovementTuning::default())
+            .insert_resource(UndoGranularity::default())
+            .insert_resource(UndoLimit::default())
+            .insert_resource(FixedTimestepSetting::default())
+            .insert_resource(TurnBeforeMovingSetting::default())
+            .insert_resource(InputBuffer::default())
+            .insert_resource(HeldMove::default())
+            .insert_resource(UndoRepeat::default())
             .insert_resource(LevelState::default())
-            .insert_resource(UndoStack::default())
+            .insert_resource(MoveHistory::default())
             .add_systems(
                 Update,
                 (
-                    pause_game,
-                    handle_input.after(pause_game),
-                    reset_state.after(handle_input),
-                    move_objects.after(handle_input),
-                    load_next_level.after(move_objects),
+                    pause_game.run_if(transition_idle),
+                    restart_level.after(pause_game).run_if(transition_idle),
+                    handle_undo_input.after(pause_game).run_if(transition_idle),
+                    buffer_input.after(pause_game).run_if(transition_idle),
+                    switch_player.after(pause_game).run_if(transition_idle),
+                    rewind_held.after(pause_game).run_if(transition_idle),
+                    handle_input
+                        .after(handle_undo_input)
+                        .after(rewind_held)
+                        .after(buffer_input)
+                        .after(switch_player)
+                        .run_if(transition_idle),
+                    resolve_moves.after(handle_input),
+                    stop_blocked_walks.after(resolve_moves),
+                    reset_state.after(resolve_moves),
+                    advance_moves
+                        .after(resolve_moves)
+                        .run_if(not(fixed_timestep_on)),
+                    tween_moves.after(advance_moves),
+                    record_moves.after(advance_moves),
+                    update_doors.after(record_moves).after(reset_state),
+                    show_facing.after(handle_input),
+                    update_pits.after(record_moves).after(reset_state),
+                    shake_blocks,
+                    highlight_active_player
+                        .after(record_moves)
+                        .after(reset_state)
+                        .after(switch_player),
+                    complete_level.after(record_moves).after(reset_state),
+                    load_next_level.after(complete_level),
                 )
                     .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                FixedUpdate,
+                advance_moves
+                    .run_if(fixed_timestep_on)
+                    .run_if(in_state(GameState::Playing)),
             );
     }
 }
@@ -0,0 +1,290 @@
+use bevy::{ecs::system::SystemParam, prelude::*, sprite::Anchor};
+
+use crate::{
+    notes_plugin::NoteEditor,
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{LevelPack, LevelState, NextLevelEvent, PackCompletedEvent},
+    sokoban_core::{cell_from_code, Tile},
+    GameState, Obstacle, Position, TILE_SIZE,
+};
+
+pub const PRACTICE_PACK: &str = "Practice";
+
+pub struct PracticePlugin;
+
+// The pack and level to go back to when practice ends.
+#[derive(Resource, Default)]
+pub struct PracticeSession {
+    original: Option<(LevelPack, i32)>,
+}
+
+impl PracticeSession {
+    pub fn is_active(&self) -> bool {
+        self.original.is_some()
+    }
+}
+
+#[derive(Resource)]
+struct PracticeSelection {
+    cursor: Position,
+    anchor: Option<Position>,
+}
+
+#[derive(SystemParam)]
+struct LevelSwitch<'w> {
+    next_level_writer: EventWriter<'w, NextLevelEvent>,
+    game_state: ResMut<'w, NextState<GameState>>,
+}
+
+impl LevelSwitch<'_> {
+    fn play(&mut self, level: i32) {
+        self.next_level_writer.send(NextLevelEvent(level));
+        self.game_state.set(GameState::Playing);
+    }
+}
+
+#[derive(Component)]
+struct PracticeOverlay;
+
+#[derive(Component)]
+struct SelectionBox;
+
+#[derive(Component)]
+struct SelectionHint;
+
+// Cuts the current board down to the given corners, walling in everything around it.
+fn practice_layout(level_state: &LevelState, from: Position, to: Position) -> Vec<Vec<i32>> {
+    let (min_x, max_x) = (from.x.min(to.x), from.x.max(to.x));
+    let (min_y, max_y) = (from.y.min(to.y), from.y.max(to.y));
+
+    let mut layout = vec![vec![8; (max_x - min_x + 3) as usize]; (max_y - min_y + 3) as usize];
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let position = Position { x, y };
+            let goal_color = level_state.goals.get(&position).map(|(_, color)| *color);
+            let tile = match level_state.obstacles.get(&position) {
+                _ if level_state.walls.contains(&position) => 8,
+                Some((_, Obstacle::Pit)) => 1024,
+                Some((_, Obstacle::Block(0))) if goal_color == Some(0) => 6,
+                // A colored block already on its goal can't be written in the level format,
+                // so it stays put as a wall.
+                Some((_, Obstacle::Block(_))) if goal_color.is_some() => 8,
+                Some((_, Obstacle::Block(0))) => 2,
+                Some((_, Obstacle::Block(color))) => 4096 + *color as i32,
+                None if level_state
+                    .players
+                    .values()
+                    .any(|player| *player == position) =>
+                {
+                    if goal_color == Some(0) {
+                        5
+                    } else {
+                        1
+                    }
+                }
+                None => match goal_color {
+                    Some(0) => 4,
+                    Some(color) => 4112 + color as i32,
+                    None => 0,
+                },
+            };
+            layout[(y - min_y + 1) as usize][(x - min_x + 1) as usize] = tile;
+        }
+    }
+    layout
+}
+
+fn handle_practice_pause_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    note_editor: Res<NoteEditor>,
+    mut commands: Commands,
+    mut practice_session: ResMut<PracticeSession>,
+    mut level_switch: LevelSwitch,
+) {
+    if note_editor.editing || !keyboard_input.just_pressed(KeyCode::P) {
+        return;
+    }
+
+    match practice_session.original.take() {
+        Some((level_pack, level)) => {
+            commands.insert_resource(level_pack);
+            level_switch.play(level);
+        }
+        None => level_switch.game_state.set(GameState::PracticeSelect),
+    }
+}
+
+fn show_practice_selection(mut commands: Commands, level_state: Res<LevelState>) {
+    commands.insert_resource(PracticeSelection {
+        cursor: level_state.player_position(),
+        anchor: None,
+    });
+    commands.spawn((
+        PracticeOverlay,
+        SelectionBox,
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 0.0, 0.35),
+                anchor: Anchor::TopLeft,
+                ..default()
+            },
+            ..default()
+        },
+    ));
+    commands.spawn((
+        PracticeOverlay,
+        SelectionHint,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+fn hide_practice_selection(
+    mut commands: Commands,
+    overlay_query: Query<Entity, With<PracticeOverlay>>,
+) {
+    for entity in overlay_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn handle_practice_select_input(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    level_state: Res<LevelState>,
+    level_pack: Res<LevelPack>,
+    mut selection: ResMut<PracticeSelection>,
+    mut practice_session: ResMut<PracticeSession>,
+    mut level_switch: LevelSwitch,
+) {
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        level_switch.game_state.set(GameState::Paused);
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        selection.cursor = selection.cursor.add(0, -1);
+    } else if keyboard_input.just_pressed(KeyCode::Down) {
+        selection.cursor = selection.cursor.add(0, 1);
+    } else if keyboard_input.just_pressed(KeyCode::Left) {
+        selection.cursor = selection.cursor.add(-1, 0);
+    } else if keyboard_input.just_pressed(KeyCode::Right) {
+        selection.cursor = selection.cursor.add(1, 0);
+    }
+
+    if !keyboard_input.just_pressed(KeyCode::Return) {
+        return;
+    }
+    let Some(anchor) = selection.anchor else {
+        selection.anchor = Some(selection.cursor);
+        return;
+    };
+
+    let layout = practice_layout(&level_state, anchor, selection.cursor);
+    if !layout
+        .iter()
+        .flatten()
+        .any(|tile| cell_from_code(*tile).contains(&Tile::Player))
+    {
+        return;
+    }
+    if !practice_session.is_active() {
+        practice_session.original = Some((level_pack.clone(), level_state.current_level));
+    }
+    commands.insert_resource(LevelPack {
+        name: PRACTICE_PACK.to_string(),
+        levels: vec![layout],
+        metadata: Vec::new(),
+        return_state: GameState::Playing,
+    });
+    level_switch.play(1);
+}
+
+fn update_practice_selection(
+    selection: Res<PracticeSelection>,
+    level_state: Res<LevelState>,
+    mut box_query: Query<(&mut Sprite, &mut Transform), With<SelectionBox>>,
+    mut hint_query: Query<&mut Text, With<SelectionHint>>,
+) {
+    let anchor = selection.anchor.unwrap_or(selection.cursor);
+    let min = Position {
+        x: anchor.x.min(selection.cursor.x),
+        y: anchor.y.min(selection.cursor.y),
+    };
+    let width = (anchor.x - selection.cursor.x).abs() + 1;
+    let height = (anchor.y - selection.cursor.y).abs() + 1;
+
+    if let Ok((mut sprite, mut transform)) = box_query.get_single_mut() {
+        sprite.custom_size = Some(Vec2::new(
+            width as f32 * TILE_SIZE,
+            height as f32 * TILE_SIZE,
+        ));
+        transform.translation = min.to_translation_z(5.0);
+    }
+
+    if let Ok(mut text) = hint_query.get_single_mut() {
+        let player_position = level_state.player_position();
+        let contains_player = (min.x..min.x + width).contains(&player_position.x)
+            && (min.y..min.y + height).contains(&player_position.y);
+        text.sections[0].value = if selection.anchor.is_none() {
+            "Practice: move to a corner and press Enter  Backspace: cancel".to_string()
+        } else if !contains_player {
+            "The region must contain the player".to_string()
+        } else {
+            "Enter: practice this region  Backspace: cancel".to_string()
+        };
+    }
+}
+
+// Finishing the region starts it again, practice only ends from the pause menu.
+fn restart_practice(
+    mut pack_completed_reader: EventReader<PackCompletedEvent>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+) {
+    for pack_completed in pack_completed_reader.read() {
+        if pack_completed.name == PRACTICE_PACK {
+            next_level_writer.send(NextLevelEvent(1));
+        }
+    }
+}
+
+fn end_practice_session(mut practice_session: ResMut<PracticeSession>) {
+    practice_session.original = None;
+}
+
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PracticeSession::default())
+            .add_systems(OnEnter(GameState::PracticeSelect), show_practice_selection)
+            .add_systems(OnExit(GameState::PracticeSelect), hide_practice_selection)
+            .add_systems(OnEnter(GameState::LevelSelect), end_practice_session)
+            .add_systems(OnEnter(GameState::Challenges), end_practice_session)
+            .add_systems(OnEnter(GameState::Editing), end_practice_session)
+            .add_systems(
+                Update,
+                handle_practice_pause_input
+                    .run_if(in_state(GameState::Paused))
+                    .run_if(quit_prompt_closed),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_practice_select_input.run_if(quit_prompt_closed),
+                    update_practice_selection.after(handle_practice_select_input),
+                )
+                    .run_if(in_state(GameState::PracticeSelect)),
+            )
+            .add_systems(Update, restart_practice);
+    }
+}
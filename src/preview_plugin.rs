@@ -0,0 +1,105 @@
+use bevy::{ecs::system::SystemParam, prelude::*, sprite::Anchor};
+
+use crate::{
+    pause_plugin::MovePreviewSetting,
+    play_plugin::{plan_move, LevelState, Player, MOVE_KEYS},
+    theme_plugin::SkinTextures,
+    GameState, LevelEntity, Position,
+};
+
+const PREVIEW_ALPHA: f32 = 0.45;
+
+pub struct PreviewPlugin;
+
+#[derive(Component)]
+struct MovePreview;
+
+#[derive(SystemParam)]
+struct PreviewInput<'w> {
+    keyboard_input: Res<'w, Input<KeyCode>>,
+    move_preview_setting: Res<'w, MovePreviewSetting>,
+}
+
+impl PreviewInput<'_> {
+    // The direction being previewed, if any.
+    fn step(&self) -> Option<(i32, i32)> {
+        if !is_previewing(&self.keyboard_input, &self.move_preview_setting) {
+            return None;
+        }
+        MOVE_KEYS
+            .iter()
+            .find(|(key, _)| self.keyboard_input.pressed(*key))
+            .map(|(_, step)| *step)
+    }
+}
+
+pub fn is_previewing(
+    keyboard_input: &Input<KeyCode>,
+    move_preview_setting: &MovePreviewSetting,
+) -> bool {
+    move_preview_setting.enabled
+        && keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+}
+
+fn spawn_ghost(commands: &mut Commands, texture: Handle<Image>, position: Position) {
+    let mut translation = position.to_translation();
+    translation.z = 3.0;
+    commands.spawn((
+        LevelEntity,
+        MovePreview,
+        SpriteBundle {
+            sprite: Sprite {
+                anchor: Anchor::TopLeft,
+                color: Color::rgba(1.0, 1.0, 1.0, PREVIEW_ALPHA),
+                ..default()
+            },
+            texture,
+            transform: Transform::from_translation(translation),
+            ..default()
+        },
+    ));
+}
+
+// Redrawn whenever the held direction or the board changes.
+fn show_move_preview(
+    mut commands: Commands,
+    skin_textures: SkinTextures,
+    preview_input: PreviewInput,
+    level_state: Res<LevelState>,
+    player_query: Query<&Player>,
+    preview_query: Query<Entity, With<MovePreview>>,
+    mut shown: Local<Option<(i32, i32)>>,
+) {
+    let is_moving = player_query
+        .get(level_state.active_player)
+        .is_ok_and(|player| player.is_moving);
+    let step = preview_input.step().filter(|_| !is_moving);
+    if step == *shown && !level_state.is_changed() {
+        return;
+    }
+    *shown = step;
+
+    for entity in &preview_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(planned) = step.and_then(|step| plan_move(&level_state, step)) else {
+        return;
+    };
+    spawn_ghost(
+        &mut commands,
+        skin_textures.load("player.png"),
+        planned.player_end(),
+    );
+    if let Some(push) = planned.push {
+        spawn_ghost(&mut commands, skin_textures.load("block.png"), push.end());
+    }
+}
+
+impl Plugin for PreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            show_move_preview.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
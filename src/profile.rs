@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::storage::{default_storage, StorageBackend};
+
+#[derive(Resource, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub storage: Arc<dyn StorageBackend>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: std::env::var("SOKOBAN_PROFILE").unwrap_or_else(|_| "default".to_string()),
+            storage: default_storage().into(),
+        }
+    }
+}
+
+impl Profile {
+    fn save_key(&self, file_name: &str) -> String {
+        format!("{}/{}", self.name, file_name)
+    }
+
+    pub fn load<T: DeserializeOwned + Default>(&self, file_name: &str) -> T {
+        let Some(contents) = self.storage.read(&self.save_key(file_name)) else {
+            return T::default();
+        };
+        ron::from_str(&contents).unwrap_or_else(|error| {
+            warn!("Could not read {}: {}", file_name, error);
+            T::default()
+        })
+    }
+
+    pub fn save<T: Serialize>(&self, file_name: &str, value: &T) {
+        let contents = match ron::ser::to_string_pretty(value, Default::default()) {
+            Ok(contents) => contents,
+            Err(error) => {
+                warn!("Could not serialize {}: {}", file_name, error);
+                return;
+            }
+        };
+        if let Err(error) = self.storage.write(&self.save_key(file_name), &contents) {
+            warn!("Could not write {}", error);
+        }
+    }
+}
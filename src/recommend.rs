@@ -0,0 +1,83 @@
+use bevy::utils::HashMap;
+
+use crate::{difficulty::Difficulty, stats_plugin::LevelResult};
+
+const STRUGGLING_UNDO_RATE: f32 = 0.3;
+const STRUGGLING_SECONDS: f32 = 120.0;
+const CRUISING_UNDO_RATE: f32 = 0.1;
+const CRUISING_SECONDS: f32 = 45.0;
+
+#[derive(Debug, PartialEq)]
+pub enum Pace {
+    Struggling,
+    Steady,
+    Cruising,
+}
+
+pub struct Suggestion {
+    pub level: i32,
+    pub reason: &'static str,
+}
+
+pub fn assess_pace(results: &[LevelResult]) -> Option<Pace> {
+    if results.is_empty() {
+        return None;
+    }
+    let moves: usize = results.iter().map(|result| result.moves).sum();
+    let undos: usize = results.iter().map(|result| result.undos).sum();
+    let undo_rate = undos as f32 / moves.max(1) as f32;
+    let average_seconds =
+        results.iter().map(|result| result.seconds).sum::<f32>() / results.len() as f32;
+
+    Some(
+        if undo_rate > STRUGGLING_UNDO_RATE || average_seconds > STRUGGLING_SECONDS {
+            Pace::Struggling
+        } else if undo_rate < CRUISING_UNDO_RATE && average_seconds < CRUISING_SECONDS {
+            Pace::Cruising
+        } else {
+            Pace::Steady
+        },
+    )
+}
+
+// Picks a level a step easier, a step harder or about as hard as the last one
+// played, preferring levels that haven't been played recently.
+pub fn suggest_level(
+    results: &[LevelResult],
+    difficulties: &HashMap<i32, Difficulty>,
+) -> Option<Suggestion> {
+    let pace = assess_pace(results)?;
+    let last_level = results.last()?.level;
+    let target = difficulties.get(&last_level)?.score();
+
+    let mut candidates: Vec<(i32, f32)> = difficulties
+        .iter()
+        .map(|(level, difficulty)| (*level, difficulty.score()))
+        .filter(|(level, score)| *level != last_level && *score < f32::MAX)
+        .collect();
+    if candidates
+        .iter()
+        .any(|(level, _)| !results.iter().any(|result| result.level == *level))
+    {
+        candidates.retain(|(level, _)| !results.iter().any(|result| result.level == *level));
+    }
+
+    let distance = |score: f32| (score - target).abs();
+    let (level, reason) = match pace {
+        Pace::Struggling => candidates
+            .iter()
+            .filter(|(_, score)| *score < target)
+            .min_by(|a, b| distance(a.1).total_cmp(&distance(b.1)))
+            .map(|(level, _)| (*level, "something a little gentler")),
+        Pace::Cruising => candidates
+            .iter()
+            .filter(|(_, score)| *score > target)
+            .min_by(|a, b| distance(a.1).total_cmp(&distance(b.1)))
+            .map(|(level, _)| (*level, "a tougher challenge")),
+        Pace::Steady => candidates
+            .iter()
+            .min_by(|a, b| distance(a.1).total_cmp(&distance(b.1)))
+            .map(|(level, _)| (*level, "more at your current pace")),
+    }?;
+    Some(Suggestion { level, reason })
+}
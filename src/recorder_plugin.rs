@@ -0,0 +1,107 @@
+// Records the board through each attempt and writes the solution out as a GIF once the level
+// is solved. Only built with `--features recorder`, since it writes a file for every level won.
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+
+use crate::{
+    clip_plugin::{encode_board_gif, ClipFrame},
+    play_plugin::{LevelCompletedEvent, LevelState},
+};
+
+const RECORDINGS_DIRECTORY: &str = "recordings";
+// Moves are played back evenly, without the pauses to think between them.
+const SECONDS_PER_MOVE: f32 = 0.15;
+
+pub struct RecorderPlugin;
+
+// The board after each move of the attempt, the first is the board it started from.
+#[derive(Resource, Default)]
+struct Recording {
+    layout: Vec<Vec<i32>>,
+    frames: Vec<ClipFrame>,
+}
+
+impl Recording {
+    fn record(&mut self, level_state: &LevelState) {
+        if self.layout != level_state.layout {
+            self.layout = level_state.layout.clone();
+            self.frames.clear();
+        }
+        // Undone moves aren't part of the solution.
+        let moves = level_state.moves.len();
+        self.frames.truncate(moves);
+        // A recording that missed moves, say from joining part way through, can't catch up.
+        if self.frames.len() == moves {
+            self.frames
+                .push(ClipFrame::of(level_state, moves as f32 * SECONDS_PER_MOVE));
+        }
+    }
+}
+
+fn record_attempt(level_state: Res<LevelState>, mut recording: ResMut<Recording>) {
+    if level_state.is_changed() {
+        recording.record(&level_state);
+    }
+}
+
+fn recording_path(level: i32) -> PathBuf {
+    (1..)
+        .map(|solution| {
+            PathBuf::from(RECORDINGS_DIRECTORY)
+                .join(format!("level-{}-solution-{}.gif", level, solution))
+        })
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+fn save_solution(
+    level_state: Res<LevelState>,
+    mut recording: ResMut<Recording>,
+    images: Res<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+    mut level_completed_reader: EventReader<LevelCompletedEvent>,
+) {
+    let Some(level_completed) = level_completed_reader.read().last() else {
+        return;
+    };
+    // The winning move may have landed after this frame's recording.
+    recording.record(&level_state);
+    if recording.frames.len() != level_completed.moves.len() + 1 {
+        warn!(
+            "Level {} wasn't recorded from the start, not saving its solution",
+            level_completed.level
+        );
+        return;
+    }
+
+    let gif = match encode_board_gif(
+        &recording.layout,
+        &recording.frames,
+        0.0,
+        &images,
+        &asset_server,
+    ) {
+        Ok(gif) => gif,
+        Err(error) => {
+            warn!("Could not encode solution: {}", error);
+            return;
+        }
+    };
+    let path = recording_path(level_completed.level);
+    if let Err(error) = fs::create_dir_all(RECORDINGS_DIRECTORY).and_then(|_| fs::write(&path, gif))
+    {
+        warn!("Could not write {}: {}", path.display(), error);
+        return;
+    }
+    info!("Saved solution to {}", path.display());
+}
+
+impl Plugin for RecorderPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Recording::default()).add_systems(
+            Update,
+            (record_attempt, save_solution.after(record_attempt)),
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::{collections::BTreeMap, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    campaign_plugin::{Campaign, CAMPAIGN_DIRECTORY},
+    level_hash,
+    play_plugin::{LevelCompletedEvent, LevelState},
+    practice_plugin::PracticeSession,
+    profile::Profile,
+    solver::{apply_moves, is_solver_level},
+};
+
+const BEST_REPLAYS_FILE: &str = "best_replays.ron";
+const SHARE_CODE_VERSION: &str = "SOK1";
+// Longer than any real solution, it stops a made-up count from expanding without end.
+const MAX_SHARED_MOVES: usize = 10_000;
+
+pub struct ReplayPlugin;
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct BestReplays(pub BTreeMap<u32, String>);
+
+#[derive(Debug)]
+pub struct VerifiedReplay {
+    pub level: i32,
+    pub moves: usize,
+    pub pushes: usize,
+}
+
+// Runs of the same move are written as a count followed by the move, e.g. "lllU" -> "3lU".
+fn compress_moves(moves: &str) -> String {
+    let mut compressed = String::new();
+    let mut chars = moves.chars().peekable();
+    while let Some(step) = chars.next() {
+        let mut count = 1;
+        while chars.peek() == Some(&step) {
+            chars.next();
+            count += 1;
+        }
+        if count > 1 {
+            compressed.push_str(&count.to_string());
+        }
+        compressed.push(step);
+    }
+    compressed
+}
+
+fn expand_moves(compressed: &str) -> Result<String, String> {
+    let mut moves = String::new();
+    let mut count = String::new();
+    for character in compressed.chars() {
+        if character.is_ascii_digit() {
+            count.push(character);
+            continue;
+        }
+        let repeat = if count.is_empty() {
+            1
+        } else {
+            count
+                .parse::<usize>()
+                .map_err(|_| format!("Bad move count {}", count))?
+        };
+        if repeat > MAX_SHARED_MOVES.saturating_sub(moves.len()) {
+            return Err(format!(
+                "Share code has more than {} moves",
+                MAX_SHARED_MOVES
+            ));
+        }
+        moves.extend(std::iter::repeat_n(character, repeat));
+        count.clear();
+    }
+    if !count.is_empty() {
+        return Err("Share code ends with a move count".to_string());
+    }
+    Ok(moves)
+}
+
+// Share codes are checked by replaying them with the plain push rules, so levels with any
+// other tiles can't be shared.
+pub fn share_code(layout: &[Vec<i32>], moves: &str) -> Option<String> {
+    if !is_solver_level(layout) {
+        return None;
+    }
+    let outcome = apply_moves(layout, moves).ok()?;
+    Some(format!(
+        "{}-{:08x}-{}-{}-{}",
+        SHARE_CODE_VERSION,
+        level_hash(layout),
+        outcome.moves,
+        outcome.pushes,
+        compress_moves(moves)
+    ))
+}
+
+pub fn verify_share_code(code: &str) -> Result<VerifiedReplay, String> {
+    let parts: Vec<&str> = code.trim().split('-').collect();
+    let [version, hash, moves, pushes, solution] = parts[..] else {
+        return Err("Share code should have five parts".to_string());
+    };
+    if version != SHARE_CODE_VERSION {
+        return Err(format!("Unsupported share code version {}", version));
+    }
+    let hash = u32::from_str_radix(hash, 16).map_err(|_| "Bad level hash".to_string())?;
+    let claimed_moves: usize = moves.parse().map_err(|_| "Bad move count".to_string())?;
+    let claimed_pushes: usize = pushes.parse().map_err(|_| "Bad push count".to_string())?;
+
+    let campaign = Campaign::load(Path::new(CAMPAIGN_DIRECTORY))?;
+    let Some((level, layout)) = (1..)
+        .zip(campaign.levels().map(|level| &level.layout))
+        .find(|(_, layout)| level_hash(layout) == hash)
+    else {
+        return Err("No installed level matches this share code".to_string());
+    };
+    if !is_solver_level(layout) {
+        return Err(format!(
+            "Level {} can't be checked from a share code",
+            level
+        ));
+    }
+
+    let outcome = apply_moves(layout, &expand_moves(solution)?)?;
+    if !outcome.solved {
+        return Err("The solution does not solve the level".to_string());
+    }
+    if outcome.moves != claimed_moves || outcome.pushes != claimed_pushes {
+        return Err(format!(
+            "Claimed {} moves and {} pushes but the replay took {} moves and {} pushes",
+            claimed_moves, claimed_pushes, outcome.moves, outcome.pushes
+        ));
+    }
+
+    Ok(VerifiedReplay {
+        level,
+        moves: outcome.moves,
+        pushes: outcome.pushes,
+    })
+}
+
+fn load_best_replays(mut commands: Commands, profile: Res<Profile>, campaign: Res<Campaign>) {
+    let mut best_replays = profile.load::<BestReplays>(BEST_REPLAYS_FILE);
+    if campaign.rekey_by_level_hash(&mut best_replays.0) {
+        profile.save(BEST_REPLAYS_FILE, &best_replays);
+    }
+    commands.insert_resource(best_replays);
+}
+
+fn record_best_replay(
+    profile: Res<Profile>,
+    level_state: Res<LevelState>,
+    practice_session: Res<PracticeSession>,
+    mut best_replays: ResMut<BestReplays>,
+    mut level_completed_reader: EventReader<LevelCompletedEvent>,
+) {
+    for level_completed in level_completed_reader.read() {
+        if practice_session.is_active() {
+            continue;
+        }
+        let hash = level_hash(&level_state.layout);
+        let is_best = match best_replays.0.get(&hash) {
+            Some(best) => level_completed.moves.len() < best.len(),
+            None => true,
+        };
+        if !is_best {
+            continue;
+        }
+
+        best_replays.0.insert(hash, level_completed.moves.clone());
+        profile.save(BEST_REPLAYS_FILE, &*best_replays);
+        if let Some(code) = share_code(&level_state.layout, &level_completed.moves) {
+            info!("New best for level {}: {}", level_completed.level, code);
+        }
+    }
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BestReplays::default())
+            .add_systems(Startup, load_best_replays)
+            .add_systems(Update, record_best_replay);
+    }
+}
@@ -0,0 +1,280 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+
+use crate::{
+    custom_levels_plugin::{CustomLevelFile, CUSTOM_LEVELS_DIRECTORY},
+    difficulty::DIFFICULTY_LABELS,
+    edit_plugin::EditingState,
+    pause_plugin::quit_prompt_closed,
+    GameState,
+};
+
+const FIELD_NAMES: [&str; 5] = ["Title", "Author", "Difficulty", "Par", "Hint"];
+// Picked from `DIFFICULTY_LABELS` rather than typed.
+const DIFFICULTY_FIELD: usize = 2;
+const PAR_FIELD: usize = 3;
+const MAX_FIELD_LENGTH: usize = 80;
+
+// F2 in the editor names the level and saves it with the custom levels.
+pub struct SaveDialogPlugin;
+
+#[derive(Resource, Default)]
+pub struct SaveDialog {
+    open: bool,
+    // Title, author, difficulty, par and hint, kept between saves so the author only has to be
+    // typed once.
+    fields: [String; 5],
+    field: usize,
+    error: Option<String>,
+}
+
+#[derive(Component)]
+struct SaveDialogPanel;
+
+#[derive(Component)]
+struct SaveDialogFields;
+
+#[derive(Component)]
+struct SaveDialogError;
+
+pub fn save_dialog_closed(save_dialog: Res<SaveDialog>) -> bool {
+    !save_dialog.open
+}
+
+// The file name a title is saved under, e.g. "Tight Corner!" -> "tight-corner".
+fn file_stem(title: &str) -> String {
+    title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn save_level(editing_state: &EditingState, fields: &[String; 5]) -> Result<String, String> {
+    let [title, author, difficulty, par, hint] = fields.each_ref().map(|field| field.trim());
+    if editing_state.is_empty() {
+        return Err("There's no level to save yet".to_string());
+    }
+    let stem = file_stem(title);
+    if stem.is_empty() {
+        return Err("The level needs a title".to_string());
+    }
+    let level_file = CustomLevelFile {
+        name: title.to_string(),
+        author: author.to_string(),
+        difficulty: difficulty.to_string(),
+        par: par.parse().ok(),
+        hint: hint.to_string(),
+        decorations: editing_state.serialize_decorations(),
+        layout: editing_state.serialize(),
+    };
+    let contents = ron::ser::to_string_pretty(&level_file, Default::default())
+        .map_err(|error| error.to_string())?;
+    fs::create_dir_all(CUSTOM_LEVELS_DIRECTORY).map_err(|error| error.to_string())?;
+    let path = Path::new(CUSTOM_LEVELS_DIRECTORY).join(format!("{}.ron", stem));
+    fs::write(&path, contents).map_err(|error| format!("{}: {}", path.display(), error))?;
+    Ok(path.display().to_string())
+}
+
+fn toggle_save_dialog(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut save_dialog: ResMut<SaveDialog>,
+    save_dialog_panel_query: Query<Entity, With<SaveDialogPanel>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F2) {
+        return;
+    }
+    save_dialog.open = !save_dialog.open;
+    save_dialog.error = None;
+    if !save_dialog.open {
+        for entity in save_dialog_panel_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    save_dialog.field = 0;
+
+    commands
+        .spawn((
+            SaveDialogPanel,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Save level",
+                TextStyle {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                SaveDialogFields,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn((
+                SaveDialogError,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::RED,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn(TextBundle::from_section(
+                "Tab: next field  Left/Right: difficulty  Enter: save  F2: cancel",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn handle_save_dialog_input(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut character_reader: EventReader<ReceivedCharacter>,
+    mut editing_state: ResMut<EditingState>,
+    mut save_dialog: ResMut<SaveDialog>,
+    save_dialog_panel_query: Query<Entity, With<SaveDialogPanel>>,
+) {
+    let typed: String = character_reader.read().map(|event| event.char).collect();
+    if !save_dialog.open {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        save_dialog.field = (save_dialog.field + 1) % FIELD_NAMES.len();
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        match save_level(&editing_state, &save_dialog.fields) {
+            Ok(path) => {
+                info!("Saved level to {}", path);
+                editing_state.mark_saved();
+                save_dialog.open = false;
+                save_dialog.fields[0].clear();
+                for entity in save_dialog_panel_query.iter() {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+            Err(error) => save_dialog.error = Some(error),
+        }
+        return;
+    }
+
+    let field = save_dialog.field;
+    if field == DIFFICULTY_FIELD {
+        let step = if keyboard_input.just_pressed(KeyCode::Right) {
+            1
+        } else if keyboard_input.just_pressed(KeyCode::Left) {
+            DIFFICULTY_LABELS.len()
+        } else {
+            return;
+        };
+        // Unrated comes before the easiest and after the hardest.
+        let current = DIFFICULTY_LABELS
+            .iter()
+            .position(|label| *label == save_dialog.fields[field])
+            .map_or(0, |index| index + 1);
+        save_dialog.fields[field] = match (current + step) % (DIFFICULTY_LABELS.len() + 1) {
+            0 => String::new(),
+            index => DIFFICULTY_LABELS[index - 1].to_string(),
+        };
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        save_dialog.fields[field].pop();
+    }
+    for character in typed.chars().filter(|c| !c.is_control()) {
+        if field == PAR_FIELD && !character.is_ascii_digit() {
+            continue;
+        }
+        if save_dialog.fields[field].chars().count() < MAX_FIELD_LENGTH {
+            save_dialog.fields[field].push(character);
+        }
+    }
+}
+
+fn update_save_dialog(
+    save_dialog: Res<SaveDialog>,
+    mut fields_query: Query<(Ref<SaveDialogFields>, &mut Text), Without<SaveDialogError>>,
+    mut error_query: Query<&mut Text, With<SaveDialogError>>,
+) {
+    // The panel is spawned a frame after the dialog opens.
+    let panel_added = fields_query.iter().any(|(fields, _)| fields.is_added());
+    if !save_dialog.is_changed() && !panel_added {
+        return;
+    }
+    let lines: Vec<String> = FIELD_NAMES
+        .iter()
+        .zip(&save_dialog.fields)
+        .enumerate()
+        .map(|(index, (name, value))| {
+            if index == save_dialog.field {
+                format!("> {}: {}_", name, value)
+            } else {
+                format!("  {}: {}", name, value)
+            }
+        })
+        .collect();
+    for (_, mut text) in &mut fields_query {
+        text.sections[0].value = lines.join("\n");
+    }
+    for mut text in &mut error_query {
+        text.sections[0].value = save_dialog.error.clone().unwrap_or_default();
+    }
+}
+
+fn close_save_dialog(
+    mut commands: Commands,
+    mut save_dialog: ResMut<SaveDialog>,
+    save_dialog_panel_query: Query<Entity, With<SaveDialogPanel>>,
+) {
+    save_dialog.open = false;
+    for entity in save_dialog_panel_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+impl Plugin for SaveDialogPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SaveDialog::default())
+            .add_systems(OnExit(GameState::Editing), close_save_dialog)
+            .add_systems(
+                Update,
+                (
+                    toggle_save_dialog,
+                    handle_save_dialog_input.after(toggle_save_dialog),
+                    update_save_dialog.after(handle_save_dialog_input),
+                )
+                    .run_if(in_state(GameState::Editing))
+                    .run_if(quit_prompt_closed),
+            );
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{prelude::*, render::view::screenshot::ScreenshotManager, window::PrimaryWindow};
+
+use crate::challenge_plugin::iso_date;
+
+const SCREENSHOTS_DIRECTORY: &str = "screenshots";
+const SCREENSHOT_KEY: KeyCode = KeyCode::F12;
+const TOAST_SECONDS: f32 = 2.0;
+
+pub struct ScreenshotPlugin;
+
+// Says where the last screenshot went, fading out over its last second.
+#[derive(Component)]
+struct ScreenshotToast(Timer);
+
+// Named for when it was taken, in UTC, so they sort in the order they were taken.
+fn screenshot_path() -> PathBuf {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let time_of_day = seconds % 86400;
+    let timestamp = format!(
+        "{}-{:02}-{:02}-{:02}",
+        iso_date((seconds / 86400) as i64),
+        time_of_day / 3600,
+        time_of_day / 60 % 60,
+        time_of_day % 60
+    );
+    (1..)
+        .map(|count| match count {
+            1 => format!("sokoban-{}.png", timestamp),
+            count => format!("sokoban-{}-{}.png", timestamp, count),
+        })
+        .map(|name| PathBuf::from(SCREENSHOTS_DIRECTORY).join(name))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+fn spawn_toast(commands: &mut Commands, message: String) {
+    commands.spawn((
+        ScreenshotToast(Timer::from_seconds(TOAST_SECONDS, TimerMode::Once)),
+        TextBundle::from_section(
+            message,
+            TextStyle {
+                font_size: 16.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.8)),
+        ZIndex::Global(30),
+    ));
+}
+
+// The frame is captured as it's drawn, so the toast waits a frame to stay out of the picture
+// and any earlier one is cleared straight away.
+fn take_screenshot(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    mut pending_toast: Local<Option<String>>,
+    window_query: Query<Entity, With<PrimaryWindow>>,
+    toast_query: Query<Entity, With<ScreenshotToast>>,
+) {
+    if let Some(message) = pending_toast.take() {
+        spawn_toast(&mut commands, message);
+    }
+    if !keyboard_input.just_pressed(SCREENSHOT_KEY) {
+        return;
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    for entity in toast_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    let path = screenshot_path();
+    let saved = fs::create_dir_all(SCREENSHOTS_DIRECTORY)
+        .map_err(|error| error.to_string())
+        .and_then(|_| {
+            screenshot_manager
+                .save_screenshot_to_disk(window, &path)
+                .map_err(|error| error.to_string())
+        });
+    *pending_toast = Some(match saved {
+        Ok(()) => {
+            info!("Saved screenshot to {}", path.display());
+            format!("Screenshot saved to {}", path.display())
+        }
+        Err(error) => {
+            warn!("Could not save screenshot to {}: {}", path.display(), error);
+            "Could not save screenshot".to_string()
+        }
+    });
+}
+
+fn fade_toast(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toast_query: Query<(
+        Entity,
+        &mut ScreenshotToast,
+        &mut Text,
+        &mut BackgroundColor,
+    )>,
+) {
+    for (entity, mut toast, mut text, mut background_color) in &mut toast_query {
+        toast.0.tick(time.delta());
+        if toast.0.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let alpha = toast.0.remaining_secs().min(1.0);
+        text.sections[0].style.color.set_a(alpha);
+        background_color.0.set_a(alpha * 0.8);
+    }
+}
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (take_screenshot, fade_toast));
+    }
+}
@@ -0,0 +1,321 @@
+use bevy::prelude::*;
+
+use crate::{
+    edit_plugin::EditingState,
+    level_code::{decode_level, encode_level},
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{LevelPack, NextLevelEvent},
+    save_dialog_plugin::save_dialog_closed,
+    GameState,
+};
+
+const SHARED_PACK: &str = "Shared level";
+const MAX_CODE_LENGTH: usize = 2048;
+
+// Levels passed around as text: Tab in the editor shows the level's code and copies it, I on the
+// level select takes one to type or paste in and play.
+pub struct SharePlugin;
+
+// The system clipboard, opened the first time it's needed and kept open after that, since on
+// X11 copied text is only there while the program that copied it is holding on to it.
+#[derive(Default)]
+struct SystemClipboard {
+    #[cfg(not(target_arch = "wasm32"))]
+    clipboard: Option<arboard::Clipboard>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SystemClipboard {
+    fn open(&mut self) -> Result<&mut arboard::Clipboard, String> {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(arboard::Clipboard::new().map_err(|error| error.to_string())?);
+        }
+        Ok(self.clipboard.as_mut().unwrap())
+    }
+
+    fn copy(&mut self, text: &str) -> Result<(), String> {
+        self.open()?
+            .set_text(text)
+            .map_err(|error| error.to_string())
+    }
+
+    fn paste(&mut self) -> Result<String, String> {
+        self.open()?.get_text().map_err(|error| error.to_string())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl SystemClipboard {
+    fn copy(&mut self, _text: &str) -> Result<(), String> {
+        Err("The clipboard isn't available here".to_string())
+    }
+
+    fn paste(&mut self) -> Result<String, String> {
+        Err("The clipboard isn't available here".to_string())
+    }
+}
+
+#[derive(Resource, Default)]
+struct ImportState {
+    buffer: String,
+    error: Option<String>,
+}
+
+impl ImportState {
+    // Only what can appear in a code, so stray spaces and newlines are dropped.
+    fn push_code(&mut self, text: &str) {
+        for character in text
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        {
+            if self.buffer.len() < MAX_CODE_LENGTH {
+                self.buffer.push(character);
+                self.error = None;
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct ExportPanel;
+
+#[derive(Component)]
+struct ImportMenu;
+
+#[derive(Component)]
+struct ImportCodeText;
+
+#[derive(Component)]
+struct ImportErrorText;
+
+fn toggle_export_panel(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    editing_state: Res<EditingState>,
+    mut clipboard: NonSendMut<SystemClipboard>,
+    export_panel_query: Query<Entity, With<ExportPanel>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Tab) {
+        return;
+    }
+    if let Ok(entity) = export_panel_query.get_single() {
+        commands.entity(entity).despawn();
+        return;
+    }
+    let text = if !editing_state.is_empty() {
+        let code = encode_level(&editing_state.serialize());
+        info!("Level code: {}", code);
+        match clipboard.copy(&code) {
+            Ok(()) => format!("Level code (copied to the clipboard): {}", code),
+            Err(error) => {
+                warn!("Could not copy the level code: {}", error);
+                format!("Level code (also in the log): {}", code)
+            }
+        }
+    } else {
+        "Nothing to share yet".to_string()
+    };
+    commands.spawn((
+        ExportPanel,
+        TextBundle::from_section(
+            text,
+            TextStyle {
+                font_size: 16.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            right: Val::Px(10.0),
+            padding: UiRect::all(Val::Px(4.0)),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.8)),
+    ));
+}
+
+fn hide_export_panel(mut commands: Commands, export_panel_query: Query<Entity, With<ExportPanel>>) {
+    for entity in export_panel_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn start_import(keyboard_input: Res<Input<KeyCode>>, mut game_state: ResMut<NextState<GameState>>) {
+    if keyboard_input.just_pressed(KeyCode::I) {
+        game_state.set(GameState::ImportCode);
+    }
+}
+
+fn show_import_screen(
+    mut commands: Commands,
+    mut received_characters: ResMut<Events<ReceivedCharacter>>,
+) {
+    // The I that opened the screen isn't part of the code.
+    received_characters.clear();
+    commands.insert_resource(ImportState::default());
+
+    commands
+        .spawn((
+            ImportMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Import a level",
+                TextStyle {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                ImportCodeText,
+                TextBundle::from_section(
+                    "_",
+                    TextStyle {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    width: Val::Percent(90.0),
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..default()
+                })
+                .with_background_color(Color::rgb(0.15, 0.15, 0.15)),
+            ));
+            parent.spawn((
+                ImportErrorText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::RED,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn(TextBundle::from_section(
+                "Type a level code or paste one with Ctrl+V  Enter: play  Tab: levels",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn hide_import_screen(mut commands: Commands, import_menu_query: Query<Entity, With<ImportMenu>>) {
+    for entity in import_menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_import_input(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut character_reader: EventReader<ReceivedCharacter>,
+    mut clipboard: NonSendMut<SystemClipboard>,
+    mut import_state: ResMut<ImportState>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let typed: String = character_reader.read().map(|event| event.char).collect();
+
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        game_state.set(GameState::LevelSelect);
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        match decode_level(&import_state.buffer) {
+            Ok(layout) => {
+                commands.insert_resource(LevelPack {
+                    name: SHARED_PACK.to_string(),
+                    levels: vec![layout],
+                    metadata: Vec::new(),
+                    return_state: GameState::LevelSelect,
+                });
+                next_level_writer.send(NextLevelEvent(1));
+                game_state.set(GameState::Playing);
+            }
+            Err(error) => import_state.error = Some(error),
+        }
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        import_state.buffer.pop();
+        import_state.error = None;
+    }
+    let pasting = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+        && keyboard_input.just_pressed(KeyCode::V);
+    if pasting {
+        match clipboard.paste() {
+            Ok(pasted) => import_state.push_code(&pasted),
+            Err(error) => import_state.error = Some(format!("Could not paste: {}", error)),
+        }
+    } else {
+        import_state.push_code(&typed);
+    }
+}
+
+fn update_import_screen(
+    import_state: Res<ImportState>,
+    mut code_text_query: Query<&mut Text, (With<ImportCodeText>, Without<ImportErrorText>)>,
+    mut error_text_query: Query<&mut Text, With<ImportErrorText>>,
+) {
+    if !import_state.is_changed() {
+        return;
+    }
+    for mut text in &mut code_text_query {
+        text.sections[0].value = format!("{}_", import_state.buffer);
+    }
+    for mut text in &mut error_text_query {
+        text.sections[0].value = import_state.error.clone().unwrap_or_default();
+    }
+}
+
+impl Plugin for SharePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ImportState::default())
+            .insert_non_send_resource(SystemClipboard::default())
+            .add_systems(OnExit(GameState::Editing), hide_export_panel)
+            .add_systems(OnEnter(GameState::ImportCode), show_import_screen)
+            .add_systems(OnExit(GameState::ImportCode), hide_import_screen)
+            .add_systems(
+                Update,
+                toggle_export_panel
+                    .run_if(in_state(GameState::Editing))
+                    .run_if(quit_prompt_closed)
+                    .run_if(save_dialog_closed),
+            )
+            .add_systems(
+                Update,
+                start_import
+                    .run_if(in_state(GameState::LevelSelect))
+                    .run_if(quit_prompt_closed),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_import_input,
+                    update_import_screen.after(handle_import_input),
+                )
+                    .run_if(in_state(GameState::ImportCode))
+                    .run_if(quit_prompt_closed),
+            );
+    }
+}
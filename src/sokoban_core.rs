@@ -0,0 +1,453 @@
+// The grid and the basic push rules, kept free of Bevy so they can be tested on their own
+// and shared with the solver.
+use std::collections::HashSet;
+
+pub const DIRECTIONS: [(i32, i32, char); 4] =
+    [(0, -1, 'u'), (0, 1, 'd'), (-1, 0, 'l'), (1, 0, 'r')];
+
+#[derive(Copy, Clone, Eq, Hash, PartialEq, Debug, Default)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Position {
+    pub fn add(&self, x: i32, y: i32) -> Position {
+        Position {
+            x: self.x + x,
+            y: self.y + y,
+        }
+    }
+}
+
+// The direction of a LURD step, either case.
+pub fn step_direction(step: char) -> Option<(i32, i32)> {
+    DIRECTIONS
+        .into_iter()
+        .find(|(_, _, direction)| *direction == step.to_ascii_lowercase())
+        .map(|(x, y, _)| (x, y))
+}
+
+// Everything reachable from `starts` through tiles that are `passable`.
+pub fn flood_fill(
+    starts: impl IntoIterator<Item = Position>,
+    passable: impl Fn(Position) -> bool,
+) -> HashSet<Position> {
+    let mut visited = HashSet::new();
+    let mut to_visit: Vec<Position> = starts.into_iter().collect();
+    while let Some(position) = to_visit.pop() {
+        if !visited.insert(position) {
+            continue;
+        }
+        for (x, y, _) in DIRECTIONS {
+            let next = position.add(x, y);
+            if passable(next) && !visited.contains(&next) {
+                to_visit.push(next);
+            }
+        }
+    }
+    visited
+}
+
+// A single cell of a level, decoded from the numbers levels are stored as.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tile {
+    Empty,
+    Player,
+    // Blocks and goals with their color, 0 for plain ones.
+    Block(u8),
+    HeavyBlock,
+    Goal(u8),
+    Wall,
+    Switch,
+    Door,
+    // Two teleporters share each pair number.
+    Teleporter(i32),
+    Ice,
+    // The arrow's index in `ONE_WAY_DIRECTIONS`.
+    OneWay(usize),
+    // How much energy the pickup gives.
+    Pickup(i32),
+    Pit,
+    Mirror,
+    Exit,
+    // Patrols back and forth, setting off along `ONE_WAY_DIRECTIONS` at this index.
+    Enemy(usize),
+    // Keys open the locked door of the same color, used up in the opening.
+    Key(u8),
+    LockedDoor(u8),
+    // Floor that gives way into a pit once the player steps off it.
+    Fragile,
+    // Two portals share each pair number, like teleporters.
+    Portal(i32),
+}
+
+impl Tile {
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            1 => Tile::Player,
+            // Colored blocks are 4096 plus their color, heavy blocks are 16384.
+            2 => Tile::Block(0),
+            4097..=4111 => Tile::Block((code - 4096) as u8),
+            16384 => Tile::HeavyBlock,
+            // Colored goals are 4112 plus their color.
+            4 => Tile::Goal(0),
+            4113..=4127 => Tile::Goal((code - 4112) as u8),
+            8 => Tile::Wall,
+            16 => Tile::Switch,
+            32 => Tile::Door,
+            // Teleporters are 64 plus their pair number.
+            64..=127 => Tile::Teleporter(code - 64),
+            128 => Tile::Ice,
+            // One-way tiles are 256 plus their direction, clockwise from up.
+            256..=259 => Tile::OneWay((code - 256) as usize),
+            // Energy pickups are 512 plus the energy they give.
+            512..=1023 => Tile::Pickup(code - 512),
+            1024 => Tile::Pit,
+            2048 => Tile::Mirror,
+            8192 => Tile::Exit,
+            // Enemies are 32768 plus the direction they set off in, clockwise from up.
+            32768..=32771 => Tile::Enemy((code - 32768) as usize),
+            // Keys are 65536 plus their color, locked doors 65552 plus theirs.
+            65536..=65551 => Tile::Key((code - 65536) as u8),
+            65552..=65567 => Tile::LockedDoor((code - 65552) as u8),
+            131072 => Tile::Fragile,
+            // Portals are 262144 plus their pair number.
+            262144..=262207 => Tile::Portal(code - 262144),
+            _ => Tile::Empty,
+        }
+    }
+
+    pub fn code(self) -> i32 {
+        match self {
+            Tile::Empty => 0,
+            Tile::Player => 1,
+            Tile::Block(0) => 2,
+            Tile::Block(color) => 4096 + color as i32,
+            Tile::HeavyBlock => 16384,
+            Tile::Goal(0) => 4,
+            Tile::Goal(color) => 4112 + color as i32,
+            Tile::Wall => 8,
+            Tile::Switch => 16,
+            Tile::Door => 32,
+            Tile::Teleporter(pair) => 64 + pair,
+            Tile::Ice => 128,
+            Tile::OneWay(direction) => 256 + direction as i32,
+            Tile::Pickup(energy) => 512 + energy,
+            Tile::Pit => 1024,
+            Tile::Mirror => 2048,
+            Tile::Exit => 8192,
+            Tile::Enemy(direction) => 32768 + direction as i32,
+            Tile::Key(color) => 65536 + color as i32,
+            Tile::LockedDoor(color) => 65552 + color as i32,
+            Tile::Fragile => 131072,
+            Tile::Portal(pair) => 262144 + pair,
+        }
+    }
+}
+
+// Standard Sokoban formats can put the player or a block on a goal. Those cells are stored as
+// the two codes added together, 5 and 6, and hold both tiles with the goal first.
+pub fn cell_from_code(code: i32) -> Vec<Tile> {
+    match code {
+        5 => vec![Tile::Goal(0), Tile::Player],
+        6 => vec![Tile::Goal(0), Tile::Block(0)],
+        code => vec![Tile::from_code(code)],
+    }
+}
+
+pub fn cell_code(cell: &[Tile]) -> i32 {
+    cell.iter().map(|tile| tile.code()).sum()
+}
+
+pub fn is_known_code(code: i32) -> bool {
+    cell_code(&cell_from_code(code)) == code
+}
+
+// The parts of a level that never move.
+pub struct Board {
+    pub walls: HashSet<Position>,
+    pub goals: Vec<Position>,
+}
+
+impl Board {
+    // Only plain levels are understood: a player, blocks, goals and walls. Anything outside
+    // the layout counts as wall, any other tile is an error.
+    pub fn parse(level_layout: &[Vec<i32>]) -> Result<(Board, Position, Vec<Position>), String> {
+        let mut walls = HashSet::new();
+        let mut goals = Vec::new();
+        let mut blocks = Vec::new();
+        let mut player_position = None;
+
+        let height = level_layout.len() as i32;
+        let width = level_layout.iter().map(|row| row.len()).max().unwrap_or(0) as i32;
+
+        for y in -1..=height {
+            for x in -1..=width {
+                let position = Position { x, y };
+                let code = level_layout
+                    .get(y as usize)
+                    .and_then(|row| row.get(x as usize))
+                    .copied()
+                    .unwrap_or(Tile::Wall.code());
+                if !is_known_code(code) {
+                    return Err(format!("Level has unknown tile {}", code));
+                }
+                for tile in cell_from_code(code) {
+                    match tile {
+                        Tile::Empty => {}
+                        Tile::Player if player_position.is_some() => {
+                            return Err("Level has more than one player".to_string());
+                        }
+                        Tile::Player => player_position = Some(position),
+                        Tile::Block(0) => blocks.push(position),
+                        Tile::Goal(0) => goals.push(position),
+                        Tile::Wall => {
+                            walls.insert(position);
+                        }
+                        tile => {
+                            return Err(format!(
+                                "Level has a {:?} tile, only plain Sokoban tiles are understood",
+                                tile
+                            ))
+                        }
+                    }
+                }
+            }
+        }
+
+        blocks.sort_by_key(|p| (p.y, p.x));
+        let player_position = player_position.ok_or("Level has no player")?;
+        Ok((Board { walls, goals }, player_position, blocks))
+    }
+
+    pub fn is_wall(&self, position: &Position) -> bool {
+        self.walls.contains(position)
+    }
+
+    // A block pushed into a corner that isn't a goal can never be moved again.
+    pub fn is_dead_corner(&self, position: &Position) -> bool {
+        if self.goals.contains(position) {
+            return false;
+        }
+        let vertical = self.is_wall(&position.add(0, -1)) || self.is_wall(&position.add(0, 1));
+        let horizontal = self.is_wall(&position.add(-1, 0)) || self.is_wall(&position.add(1, 0));
+        vertical && horizontal
+    }
+
+    pub fn is_solved(&self, blocks: &[Position]) -> bool {
+        self.goals.iter().all(|goal| blocks.contains(goal))
+    }
+
+    // The step the player would take, or `None` if a wall or a block that can't move is in
+    // the way.
+    pub fn try_move(
+        &self,
+        player: Position,
+        blocks: &[Position],
+        (move_x, move_y): (i32, i32),
+    ) -> Option<Step> {
+        let move_to = player.add(move_x, move_y);
+        if self.is_wall(&move_to) {
+            return None;
+        }
+        let pushed = match blocks.iter().position(|block| *block == move_to) {
+            Some(block_index) => {
+                let block_move_to = move_to.add(move_x, move_y);
+                if self.is_wall(&block_move_to) || blocks.contains(&block_move_to) {
+                    return None;
+                }
+                Some((block_index, move_to, block_move_to))
+            }
+            None => None,
+        };
+        Some(Step {
+            direction: (move_x, move_y),
+            player_from: player,
+            player_to: move_to,
+            pushed,
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Step {
+    pub direction: (i32, i32),
+    pub player_from: Position,
+    pub player_to: Position,
+    // The index of the pushed block, where it was and where it went.
+    pub pushed: Option<(usize, Position, Position)>,
+}
+
+impl Step {
+    pub fn apply(&self, player: &mut Position, blocks: &mut [Position]) {
+        *player = self.player_to;
+        if let Some((block_index, _, to)) = self.pushed {
+            blocks[block_index] = to;
+        }
+    }
+
+    pub fn undo(&self, player: &mut Position, blocks: &mut [Position]) {
+        *player = self.player_from;
+        if let Some((block_index, from, _)) = self.pushed {
+            blocks[block_index] = from;
+        }
+    }
+
+    // LURD notation, uppercase when a block was pushed.
+    pub fn lurd(&self) -> char {
+        let (_, _, direction) = DIRECTIONS
+            .into_iter()
+            .find(|(x, y, _)| (*x, *y) == self.direction)
+            .unwrap();
+        if self.pushed.is_some() {
+            direction.to_ascii_uppercase()
+        } else {
+            direction
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UP: (i32, i32) = (0, -1);
+    const DOWN: (i32, i32) = (0, 1);
+    const LEFT: (i32, i32) = (-1, 0);
+    const RIGHT: (i32, i32) = (1, 0);
+
+    fn board(layout: &[&[i32]]) -> (Board, Position, Vec<Position>) {
+        let layout: Vec<Vec<i32>> = layout.iter().map(|row| row.to_vec()).collect();
+        Board::parse(&layout).unwrap()
+    }
+
+    #[test]
+    fn walks_onto_empty_tiles() {
+        let (board, player, blocks) = board(&[&[8, 8, 8, 8], &[8, 1, 0, 8], &[8, 8, 8, 8]]);
+
+        let step = board.try_move(player, &blocks, RIGHT).unwrap();
+
+        assert_eq!(step.player_to, Position { x: 2, y: 1 });
+        assert_eq!(step.pushed, None);
+        assert_eq!(step.lurd(), 'r');
+    }
+
+    #[test]
+    fn walls_block_the_player() {
+        let (board, player, blocks) = board(&[&[8, 8, 8, 8], &[8, 1, 0, 8], &[8, 8, 8, 8]]);
+
+        assert!(board.try_move(player, &blocks, UP).is_none());
+        assert!(board.try_move(player, &blocks, LEFT).is_none());
+        assert!(board.try_move(player, &blocks, DOWN).is_none());
+    }
+
+    #[test]
+    fn pushes_a_block_into_empty_space() {
+        let (board, player, blocks) =
+            board(&[&[8, 8, 8, 8, 8], &[8, 1, 2, 0, 8], &[8, 8, 8, 8, 8]]);
+
+        let step = board.try_move(player, &blocks, RIGHT).unwrap();
+
+        assert_eq!(
+            step.pushed,
+            Some((0, Position { x: 2, y: 1 }, Position { x: 3, y: 1 }))
+        );
+        assert_eq!(step.lurd(), 'R');
+    }
+
+    #[test]
+    fn cannot_push_a_block_into_a_wall() {
+        let (board, player, blocks) = board(&[&[8, 8, 8, 8], &[8, 1, 2, 8], &[8, 8, 8, 8]]);
+
+        assert!(board.try_move(player, &blocks, RIGHT).is_none());
+    }
+
+    #[test]
+    fn cannot_push_two_blocks_at_once() {
+        let (board, player, blocks) = board(&[
+            &[8, 8, 8, 8, 8, 8],
+            &[8, 1, 2, 2, 0, 8],
+            &[8, 8, 8, 8, 8, 8],
+        ]);
+
+        assert!(board.try_move(player, &blocks, RIGHT).is_none());
+    }
+
+    #[test]
+    fn undo_puts_everything_back() {
+        let (board, mut player, mut blocks) =
+            board(&[&[8, 8, 8, 8, 8], &[8, 1, 2, 0, 8], &[8, 8, 8, 8, 8]]);
+        let (start_player, start_blocks) = (player, blocks.clone());
+
+        let step = board.try_move(player, &blocks, RIGHT).unwrap();
+        step.apply(&mut player, &mut blocks);
+        assert_eq!(player, Position { x: 2, y: 1 });
+        assert_eq!(blocks, vec![Position { x: 3, y: 1 }]);
+
+        step.undo(&mut player, &mut blocks);
+        assert_eq!(player, start_player);
+        assert_eq!(blocks, start_blocks);
+    }
+
+    #[test]
+    fn solved_once_every_goal_is_covered() {
+        let (board, mut player, mut blocks) = board(&[
+            &[8, 8, 8, 8, 8, 8],
+            &[8, 1, 2, 0, 4, 8],
+            &[8, 8, 8, 8, 8, 8],
+        ]);
+        assert!(!board.is_solved(&blocks));
+
+        for _ in 0..2 {
+            let step = board.try_move(player, &blocks, RIGHT).unwrap();
+            step.apply(&mut player, &mut blocks);
+        }
+
+        assert!(board.is_solved(&blocks));
+    }
+
+    #[test]
+    fn reads_the_player_and_blocks_on_goals() {
+        let (board, player, mut blocks) =
+            board(&[&[8, 8, 8, 8, 8], &[8, 5, 2, 6, 8], &[8, 8, 8, 8, 8]]);
+
+        assert_eq!(player, Position { x: 1, y: 1 });
+        assert_eq!(
+            blocks,
+            vec![Position { x: 2, y: 1 }, Position { x: 3, y: 1 }]
+        );
+        assert!(!board.is_solved(&blocks));
+
+        blocks[0] = player;
+        assert!(board.is_solved(&blocks));
+    }
+
+    // 64 is a teleporter and 3 isn't a tile at all.
+    #[test]
+    fn rejects_tiles_it_does_not_understand() {
+        for tile in [64, 3] {
+            let layout = vec![vec![8, 8, 8, 8], vec![8, 1, tile, 8], vec![8, 8, 8, 8]];
+            assert!(Board::parse(&layout).is_err());
+        }
+    }
+
+    #[test]
+    fn flood_fill_stays_inside_walls() {
+        let (board, player, _) = board(&[&[8, 8, 8, 8, 8], &[8, 1, 0, 8, 0], &[8, 8, 8, 8, 8]]);
+
+        let reached = flood_fill([player], |position| !board.is_wall(&position));
+
+        assert_eq!(
+            reached,
+            HashSet::from([Position { x: 1, y: 1 }, Position { x: 2, y: 1 }])
+        );
+    }
+
+    #[test]
+    fn reads_lurd_steps() {
+        assert_eq!(step_direction('u'), Some(UP));
+        assert_eq!(step_direction('R'), Some(RIGHT));
+        assert_eq!(step_direction('x'), None);
+    }
+}
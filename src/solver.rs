@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+
+use bevy::utils::HashSet;
+
+use crate::{
+    sokoban_core::{cell_from_code, step_direction, Board, Tile, DIRECTIONS},
+    Position,
+};
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct SolveStats {
+    pub explored: usize,
+    pub branches: usize,
+}
+
+impl SolveStats {
+    pub fn branching_factor(&self) -> f32 {
+        if self.explored == 0 {
+            return 0.0;
+        }
+        self.branches as f32 / self.explored as f32
+    }
+}
+
+// Whether a level is made only of what the solver understands: a single player, plain
+// blocks and goals, and walls.
+pub fn is_solver_level(level_layout: &[Vec<i32>]) -> bool {
+    let cells: Vec<Tile> = level_layout
+        .iter()
+        .flatten()
+        .flat_map(|tile| cell_from_code(*tile))
+        .collect();
+    cells.iter().filter(|tile| **tile == Tile::Player).count() == 1
+        && cells.iter().all(|tile| {
+            matches!(
+                tile,
+                Tile::Empty | Tile::Player | Tile::Block(0) | Tile::Goal(0) | Tile::Wall
+            )
+        })
+}
+
+// Breadth-first search over player moves, returning the shortest solution in
+// LURD notation (lowercase for walking, uppercase for pushing a block).
+pub fn solve(level_layout: &[Vec<i32>], max_states: usize) -> (Option<String>, SolveStats) {
+    solve_after(level_layout, "", max_states)
+}
+
+// Like `solve`, but from wherever `moves` leave the player and blocks.
+pub fn solve_after(
+    level_layout: &[Vec<i32>],
+    moves: &str,
+    max_states: usize,
+) -> (Option<String>, SolveStats) {
+    let mut stats = SolveStats::default();
+    let Ok((board, mut player_position, mut blocks)) = Board::parse(level_layout) else {
+        return (None, stats);
+    };
+    for step in moves.chars() {
+        let Some(planned) = step_direction(step)
+            .and_then(|direction| board.try_move(player_position, &blocks, direction))
+        else {
+            return (None, stats);
+        };
+        planned.apply(&mut player_position, &mut blocks);
+    }
+    blocks.sort_by_key(|p| (p.y, p.x));
+    if board.goals.is_empty() {
+        return (None, stats);
+    }
+
+    // Each node stores its parent index and the move that led to it.
+    let mut nodes: Vec<(Position, Vec<Position>, usize, char)> =
+        vec![(player_position, blocks.clone(), 0, ' ')];
+    let mut seen = HashSet::default();
+    seen.insert((player_position, blocks));
+    let mut to_visit = VecDeque::from([0]);
+    // Spare blocks can be parked in corners, so only prune when every block is needed.
+    let prune_dead_corners = nodes[0].1.len() <= board.goals.len();
+
+    while let Some(node_index) = to_visit.pop_front() {
+        let (mut player_position, mut blocks) = (nodes[node_index].0, nodes[node_index].1.clone());
+        if board.is_solved(&blocks) {
+            let mut solution = Vec::new();
+            let mut current = node_index;
+            while current != 0 {
+                solution.push(nodes[current].3);
+                current = nodes[current].2;
+            }
+            solution.reverse();
+            return (Some(solution.into_iter().collect()), stats);
+        }
+
+        stats.explored += 1;
+        if stats.explored > max_states {
+            return (None, stats);
+        }
+
+        for (move_x, move_y, _) in DIRECTIONS {
+            let Some(step) = board.try_move(player_position, &blocks, (move_x, move_y)) else {
+                continue;
+            };
+            if step.pushed.is_some_and(|(_, _, block_move_to)| {
+                prune_dead_corners && board.is_dead_corner(&block_move_to)
+            }) {
+                continue;
+            }
+
+            // Step out to look at the next state, then back again for the next direction.
+            step.apply(&mut player_position, &mut blocks);
+            let next_position = player_position;
+            let mut next_blocks = blocks.clone();
+            next_blocks.sort_by_key(|p| (p.y, p.x));
+            step.undo(&mut player_position, &mut blocks);
+
+            stats.branches += 1;
+            if seen.insert((next_position, next_blocks.clone())) {
+                nodes.push((next_position, next_blocks, node_index, step.lurd()));
+                to_visit.push_back(nodes.len() - 1);
+            }
+        }
+    }
+
+    (None, stats)
+}
+
+pub struct ReplayOutcome {
+    pub moves: usize,
+    pub pushes: usize,
+    pub solved: bool,
+}
+
+// Plays a LURD move string against a level, failing on the first illegal move.
+pub fn apply_moves(level_layout: &[Vec<i32>], moves: &str) -> Result<ReplayOutcome, String> {
+    let (board, mut player_position, mut blocks) = Board::parse(level_layout)?;
+
+    let mut pushes = 0;
+    for (index, step) in moves.chars().enumerate() {
+        let Some(direction) = step_direction(step) else {
+            return Err(format!("Move {} is not one of LURD", index + 1));
+        };
+
+        let move_to = player_position.add(direction.0, direction.1);
+        if board.is_wall(&move_to) {
+            return Err(format!("Move {} walks into a wall", index + 1));
+        }
+        if blocks.contains(&move_to) != step.is_ascii_uppercase() {
+            return Err(format!("Move {} does not match a push", index + 1));
+        }
+        let Some(planned) = board.try_move(player_position, &blocks, direction) else {
+            return Err(format!("Move {} pushes a block that can't move", index + 1));
+        };
+        if planned.pushed.is_some() {
+            pushes += 1;
+        }
+        planned.apply(&mut player_position, &mut blocks);
+    }
+
+    Ok(ReplayOutcome {
+        moves: moves.chars().count(),
+        pushes,
+        solved: board.is_solved(&blocks),
+    })
+}
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    play_plugin::{LevelCompletedEvent, LevelLoadedEvent, LevelPack, CAMPAIGN_PACK},
+    profile::Profile,
+};
+
+const SPLITS_FILE: &str = "splits.ron";
+
+// Times a run through the whole campaign from loading level 1, splitting as each level
+// is solved.
+pub struct SpeedrunPlugin;
+
+// The split timer is off unless turned on from the pause menu.
+#[derive(Resource, Default)]
+pub struct SpeedrunSetting {
+    pub enabled: bool,
+}
+
+// The splits of the fastest finished run, seconds since the start at the end of each level.
+#[derive(Resource, Default, Serialize, Deserialize)]
+struct BestSplits(Vec<f32>);
+
+#[derive(Resource, Default)]
+struct SpeedrunRun {
+    running: bool,
+    elapsed: f32,
+    splits: Vec<f32>,
+}
+
+#[derive(Component)]
+struct SpeedrunText;
+
+pub fn speedrun_text(speedrun_setting: &SpeedrunSetting) -> String {
+    let state = if speedrun_setting.enabled {
+        "on"
+    } else {
+        "off"
+    };
+    format!("S: speedrun splits ({})", state)
+}
+
+fn format_time(seconds: f32) -> String {
+    format!("{}:{:04.1}", (seconds / 60.0) as u32, seconds % 60.0)
+}
+
+fn format_delta(seconds: f32, best: Option<&f32>) -> String {
+    match best {
+        Some(best) => {
+            let delta = seconds - best;
+            let sign = if delta < 0.0 { '-' } else { '+' };
+            format!("  {}{:.1}", sign, delta.abs())
+        }
+        None => String::new(),
+    }
+}
+
+fn load_best_splits(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(profile.load::<BestSplits>(SPLITS_FILE));
+}
+
+fn spawn_speedrun_text(mut commands: Commands) {
+    commands.spawn((
+        SpeedrunText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            left: Val::Px(10.0),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+        ZIndex::Global(20),
+    ));
+}
+
+// A run starts whenever level 1 of the campaign is loaded, carries on through retries and
+// the next level, and is dropped by anything else.
+fn follow_run(
+    speedrun_setting: Res<SpeedrunSetting>,
+    level_pack: Res<LevelPack>,
+    mut level_loaded_reader: EventReader<LevelLoadedEvent>,
+    mut speedrun_run: ResMut<SpeedrunRun>,
+) {
+    let Some(level_loaded) = level_loaded_reader.read().last() else {
+        return;
+    };
+    let expected = speedrun_run.splits.len() as i32 + 1;
+    let campaign = speedrun_setting.enabled && level_pack.name == CAMPAIGN_PACK;
+    if campaign && speedrun_run.running && level_loaded.level == expected {
+        return;
+    }
+    *speedrun_run = SpeedrunRun {
+        running: campaign && level_loaded.level == 1,
+        ..default()
+    };
+}
+
+fn tick_run(
+    time: Res<Time<Real>>,
+    speedrun_setting: Res<SpeedrunSetting>,
+    mut speedrun_run: ResMut<SpeedrunRun>,
+) {
+    if speedrun_setting.enabled && speedrun_run.running {
+        speedrun_run.elapsed += time.delta_seconds();
+    }
+}
+
+fn record_split(
+    profile: Res<Profile>,
+    level_pack: Res<LevelPack>,
+    mut best_splits: ResMut<BestSplits>,
+    mut speedrun_run: ResMut<SpeedrunRun>,
+    mut level_completed_reader: EventReader<LevelCompletedEvent>,
+) {
+    for level_completed in level_completed_reader.read() {
+        let expected = speedrun_run.splits.len() as i32 + 1;
+        if !speedrun_run.running
+            || level_pack.name != CAMPAIGN_PACK
+            || level_completed.level != expected
+        {
+            continue;
+        }
+        let elapsed = speedrun_run.elapsed;
+        speedrun_run.splits.push(elapsed);
+        let level_count = level_pack.levels.len();
+        if speedrun_run.splits.len() < level_count {
+            continue;
+        }
+        speedrun_run.running = false;
+        let best_total = best_splits.0.get(level_count - 1);
+        if best_total.is_none_or(|best_total| elapsed < *best_total) {
+            best_splits.0 = speedrun_run.splits.clone();
+            profile.save(SPLITS_FILE, &*best_splits);
+        }
+    }
+}
+
+fn update_speedrun_text(
+    speedrun_setting: Res<SpeedrunSetting>,
+    best_splits: Res<BestSplits>,
+    speedrun_run: Res<SpeedrunRun>,
+    mut speedrun_text_query: Query<(&mut Text, &mut Visibility), With<SpeedrunText>>,
+) {
+    let shown =
+        speedrun_setting.enabled && (speedrun_run.running || !speedrun_run.splits.is_empty());
+    let mut lines: Vec<String> = speedrun_run
+        .splits
+        .iter()
+        .enumerate()
+        .map(|(index, split)| {
+            format!(
+                "Level {}  {}{}",
+                index + 1,
+                format_time(*split),
+                format_delta(*split, best_splits.0.get(index))
+            )
+        })
+        .collect();
+    if speedrun_run.running {
+        let index = speedrun_run.splits.len();
+        lines.push(format!(
+            "Level {}  {}{}",
+            index + 1,
+            format_time(speedrun_run.elapsed),
+            format_delta(speedrun_run.elapsed, best_splits.0.get(index))
+        ));
+    }
+    if let Some(best_total) = best_splits.0.last() {
+        lines.push(format!("Best  {}", format_time(*best_total)));
+    }
+
+    for (mut text, mut visibility) in &mut speedrun_text_query {
+        *visibility = if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+impl Plugin for SpeedrunPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SpeedrunSetting::default())
+            .insert_resource(BestSplits::default())
+            .insert_resource(SpeedrunRun::default())
+            .add_systems(Startup, (load_best_splits, spawn_speedrun_text))
+            .add_systems(
+                Update,
+                (
+                    follow_run,
+                    tick_run.after(follow_run),
+                    record_split.after(tick_run),
+                    update_speedrun_text.after(record_split),
+                ),
+            );
+    }
+}
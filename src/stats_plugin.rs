@@ -0,0 +1,309 @@
+use std::collections::BTreeMap;
+
+use bevy::{app::AppExit, ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{
+        HistoryEvent, LevelCompletedEvent, LevelLoadedEvent, LevelPack, MoveResolved, CAMPAIGN_PACK,
+    },
+    profile::Profile,
+    replay_plugin::BestReplays,
+    GameState,
+};
+
+const STATS_FILE: &str = "stats.ron";
+const RECENT_RESULTS: usize = 10;
+
+pub struct StatsPlugin;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LevelResult {
+    pub level: i32,
+    pub seconds: f32,
+    pub moves: usize,
+    pub undos: usize,
+}
+
+// The most recent campaign levels finished, oldest first, and lifetime totals.
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct GlobalStats {
+    pub recent: Vec<LevelResult>,
+    #[serde(default)]
+    pub levels_solved: usize,
+    #[serde(default)]
+    pub seconds_played: f32,
+    #[serde(default)]
+    pub total_moves: usize,
+    #[serde(default)]
+    pub total_pushes: usize,
+    #[serde(default)]
+    pub undos_used: usize,
+}
+
+impl GlobalStats {
+    pub fn summary(&self) -> Vec<(&'static str, String)> {
+        let seconds = self.seconds_played as u32;
+        vec![
+            ("Levels solved", self.levels_solved.to_string()),
+            ("Total moves", self.total_moves.to_string()),
+            ("Total pushes", self.total_pushes.to_string()),
+            ("Undos used", self.undos_used.to_string()),
+            (
+                "Time played",
+                format!(
+                    "{}h {:02}m {:02}s",
+                    seconds / 3600,
+                    seconds / 60 % 60,
+                    seconds % 60
+                ),
+            ),
+        ]
+    }
+}
+
+// What the stats looked like at launch, the session summary is the difference.
+#[derive(Resource, Default)]
+struct SessionStart {
+    levels_solved: usize,
+    seconds_played: f32,
+    bests: BTreeMap<u32, String>,
+}
+
+#[derive(SystemParam)]
+pub struct SessionStats<'w> {
+    start: Res<'w, SessionStart>,
+    global_stats: Res<'w, GlobalStats>,
+    best_replays: Res<'w, BestReplays>,
+}
+
+impl SessionStats<'_> {
+    pub fn summary(&self) -> Vec<String> {
+        let seconds = (self.global_stats.seconds_played - self.start.seconds_played) as u32;
+        let new_bests = self
+            .best_replays
+            .0
+            .iter()
+            .filter(|(hash, moves)| self.start.bests.get(hash) != Some(moves))
+            .count();
+        vec![
+            format!(
+                "Levels solved: {}",
+                self.global_stats.levels_solved - self.start.levels_solved
+            ),
+            format!("Time played: {}m {:02}s", seconds / 60, seconds % 60),
+            format!("New bests: {}", new_bests),
+        ]
+    }
+}
+
+#[derive(Component)]
+struct StatsMenu;
+
+#[derive(Resource, Default)]
+struct LevelAttempt {
+    seconds: f32,
+    undos: usize,
+}
+
+fn load_stats(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(profile.load::<GlobalStats>(STATS_FILE));
+}
+
+fn start_session(
+    mut commands: Commands,
+    global_stats: Res<GlobalStats>,
+    best_replays: Res<BestReplays>,
+) {
+    commands.insert_resource(SessionStart {
+        levels_solved: global_stats.levels_solved,
+        seconds_played: global_stats.seconds_played,
+        bests: best_replays.0.clone(),
+    });
+}
+
+fn start_level_attempt(
+    mut level_attempt: ResMut<LevelAttempt>,
+    mut level_loaded_reader: EventReader<LevelLoadedEvent>,
+) {
+    if level_loaded_reader.read().last().is_some() {
+        *level_attempt = LevelAttempt::default();
+    }
+}
+
+fn track_level_attempt(
+    time: Res<Time>,
+    mut level_attempt: ResMut<LevelAttempt>,
+    mut global_stats: ResMut<GlobalStats>,
+    mut history_reader: EventReader<HistoryEvent>,
+) {
+    level_attempt.seconds += time.delta_seconds();
+    global_stats.seconds_played += time.delta_seconds();
+    let undos = history_reader
+        .read()
+        .filter(|history_event| !matches!(history_event, HistoryEvent::Redo))
+        .count();
+    level_attempt.undos += undos;
+    global_stats.undos_used += undos;
+}
+
+fn count_moves(
+    mut global_stats: ResMut<GlobalStats>,
+    mut move_resolved_reader: EventReader<MoveResolved>,
+) {
+    for move_resolved in move_resolved_reader.read() {
+        if move_resolved.blocked {
+            continue;
+        }
+        global_stats.total_moves += 1;
+        if move_resolved.pushed.is_some() {
+            global_stats.total_pushes += 1;
+        }
+    }
+}
+
+fn record_level_result(
+    profile: Res<Profile>,
+    level_pack: Res<LevelPack>,
+    level_attempt: Res<LevelAttempt>,
+    mut global_stats: ResMut<GlobalStats>,
+    mut level_completed_reader: EventReader<LevelCompletedEvent>,
+) {
+    for level_completed in level_completed_reader.read() {
+        global_stats.levels_solved += 1;
+        // Only campaign levels can be suggested from the level select.
+        if level_pack.name != CAMPAIGN_PACK {
+            continue;
+        }
+        global_stats.recent.push(LevelResult {
+            level: level_completed.level,
+            seconds: level_attempt.seconds,
+            moves: level_completed.moves.len(),
+            undos: level_attempt.undos,
+        });
+        let overflow = global_stats.recent.len().saturating_sub(RECENT_RESULTS);
+        global_stats.recent.drain(..overflow);
+        profile.save(STATS_FILE, &*global_stats);
+    }
+}
+
+// Keeps the time played since the last level was finished.
+fn save_stats_on_exit(
+    profile: Res<Profile>,
+    global_stats: Res<GlobalStats>,
+    mut app_exit_reader: EventReader<AppExit>,
+) {
+    if app_exit_reader.read().last().is_some() {
+        profile.save(STATS_FILE, &*global_stats);
+    }
+}
+
+fn show_stats_screen(mut commands: Commands, global_stats: Res<GlobalStats>) {
+    commands
+        .spawn((
+            StatsMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Statistics",
+                TextStyle {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+            for (label, value) in global_stats.summary() {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(280.0),
+                            justify_content: JustifyContent::SpaceBetween,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            label,
+                            TextStyle {
+                                font_size: 20.0,
+                                color: Color::CYAN,
+                                ..default()
+                            },
+                        ));
+                        parent.spawn(TextBundle::from_section(
+                            value,
+                            TextStyle {
+                                font_size: 20.0,
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+            parent.spawn(TextBundle::from_section(
+                "L: levels",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn hide_stats_screen(mut commands: Commands, stats_menu_query: Query<Entity, With<StatsMenu>>) {
+    for entity in stats_menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_stats_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::L) {
+        game_state.set(GameState::LevelSelect);
+    }
+}
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GlobalStats::default())
+            .insert_resource(LevelAttempt::default())
+            .insert_resource(SessionStart::default())
+            .add_systems(Startup, load_stats)
+            .add_systems(PostStartup, start_session)
+            .add_systems(Last, save_stats_on_exit)
+            .add_systems(
+                Update,
+                (
+                    start_level_attempt,
+                    track_level_attempt
+                        .after(start_level_attempt)
+                        .run_if(in_state(GameState::Playing)),
+                    record_level_result.after(track_level_attempt),
+                    count_moves.run_if(in_state(GameState::Playing)),
+                ),
+            )
+            .add_systems(OnEnter(GameState::Stats), show_stats_screen)
+            .add_systems(OnExit(GameState::Stats), hide_stats_screen)
+            .add_systems(
+                Update,
+                handle_stats_input
+                    .run_if(in_state(GameState::Stats))
+                    .run_if(quit_prompt_closed),
+            );
+    }
+}
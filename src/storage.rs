@@ -0,0 +1,75 @@
+use std::fmt::Display;
+
+// Where saves and settings end up: files on native builds, `localStorage` in the browser.
+pub trait StorageBackend: Send + Sync {
+    fn read(&self, key: &str) -> Option<String>;
+    fn write(&self, key: &str, contents: &str) -> Result<(), String>;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage {
+    pub root: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl StorageBackend for FileStorage {
+    fn read(&self, key: &str) -> Option<String> {
+        std::fs::read_to_string(self.root.join(key)).ok()
+    }
+
+    fn write(&self, key: &str, contents: &str) -> Result<(), String> {
+        let path = self.root.join(key);
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)
+                .map_err(|error| describe(directory.display(), error))?;
+        }
+        std::fs::write(&path, contents).map_err(|error| describe(path.display(), error))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub struct LocalStorage {
+    pub prefix: String,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl LocalStorage {
+    fn storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl StorageBackend for LocalStorage {
+    fn read(&self, key: &str) -> Option<String> {
+        Self::storage()?
+            .get_item(&format!("{}/{}", self.prefix, key))
+            .ok()?
+    }
+
+    fn write(&self, key: &str, contents: &str) -> Result<(), String> {
+        let key = format!("{}/{}", self.prefix, key);
+        Self::storage()
+            .ok_or_else(|| "localStorage is not available".to_string())?
+            .set_item(&key, contents)
+            .map_err(|error| describe(&key, format!("{:?}", error)))
+    }
+}
+
+fn describe(target: impl Display, error: impl Display) -> String {
+    format!("{}: {}", target, error)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn default_storage() -> Box<dyn StorageBackend> {
+    Box::new(FileStorage {
+        root: "saves".into(),
+    })
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn default_storage() -> Box<dyn StorageBackend> {
+    Box::new(LocalStorage {
+        prefix: "saves".to_string(),
+    })
+}
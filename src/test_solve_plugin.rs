@@ -0,0 +1,188 @@
+use bevy::{
+    prelude::*,
+    tasks::{block_on, AsyncComputeTaskPool, Task},
+};
+
+use crate::{
+    edit_plugin::EditingState,
+    pause_plugin::quit_prompt_closed,
+    save_dialog_plugin::save_dialog_closed,
+    solver::{is_solver_level, solve, SolveStats},
+    GameState,
+};
+
+const TEST_SOLVE_KEY: KeyCode = KeyCode::F4;
+// Off the main thread there's time to look further than the hints and demo do.
+const MAX_TEST_SOLVE_STATES: usize = 5_000_000;
+
+// F4 in the editor runs the solver on the level being edited, so it can be checked before
+// it's saved. The solve runs in the background and the editor keeps going meanwhile.
+pub struct TestSolvePlugin;
+
+struct ActiveSolve {
+    layout: Vec<Vec<i32>>,
+    task: Task<(Option<String>, SolveStats)>,
+}
+
+#[derive(Resource, Default)]
+struct TestSolve {
+    active: Option<ActiveSolve>,
+    // The last finished solve's outcome and the layout it was for, cleared once the level
+    // is edited.
+    result: Option<(Vec<Vec<i32>>, String, Color)>,
+}
+
+#[derive(Component)]
+struct TestSolveText;
+
+fn start_test_solve(
+    keyboard_input: Res<Input<KeyCode>>,
+    editing_state: Res<EditingState>,
+    mut test_solve: ResMut<TestSolve>,
+) {
+    if !keyboard_input.just_pressed(TEST_SOLVE_KEY) || test_solve.active.is_some() {
+        return;
+    }
+    let layout = editing_state.serialize();
+    if !is_solver_level(&layout) {
+        test_solve.result = Some((
+            layout,
+            "Can't test-solve: only one player, plain blocks, goals and walls".to_string(),
+            Color::ORANGE,
+        ));
+        return;
+    }
+    let task_layout = layout.clone();
+    let task = AsyncComputeTaskPool::get()
+        .spawn(async move { solve(&task_layout, MAX_TEST_SOLVE_STATES) });
+    test_solve.active = Some(ActiveSolve { layout, task });
+    test_solve.result = None;
+}
+
+fn finish_test_solve(mut test_solve: ResMut<TestSolve>) {
+    if !test_solve
+        .active
+        .as_ref()
+        .is_some_and(|active| active.task.is_finished())
+    {
+        return;
+    }
+    let Some(ActiveSolve { layout, task }) = test_solve.active.take() else {
+        return;
+    };
+    let (solution, stats) = block_on(task);
+
+    let (message, color) = match solution {
+        Some(solution) => {
+            let pushes = solution.chars().filter(char::is_ascii_uppercase).count();
+            (
+                format!(
+                    "Solvable in {} pushes ({} moves)",
+                    pushes,
+                    solution.chars().count()
+                ),
+                Color::GREEN,
+            )
+        }
+        // The search ran out of room before it ran out of positions.
+        None if stats.explored > MAX_TEST_SOLVE_STATES => (
+            format!(
+                "No solution in the first {} positions, it may still be solvable",
+                MAX_TEST_SOLVE_STATES
+            ),
+            Color::ORANGE,
+        ),
+        None => ("Unsolvable".to_string(), Color::RED),
+    };
+    info!("Test-solve: {}", message);
+    test_solve.result = Some((layout, message, color));
+}
+
+// A result only holds for the layout it was worked out for, which may have been edited while
+// it was being solved.
+fn clear_stale_result(editing_state: Res<EditingState>, mut test_solve: ResMut<TestSolve>) {
+    if !editing_state.is_changed() && !test_solve.is_changed() {
+        return;
+    }
+    let layout = editing_state.serialize();
+    if test_solve
+        .result
+        .as_ref()
+        .is_some_and(|(solved_layout, ..)| *solved_layout != layout)
+    {
+        test_solve.result = None;
+    }
+}
+
+fn show_test_solve_text(mut commands: Commands) {
+    commands.spawn((
+        TestSolveText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+fn hide_test_solve_text(mut commands: Commands, text_query: Query<Entity, With<TestSolveText>>) {
+    for entity in text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn update_test_solve_text(
+    test_solve: Res<TestSolve>,
+    mut text_query: Query<&mut Text, With<TestSolveText>>,
+) {
+    if !test_solve.is_changed() {
+        return;
+    }
+    let (message, color) = match (&test_solve.active, &test_solve.result) {
+        (Some(_), _) => ("Solving...".to_string(), Color::WHITE),
+        (None, Some((_, message, color))) => (message.clone(), *color),
+        (None, None) => (String::new(), Color::WHITE),
+    };
+    for mut text in &mut text_query {
+        text.sections[0].value = message.clone();
+        text.sections[0].style.color = color;
+    }
+}
+
+// Leaving the editor drops any solve still running along with its result.
+fn cancel_test_solve(mut test_solve: ResMut<TestSolve>) {
+    *test_solve = TestSolve::default();
+}
+
+impl Plugin for TestSolvePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TestSolve::default())
+            .add_systems(OnEnter(GameState::Editing), show_test_solve_text)
+            .add_systems(
+                OnExit(GameState::Editing),
+                (hide_test_solve_text, cancel_test_solve),
+            )
+            .add_systems(
+                Update,
+                (
+                    start_test_solve
+                        .run_if(quit_prompt_closed)
+                        .run_if(save_dialog_closed),
+                    finish_test_solve,
+                    clear_stale_result.after(finish_test_solve),
+                    update_test_solve_text
+                        .after(start_test_solve)
+                        .after(clear_stale_result),
+                )
+                    .run_if(in_state(GameState::Editing)),
+            );
+    }
+}
@@ -0,0 +1,225 @@
+use std::{fs, path::Path};
+
+use bevy::{asset::LoadState, ecs::system::SystemParam, prelude::*, utils::HashMap};
+
+use crate::{
+    profile::Profile,
+    sokoban_core::Tile,
+    tiles::{tile_texture, Theme, Themed},
+};
+
+const THEME_FILE: &str = "theme.ron";
+const SKIN_FILE: &str = "skin.ron";
+// Skins are folders of replacement sprites under assets/skins/<name>/.
+const SKINS_DIRECTORY: &str = "skins";
+const SKIN_TEXTURES: [&str; 5] = [
+    "player.png",
+    "wall.png",
+    "block.png",
+    "goal.png",
+    "floor.png",
+];
+
+pub struct ThemePlugin;
+
+// The sprites tiles are drawn with, any a skin leaves out fall back to the built in ones.
+#[derive(Resource, Default)]
+pub struct Skin {
+    pub name: Option<String>,
+    textures: HashMap<&'static str, String>,
+}
+
+impl Skin {
+    pub fn named(name: Option<String>) -> Self {
+        let mut textures = HashMap::default();
+        if let Some(name) = &name {
+            for texture in SKIN_TEXTURES {
+                let path = format!("{}/{}/{}", SKINS_DIRECTORY, name, texture);
+                if Path::new("assets").join(&path).is_file() {
+                    textures.insert(texture, path);
+                }
+            }
+        }
+        Self { name, textures }
+    }
+
+    pub fn texture(&self, texture: &str) -> String {
+        self.textures
+            .get(texture)
+            .cloned()
+            .unwrap_or_else(|| texture.to_string())
+    }
+}
+
+// Every folder in assets/skins, sorted by name.
+pub fn available_skins() -> Vec<String> {
+    let Ok(entries) = fs::read_dir(Path::new("assets").join(SKINS_DIRECTORY)) else {
+        return Vec::new();
+    };
+    let mut skins: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    skins.sort();
+    skins
+}
+
+// Every skinnable sprite packed into one texture, so the level's tiles can be drawn in a
+// single batch.
+#[derive(Resource, Default)]
+pub struct TileAtlas {
+    atlas: Handle<TextureAtlas>,
+    indices: HashMap<&'static str, usize>,
+    // The current skin's sprites, packed once they've all loaded.
+    loading: Vec<(&'static str, Handle<Image>)>,
+}
+
+impl TileAtlas {
+    // Where a tile's sprite is, once the skin has been packed.
+    pub fn get(&self, tile: Tile) -> Option<(&Handle<TextureAtlas>, usize)> {
+        let index = tile_texture(tile).and_then(|texture| self.indices.get(texture))?;
+        Some((&self.atlas, *index))
+    }
+}
+
+// Loads sprites through the current skin.
+#[derive(SystemParam)]
+pub struct SkinTextures<'w> {
+    asset_server: Res<'w, AssetServer>,
+    skin: Res<'w, Skin>,
+}
+
+impl SkinTextures<'_> {
+    pub fn load(&self, texture: &str) -> Handle<Image> {
+        self.asset_server.load(self.skin.texture(texture))
+    }
+}
+
+fn load_theme(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(profile.load::<Theme>(THEME_FILE));
+    commands.insert_resource(Skin::named(profile.load(SKIN_FILE)));
+}
+
+fn save_theme(profile: Res<Profile>, theme: Res<Theme>, skin: Res<Skin>) {
+    if theme.is_changed() && !theme.is_added() {
+        profile.save(THEME_FILE, &*theme);
+    }
+    if skin.is_changed() && !skin.is_added() {
+        profile.save(SKIN_FILE, &skin.name);
+    }
+}
+
+// Tints new sprites, or every sprite when the theme changes.
+fn apply_theme(
+    theme: Res<Theme>,
+    mut sprite_query: Query<(Ref<Themed>, &mut Sprite)>,
+    mut atlas_sprite_query: Query<(Ref<Themed>, &mut TextureAtlasSprite)>,
+) {
+    for (themed, mut sprite) in &mut sprite_query {
+        if theme.is_changed() || themed.is_added() {
+            sprite.color = theme.tint(themed.0);
+        }
+    }
+    for (themed, mut sprite) in &mut atlas_sprite_query {
+        if theme.is_changed() || themed.is_added() {
+            sprite.color = theme.tint(themed.0);
+        }
+    }
+}
+
+// Starts loading the skin's sprites whenever the skin changes.
+fn load_tile_atlas(skin_textures: SkinTextures, mut tile_atlas: ResMut<TileAtlas>) {
+    if !skin_textures.skin.is_changed() {
+        return;
+    }
+    tile_atlas.loading = SKIN_TEXTURES
+        .into_iter()
+        .map(|texture| (texture, skin_textures.load(texture)))
+        .collect();
+}
+
+fn pack_tile_atlas(
+    asset_server: Res<AssetServer>,
+    mut tile_atlas: ResMut<TileAtlas>,
+    mut images: ResMut<Assets<Image>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    if tile_atlas.loading.is_empty() {
+        return;
+    }
+    if let Some((texture, _)) = tile_atlas
+        .loading
+        .iter()
+        .find(|(_, image)| asset_server.get_load_state(image) == Some(LoadState::Failed))
+    {
+        warn!("Could not load {}, keeping the current sprites", texture);
+        tile_atlas.loading.clear();
+        return;
+    }
+
+    let mut builder = TextureAtlasBuilder::default();
+    for (_, image) in &tile_atlas.loading {
+        let Some(loaded) = images.get(image) else {
+            return;
+        };
+        builder.add_texture(image.id(), loaded);
+    }
+    let texture_atlas = match builder.finish(&mut images) {
+        Ok(texture_atlas) => texture_atlas,
+        Err(error) => {
+            warn!("Could not pack the tile sprites: {:?}", error);
+            tile_atlas.loading.clear();
+            return;
+        }
+    };
+
+    let loading = std::mem::take(&mut tile_atlas.loading);
+    tile_atlas.indices = loading
+        .iter()
+        .filter_map(|(texture, image)| {
+            texture_atlas
+                .get_texture_index(image)
+                .map(|index| (*texture, index))
+        })
+        .collect();
+    tile_atlas.atlas = texture_atlases.add(texture_atlas);
+}
+
+// Points new sprites, or every sprite once a skin has been packed, at the atlas.
+fn apply_tile_atlas(
+    tile_atlas: Res<TileAtlas>,
+    mut themed_query: Query<(
+        Ref<Themed>,
+        &mut TextureAtlasSprite,
+        &mut Handle<TextureAtlas>,
+    )>,
+) {
+    for (themed, mut sprite, mut atlas) in &mut themed_query {
+        if !tile_atlas.is_changed() && !themed.is_added() {
+            continue;
+        }
+        if let Some((tile_atlas, index)) = tile_atlas.get(themed.0) {
+            sprite.index = index;
+            *atlas = tile_atlas.clone();
+        }
+    }
+}
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Theme::default())
+            .insert_resource(Skin::default())
+            .insert_resource(TileAtlas::default())
+            .add_systems(Startup, load_theme)
+            .add_systems(
+                Update,
+                (
+                    save_theme,
+                    load_tile_atlas,
+                    pack_tile_atlas.after(load_tile_atlas),
+                ),
+            )
+            .add_systems(PostUpdate, (apply_theme, apply_tile_atlas));
+    }
+}
@@ -0,0 +1,165 @@
+use std::{fs, path::PathBuf};
+
+use bevy::{
+    asset::LoadState,
+    prelude::*,
+    render::{
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::{CompressedImageFormats, ImageSampler, ImageType},
+    },
+    utils::HashMap,
+};
+
+use crate::{
+    clip_plugin::draw_tile,
+    level_hash,
+    level_viewer_plugin::{floor_positions, viewer_tiles},
+    TILE_SIZE,
+};
+
+// Keyed by level hash, so a thumbnail is only redrawn when its layout changes.
+const THUMBNAILS_DIRECTORY: &str = "thumbnails";
+// Every other pixel of the full size board.
+const THUMBNAIL_STEP: usize = 2;
+
+pub struct ThumbnailPlugin;
+
+#[derive(Resource, Default)]
+pub struct ThumbnailCache {
+    handles: HashMap<u32, Handle<Image>>,
+    // Thumbnails handed out but not drawn yet, they wait for the tile textures to load.
+    pending: Vec<(Handle<Image>, Vec<Vec<i32>>)>,
+}
+
+fn thumbnail_path(hash: u32) -> PathBuf {
+    PathBuf::from(THUMBNAILS_DIRECTORY).join(format!("{:08x}.png", hash))
+}
+
+fn read_thumbnail(hash: u32) -> Option<Image> {
+    let bytes = fs::read(thumbnail_path(hash)).ok()?;
+    Image::from_buffer(
+        &bytes,
+        ImageType::Extension("png"),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::nearest(),
+    )
+    .ok()
+}
+
+impl ThumbnailCache {
+    // A handle that shows the level's thumbnail once it's ready, blank until then.
+    pub fn thumbnail(
+        &mut self,
+        level_layout: &[Vec<i32>],
+        images: &mut Assets<Image>,
+    ) -> Handle<Image> {
+        let hash = level_hash(level_layout);
+        if let Some(handle) = self.handles.get(&hash) {
+            return handle.clone();
+        }
+        let handle = match read_thumbnail(hash) {
+            Some(image) => images.add(image),
+            None => {
+                let handle = images.add(Image::new_fill(
+                    Extent3d::default(),
+                    TextureDimension::D2,
+                    &[0, 0, 0, 0],
+                    TextureFormat::Rgba8UnormSrgb,
+                ));
+                self.pending.push((handle.clone(), level_layout.to_vec()));
+                handle
+            }
+        };
+        self.handles.insert(hash, handle.clone());
+        handle
+    }
+}
+
+fn render_thumbnail(
+    level_layout: &[Vec<i32>],
+    images: &Assets<Image>,
+    asset_server: &AssetServer,
+) -> Image {
+    let tiles = viewer_tiles(level_layout, &floor_positions(level_layout));
+    let columns = level_layout.iter().map(|row| row.len()).max().unwrap_or(0);
+    let width = columns * TILE_SIZE as usize;
+    let height = level_layout.len() * TILE_SIZE as usize;
+    let mut pixels = vec![0; width * height * 4];
+    for (position, tile) in &tiles {
+        draw_tile(&mut pixels, width, *position, tile, images, asset_server);
+    }
+
+    let small_width = width / THUMBNAIL_STEP;
+    let small_height = height / THUMBNAIL_STEP;
+    let mut small = Vec::with_capacity(small_width * small_height * 4);
+    for y in 0..small_height {
+        for x in 0..small_width {
+            let start = ((y * width + x) * THUMBNAIL_STEP) * 4;
+            small.extend_from_slice(&pixels[start..start + 4]);
+        }
+    }
+    let mut image = Image::new(
+        Extent3d {
+            width: small_width as u32,
+            height: small_height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        small,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+fn write_thumbnail(hash: u32, image: Image) {
+    let path = thumbnail_path(hash);
+    let result = image
+        .try_into_dynamic()
+        .map_err(|error| error.to_string())
+        .and_then(|dynamic| {
+            fs::create_dir_all(THUMBNAILS_DIRECTORY).map_err(|error| error.to_string())?;
+            dynamic.save(&path).map_err(|error| error.to_string())
+        });
+    if let Err(error) = result {
+        warn!("Could not write {}: {}", path.display(), error);
+    }
+}
+
+fn draw_pending_thumbnails(
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    mut thumbnail_cache: ResMut<ThumbnailCache>,
+) {
+    if thumbnail_cache.pending.is_empty() {
+        return;
+    }
+    let textures_loaded = [
+        "floor.png",
+        "wall.png",
+        "block.png",
+        "goal.png",
+        "player.png",
+    ]
+    .iter()
+    .all(|texture| {
+        asset_server.get_load_state(asset_server.load::<Image>(*texture)) == Some(LoadState::Loaded)
+    });
+    if !textures_loaded {
+        return;
+    }
+
+    for (handle, level_layout) in std::mem::take(&mut thumbnail_cache.pending) {
+        let image = render_thumbnail(&level_layout, &images, &asset_server);
+        write_thumbnail(level_hash(&level_layout), image.clone());
+        images.insert(handle, image);
+    }
+}
+
+impl Plugin for ThumbnailPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ThumbnailCache::default())
+            .add_systems(Update, draw_pending_thumbnails);
+    }
+}
@@ -0,0 +1,156 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    sprite::Anchor,
+    utils::HashMap,
+};
+
+use crate::{
+    sokoban_core::Tile, theme_plugin::TileAtlas, tiles::Theme, LevelEntity, Position, TILE_SIZE,
+};
+
+// Tiles along each side of a chunk.
+const CHUNK_TILES: i32 = 16;
+const TILE_PIXELS: usize = TILE_SIZE as usize;
+
+// Draws the parts of a level that never move, floors and walls, a chunk at a time so
+// large levels don't need a sprite for every tile.
+pub struct TilemapPlugin;
+
+// A square of static tiles drawn into a single image, redrawn when the theme or skin changes.
+#[derive(Component)]
+pub struct TilemapChunk {
+    origin: Position,
+    tiles: Vec<(Position, Tile)>,
+}
+
+// Spawns the chunks covering `tiles`, returning the chunk each tile was drawn into.
+pub fn spawn_tilemap(
+    commands: &mut Commands,
+    tiles: impl IntoIterator<Item = (Position, Tile)>,
+) -> HashMap<Position, Entity> {
+    let mut chunks: HashMap<Position, Vec<(Position, Tile)>> = HashMap::default();
+    for (position, tile) in tiles {
+        let origin = Position {
+            x: position.x.div_euclid(CHUNK_TILES) * CHUNK_TILES,
+            y: position.y.div_euclid(CHUNK_TILES) * CHUNK_TILES,
+        };
+        chunks.entry(origin).or_default().push((position, tile));
+    }
+
+    let mut chunk_entities = HashMap::default();
+    for (origin, tiles) in chunks {
+        let entity = commands
+            .spawn((
+                LevelEntity,
+                SpriteBundle {
+                    // Left invisible until the chunk has been drawn.
+                    sprite: Sprite {
+                        color: Color::NONE,
+                        anchor: Anchor::TopLeft,
+                        ..default()
+                    },
+                    transform: Transform::from_translation(origin.to_translation_z(0.0)),
+                    ..default()
+                },
+            ))
+            .id();
+        chunk_entities.extend(tiles.iter().map(|(position, _)| (*position, entity)));
+        commands
+            .entity(entity)
+            .insert(TilemapChunk { origin, tiles });
+    }
+    chunk_entities
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// Copies each tile's sprite out of the atlas, tinted the way a sprite would be.
+fn draw_chunk(
+    chunk: &TilemapChunk,
+    theme: Theme,
+    tile_atlas: &TileAtlas,
+    texture_atlases: &Assets<TextureAtlas>,
+    images: &Assets<Image>,
+) -> Option<Image> {
+    let side = CHUNK_TILES as usize * TILE_PIXELS;
+    let mut pixels = vec![0; side * side * 4];
+    for (position, tile) in &chunk.tiles {
+        let (atlas, index) = tile_atlas.get(*tile)?;
+        let texture_atlas = texture_atlases.get(atlas)?;
+        let atlas_image = images.get(&texture_atlas.texture)?;
+        let atlas_width = atlas_image.texture_descriptor.size.width as usize;
+        let rect = texture_atlas.textures[index];
+        let tint = theme.tint(*tile).as_linear_rgba_f32();
+
+        let left = (position.x - chunk.origin.x) as usize * TILE_PIXELS;
+        let top = (position.y - chunk.origin.y) as usize * TILE_PIXELS;
+        let width = (rect.width() as usize).min(TILE_PIXELS);
+        let height = (rect.height() as usize).min(TILE_PIXELS);
+        for y in 0..height {
+            for x in 0..width {
+                let from = ((rect.min.y as usize + y) * atlas_width + rect.min.x as usize + x) * 4;
+                let to = ((top + y) * side + left + x) * 4;
+                for channel in 0..3 {
+                    let value = srgb_to_linear(atlas_image.data[from + channel] as f32 / 255.0);
+                    pixels[to + channel] =
+                        (linear_to_srgb(value * tint[channel]) * 255.0).round() as u8;
+                }
+                pixels[to + 3] = (atlas_image.data[from + 3] as f32 * tint[3]).round() as u8;
+            }
+        }
+    }
+
+    Some(Image::new(
+        Extent3d {
+            width: side as u32,
+            height: side as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        pixels,
+        TextureFormat::Rgba8UnormSrgb,
+    ))
+}
+
+// Draws new chunks, and redraws every chunk when the theme or skin changes. Chunks
+// spawned before the atlas is packed are drawn once it is.
+fn draw_tilemap_chunks(
+    theme: Res<Theme>,
+    tile_atlas: Res<TileAtlas>,
+    texture_atlases: Res<Assets<TextureAtlas>>,
+    mut images: ResMut<Assets<Image>>,
+    mut chunk_query: Query<(Ref<TilemapChunk>, &mut Handle<Image>, &mut Sprite)>,
+) {
+    let redraw = theme.is_changed() || tile_atlas.is_changed();
+    for (chunk, mut image, mut sprite) in &mut chunk_query {
+        if !redraw && !chunk.is_added() {
+            continue;
+        }
+        let Some(drawn) = draw_chunk(&chunk, *theme, &tile_atlas, &texture_atlases, &images) else {
+            continue;
+        };
+        *image = images.add(drawn);
+        sprite.color = Color::WHITE;
+    }
+}
+
+impl Plugin for TilemapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, draw_tilemap_chunks);
+    }
+}
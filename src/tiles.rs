@@ -1,17 +1,414 @@
 use bevy::{prelude::*, sprite::Anchor};
+use serde::{Deserialize, Serialize};
 
-use crate::Position;
+use crate::{level_data::Decoration, sokoban_core::Tile, Position, TILE_SIZE};
 
-pub fn spawn_floor(asset_server: &Res<AssetServer>, position: Position) -> SpriteBundle {
+// The palette every level sprite is tinted with.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Standard,
+    HighContrast,
+    // Built from the Okabe-Ito palette, which stays distinct for every kind of color blindness.
+    Colorblind,
+}
+
+// Okabe-Ito, leaving out black.
+const COLORBLIND_COLORS: [(f32, f32, f32); 7] = [
+    (0.9, 0.6, 0.0),
+    (0.34, 0.71, 0.91),
+    (0.0, 0.62, 0.45),
+    (0.94, 0.89, 0.26),
+    (0.0, 0.45, 0.7),
+    (0.84, 0.37, 0.0),
+    (0.8, 0.47, 0.65),
+];
+
+fn colorblind_color(index: i32) -> Color {
+    let (r, g, b) = COLORBLIND_COLORS[index.rem_euclid(COLORBLIND_COLORS.len() as i32) as usize];
+    Color::rgb(r, g, b)
+}
+
+impl Theme {
+    pub fn next(self) -> Self {
+        match self {
+            Theme::Standard => Theme::HighContrast,
+            Theme::HighContrast => Theme::Colorblind,
+            Theme::Colorblind => Theme::Standard,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::Standard => "standard",
+            Theme::HighContrast => "high contrast",
+            Theme::Colorblind => "colorblind",
+        }
+    }
+
+    // The tint for the pattern around the level, kept dark so the level stands out.
+    pub fn background(self) -> Color {
+        match self {
+            Theme::Standard => Color::rgb(0.16, 0.14, 0.2),
+            Theme::HighContrast => Color::rgb(0.05, 0.05, 0.05),
+            Theme::Colorblind => Color::rgb(0.12, 0.16, 0.22),
+        }
+    }
+
+    // The tint for a tile's sprite, `Tile::Empty` being the floor.
+    pub fn tint(self, tile: Tile) -> Color {
+        match self {
+            Theme::Standard => standard_tint(tile),
+            Theme::HighContrast => high_contrast_tint(tile),
+            Theme::Colorblind => colorblind_tint(tile),
+        }
+    }
+}
+
+fn standard_tint(tile: Tile) -> Color {
+    match tile {
+        Tile::Block(color) | Tile::Goal(color) | Tile::Key(color) | Tile::LockedDoor(color) => {
+            block_color(color)
+        }
+        Tile::Switch => switch_color(),
+        Tile::Door => door_color(),
+        Tile::Teleporter(pair) => teleporter_color(pair),
+        Tile::Portal(pair) => portal_color(pair),
+        Tile::Ice => ice_color(),
+        Tile::OneWay(_) => one_way_color(),
+        Tile::Pickup(_) => pickup_color(),
+        Tile::Pit => pit_color(),
+        Tile::Mirror => mirror_color(),
+        Tile::Exit => exit_color(),
+        Tile::Enemy(_) => enemy_color(),
+        Tile::Fragile => fragile_color(),
+        Tile::Empty | Tile::Player | Tile::HeavyBlock | Tile::Wall => Color::WHITE,
+    }
+}
+
+// Dark floors under bright, saturated pieces.
+fn high_contrast_tint(tile: Tile) -> Color {
+    match tile {
+        Tile::Empty => Color::rgb(0.3, 0.3, 0.3),
+        Tile::Player => Color::rgb(1.0, 1.0, 0.0),
+        Tile::Block(0) | Tile::Goal(0) | Tile::Key(0) | Tile::LockedDoor(0) => {
+            Color::rgb(1.0, 0.55, 0.0)
+        }
+        Tile::Block(color) | Tile::Goal(color) | Tile::Key(color) | Tile::LockedDoor(color) => {
+            Color::hsl(((color as i32 - 1) * 100 % 360) as f32, 1.0, 0.5)
+        }
+        Tile::HeavyBlock => Color::rgb(1.0, 0.8, 0.6),
+        Tile::Wall => Color::WHITE,
+        Tile::Switch => Color::rgb(1.0, 1.0, 0.3),
+        Tile::Door => Color::rgb(0.75, 0.35, 0.0),
+        Tile::Teleporter(pair) | Tile::Portal(pair) => {
+            Color::hsl((pair * 67 % 360) as f32, 1.0, 0.5)
+        }
+        Tile::Ice => Color::rgb(0.6, 1.0, 1.0),
+        Tile::OneWay(_) => Color::rgb(0.2, 0.8, 0.2),
+        Tile::Pickup(_) => Color::rgb(0.0, 1.0, 0.3),
+        Tile::Pit => Color::BLACK,
+        Tile::Mirror => Color::rgb(1.0, 0.3, 1.0),
+        Tile::Exit => Color::rgb(0.0, 1.0, 0.5),
+        Tile::Enemy(_) => Color::RED,
+        Tile::Fragile => Color::rgb(0.55, 0.4, 0.3),
+    }
+}
+
+fn colorblind_tint(tile: Tile) -> Color {
+    match tile {
+        Tile::Block(0) | Tile::Goal(0) | Tile::Key(0) | Tile::LockedDoor(0) => Color::WHITE,
+        Tile::Block(color) | Tile::Goal(color) | Tile::Key(color) | Tile::LockedDoor(color) => {
+            colorblind_color(color as i32 - 1)
+        }
+        Tile::Switch => colorblind_color(3),
+        Tile::Door => colorblind_color(5),
+        Tile::Teleporter(pair) | Tile::Portal(pair) => colorblind_color(pair),
+        Tile::Ice => colorblind_color(1),
+        Tile::Pickup(_) => colorblind_color(2),
+        Tile::Mirror => colorblind_color(6),
+        Tile::Exit => colorblind_color(4),
+        Tile::Enemy(_) => colorblind_color(0),
+        Tile::OneWay(_) | Tile::Pit | Tile::Fragile => standard_tint(tile),
+        Tile::Empty | Tile::Player | Tile::HeavyBlock | Tile::Wall => Color::WHITE,
+    }
+}
+
+// Marks a sprite as showing a tile so it's tinted by the current `Theme` and textured by
+// the current `Skin`.
+#[derive(Component)]
+pub struct Themed(pub Tile);
+
+// The sprite a skin can replace for each tile, found in the `TileAtlas`. The rest are drawn
+// as flat colours.
+pub fn tile_texture(tile: Tile) -> Option<&'static str> {
+    match tile {
+        Tile::Empty => Some("floor.png"),
+        Tile::Player | Tile::Mirror => Some("player.png"),
+        Tile::Block(_) | Tile::HeavyBlock => Some("block.png"),
+        Tile::Goal(_) => Some("goal.png"),
+        Tile::Wall => Some("wall.png"),
+        _ => None,
+    }
+}
+
+pub fn spawn_floor(position: Position) -> (Themed, SpriteSheetBundle) {
     let floor_translation = position.to_translation_z(0.0);
 
+    (
+        Themed(Tile::Empty),
+        SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                anchor: Anchor::TopLeft,
+                ..default()
+            },
+            transform: Transform::from_translation(floor_translation),
+            ..default()
+        },
+    )
+}
+
+// Tiles without their own texture yet are drawn as a flat colour.
+pub fn spawn_colored_tile(position: Position, color: Color, z: f32) -> SpriteBundle {
     SpriteBundle {
         sprite: Sprite {
+            color,
+            custom_size: Some(Vec2::splat(TILE_SIZE)),
             anchor: Anchor::TopLeft,
             ..default()
         },
-        texture: asset_server.load("floor.png"),
-        transform: Transform::from_translation(floor_translation),
+        transform: Transform::from_translation(position.to_translation_z(z)),
+        ..default()
+    }
+}
+
+pub fn switch_color() -> Color {
+    Color::rgb(0.9, 0.8, 0.2)
+}
+
+pub fn ice_color() -> Color {
+    Color::rgba(0.7, 0.9, 1.0, 0.8)
+}
+
+// The four arrow directions, numbered clockwise from up as in the level format.
+pub const ONE_WAY_DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+pub fn one_way_color() -> Color {
+    Color::rgb(0.35, 0.45, 0.35)
+}
+
+// A bar along the edge the arrow points at, drawn as a child of the one-way tile.
+pub fn one_way_arrow((x, y): (i32, i32)) -> SpriteBundle {
+    let bar = if x != 0 {
+        Vec2::new(3.0, TILE_SIZE - 4.0)
+    } else {
+        Vec2::new(TILE_SIZE - 4.0, 3.0)
+    };
+    let offset = TILE_SIZE / 2.0 - 2.5;
+    SpriteBundle {
+        sprite: Sprite {
+            color: Color::rgb(0.9, 0.95, 0.6),
+            custom_size: Some(bar),
+            ..default()
+        },
+        transform: Transform::from_xyz(
+            TILE_SIZE / 2.0 + x as f32 * offset,
+            -TILE_SIZE / 2.0 - y as f32 * offset,
+            0.01,
+        ),
+        ..default()
+    }
+}
+
+// Which way the player faces, marked by a dot near that edge of its tile.
+#[derive(Component)]
+pub struct FacingMarker;
+
+// Players start out facing down the screen.
+pub const STARTING_FACING: (i32, i32) = (0, 1);
+
+pub fn facing_marker_translation((x, y): (i32, i32)) -> Vec3 {
+    let offset = TILE_SIZE / 2.0 - 2.5;
+    Vec3::new(
+        TILE_SIZE / 2.0 + x as f32 * offset,
+        -TILE_SIZE / 2.0 - y as f32 * offset,
+        0.01,
+    )
+}
+
+pub fn facing_marker(direction: (i32, i32)) -> (FacingMarker, SpriteBundle) {
+    (
+        FacingMarker,
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb(0.15, 0.15, 0.2),
+                custom_size: Some(Vec2::splat(3.0)),
+                ..default()
+            },
+            transform: Transform::from_translation(facing_marker_translation(direction)),
+            ..default()
+        },
+    )
+}
+
+pub fn heavy_color() -> Color {
+    Color::rgb(0.25, 0.22, 0.3)
+}
+
+// A weight in the middle of a heavy block, spawned as the block's child.
+pub fn heavy_marker() -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color: heavy_color(),
+            custom_size: Some(Vec2::splat(TILE_SIZE / 3.0)),
+            ..default()
+        },
+        transform: Transform::from_xyz(TILE_SIZE / 2.0, -TILE_SIZE / 2.0, 0.01),
+        ..default()
+    }
+}
+
+pub fn pickup_color() -> Color {
+    Color::rgb(0.3, 0.9, 0.4)
+}
+
+// A small gem in the middle of the tile.
+pub fn spawn_pickup(position: Position) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color: pickup_color(),
+            custom_size: Some(Vec2::splat(TILE_SIZE / 2.0)),
+            ..default()
+        },
+        transform: Transform::from_translation(
+            position.to_translation_z(0.5) + Vec3::new(TILE_SIZE / 2.0, -TILE_SIZE / 2.0, 0.0),
+        )
+        .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+        ..default()
+    }
+}
+
+// A short bar lying in the middle of the tile, colored like the door it opens.
+pub fn spawn_key(position: Position, color: u8) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color: block_color(color),
+            custom_size: Some(Vec2::new(TILE_SIZE * 0.6, TILE_SIZE / 4.0)),
+            ..default()
+        },
+        transform: Transform::from_translation(
+            position.to_translation_z(0.5) + Vec3::new(TILE_SIZE / 2.0, -TILE_SIZE / 2.0, 0.0),
+        ),
+        ..default()
+    }
+}
+
+// Marks a locked door, whatever its color.
+pub fn keyhole() -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color: Color::rgb(0.1, 0.08, 0.08),
+            custom_size: Some(Vec2::new(TILE_SIZE / 5.0, TILE_SIZE / 2.5)),
+            ..default()
+        },
+        transform: Transform::from_xyz(TILE_SIZE / 2.0, -TILE_SIZE / 2.0, 0.01),
+        ..default()
+    }
+}
+
+// Above the floor and below every gameplay sprite.
+const DECORATION_Z: f32 = 0.1;
+
+pub fn spawn_decoration(position: Position, decoration: Decoration) -> SpriteBundle {
+    // Color, size, where in the tile it sits and how it's turned.
+    let (color, size, offset, angle) = match decoration {
+        Decoration::Plant => (
+            Color::rgb(0.3, 0.6, 0.3),
+            Vec2::splat(TILE_SIZE / 4.0),
+            Vec2::new(TILE_SIZE * 0.75, -TILE_SIZE * 0.25),
+            std::f32::consts::FRAC_PI_4,
+        ),
+        Decoration::Crack => (
+            Color::rgba(0.0, 0.0, 0.0, 0.35),
+            Vec2::new(TILE_SIZE * 0.6, 2.0),
+            Vec2::new(TILE_SIZE / 2.0, -TILE_SIZE / 2.0),
+            -0.6,
+        ),
+        Decoration::Crate => (
+            Color::rgb(0.55, 0.4, 0.25),
+            Vec2::splat(TILE_SIZE / 3.0),
+            Vec2::new(TILE_SIZE * 0.25, -TILE_SIZE * 0.75),
+            0.0,
+        ),
+    };
+    SpriteBundle {
+        sprite: Sprite {
+            color,
+            custom_size: Some(size),
+            ..default()
+        },
+        transform: Transform::from_translation(
+            position.to_translation_z(DECORATION_Z) + offset.extend(0.0),
+        )
+        .with_rotation(Quat::from_rotation_z(angle)),
+        ..default()
+    }
+}
+
+// Colored blocks and goals are numbered from 1, 0 is the plain untinted kind.
+pub fn block_color(color: u8) -> Color {
+    if color == 0 {
+        Color::WHITE
+    } else {
+        Color::hsl(((color as i32 - 1) * 100 % 360) as f32, 0.8, 0.6)
+    }
+}
+
+// Tints the player sprite for the mirrored second character.
+pub fn mirror_color() -> Color {
+    Color::rgb(0.6, 0.5, 1.0)
+}
+
+pub fn exit_color() -> Color {
+    Color::rgb(0.2, 0.8, 0.35)
+}
+
+pub fn enemy_color() -> Color {
+    Color::rgb(0.85, 0.2, 0.25)
+}
+
+// Paler than the floor, like it's already coming apart.
+pub fn fragile_color() -> Color {
+    Color::rgba(0.75, 0.65, 0.55, 0.6)
+}
+
+pub fn pit_color() -> Color {
+    Color::rgb(0.08, 0.06, 0.05)
+}
+
+pub fn door_color() -> Color {
+    Color::rgb(0.5, 0.3, 0.15)
+}
+
+// Each teleporter pair gets its own hue so pairs are easy to tell apart.
+pub fn teleporter_color(pair: i32) -> Color {
+    Color::hsl((pair * 67 % 360) as f32, 0.7, 0.6)
+}
+
+// Portals share the teleporters' hues, darker, and have a hole in the middle to tell them
+// apart.
+pub fn portal_color(pair: i32) -> Color {
+    Color::hsl((pair * 67 % 360) as f32, 0.7, 0.4)
+}
+
+pub fn portal_core() -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color: Color::rgb(0.05, 0.05, 0.1),
+            custom_size: Some(Vec2::splat(TILE_SIZE / 2.0)),
+            ..default()
+        },
+        transform: Transform::from_xyz(TILE_SIZE / 2.0, -TILE_SIZE / 2.0, 0.01),
         ..default()
     }
 }
@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+
+use crate::{
+    play_plugin::{HistoryEvent, InputBuffer, Player},
+    transition_plugin::transition_idle,
+    GameState,
+};
+
+// In logical pixels, shorter drags count as taps.
+const SWIPE_MIN_DISTANCE: f32 = 30.0;
+
+pub struct TouchPlugin;
+
+// The on-screen D-pad appears once the screen has been touched.
+#[derive(Resource, Default)]
+pub struct TouchControls {
+    pub show_dpad: bool,
+}
+
+// Tracks the fingers used since the screen was last untouched.
+#[derive(Resource, Default)]
+struct TouchGesture {
+    max_fingers: usize,
+    moved: bool,
+}
+
+#[derive(Component)]
+struct Dpad;
+
+#[derive(Component)]
+struct DpadButton((i32, i32));
+
+fn swipe_direction(distance: Vec2) -> Option<(i32, i32)> {
+    if distance.length() < SWIPE_MIN_DISTANCE {
+        return None;
+    }
+    // Screen y grows downwards, the same as level rows.
+    Some(if distance.x.abs() > distance.y.abs() {
+        (distance.x.signum() as i32, 0)
+    } else {
+        (0, distance.y.signum() as i32)
+    })
+}
+
+fn handle_touch(
+    touches: Res<Touches>,
+    mut touch_controls: ResMut<TouchControls>,
+    mut touch_gesture: ResMut<TouchGesture>,
+    mut input_buffer: ResMut<InputBuffer>,
+    mut history_writer: EventWriter<HistoryEvent>,
+    player_query: Query<&Player>,
+) {
+    if touches.any_just_pressed() && !touch_controls.show_dpad {
+        touch_controls.show_dpad = true;
+    }
+
+    let active = touches.iter().count();
+    touch_gesture.max_fingers = touch_gesture.max_fingers.max(active);
+    if touches
+        .iter()
+        .any(|touch| touch.distance().length() >= SWIPE_MIN_DISTANCE)
+    {
+        touch_gesture.moved = true;
+    }
+
+    let mut released = false;
+    for touch in touches.iter_just_released() {
+        released = true;
+        if touch_gesture.max_fingers == 1 {
+            if let Some(movement) = swipe_direction(touch.distance()) {
+                input_buffer.queue(movement);
+            }
+        }
+    }
+    if !released || active > 0 {
+        return;
+    }
+
+    let is_moving = player_query.iter().any(|player| player.is_moving);
+    if touch_gesture.max_fingers == 2 && !touch_gesture.moved && !is_moving {
+        history_writer.send(HistoryEvent::Undo);
+    }
+    *touch_gesture = TouchGesture::default();
+}
+
+fn show_dpad(
+    mut commands: Commands,
+    touch_controls: Res<TouchControls>,
+    dpad_query: Query<Entity, With<Dpad>>,
+) {
+    if !touch_controls.show_dpad || !dpad_query.is_empty() {
+        return;
+    }
+
+    let button = |parent: &mut ChildBuilder, label: &str, movement: (i32, i32), column, row| {
+        parent
+            .spawn((
+                DpadButton(movement),
+                ButtonBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(column as f32 * 48.0),
+                        top: Val::Px(row as f32 * 48.0),
+                        width: Val::Px(44.0),
+                        height: Val::Px(44.0),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    background_color: Color::rgba(1.0, 1.0, 1.0, 0.2).into(),
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font_size: 20.0,
+                        ..default()
+                    },
+                ));
+            });
+    };
+
+    commands
+        .spawn((
+            Dpad,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.0),
+                    bottom: Val::Px(10.0),
+                    width: Val::Px(140.0),
+                    height: Val::Px(140.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            button(parent, "^", (0, -1), 1, 0);
+            button(parent, "<", (-1, 0), 0, 1);
+            button(parent, ">", (1, 0), 2, 1);
+            button(parent, "v", (0, 1), 1, 2);
+        });
+}
+
+fn hide_dpad(mut commands: Commands, dpad_query: Query<Entity, With<Dpad>>) {
+    for entity in dpad_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_dpad(
+    mut input_buffer: ResMut<InputBuffer>,
+    interaction_query: Query<(&Interaction, &DpadButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            input_buffer.queue(button.0);
+        }
+    }
+}
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TouchControls::default())
+            .insert_resource(TouchGesture::default())
+            .add_systems(OnExit(GameState::Playing), hide_dpad)
+            .add_systems(
+                Update,
+                (
+                    (handle_touch, handle_dpad).run_if(transition_idle),
+                    show_dpad.after(handle_touch),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
@@ -0,0 +1,287 @@
+use bevy::prelude::*;
+
+use crate::{
+    generator::Rng,
+    notes_plugin::NoteEditor,
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{LoadLevelEvent, NextLevelEvent},
+    GameState,
+};
+
+const TRANSITION_SECONDS: f32 = 0.25;
+const DISSOLVE_CELLS: usize = 10;
+
+pub struct TransitionPlugin;
+
+#[derive(Resource, Default, Clone, Copy, PartialEq, Debug)]
+pub enum TransitionStyle {
+    #[default]
+    Fade,
+    Wipe,
+    Dissolve,
+}
+
+impl TransitionStyle {
+    fn next(self) -> Self {
+        match self {
+            TransitionStyle::Fade => TransitionStyle::Wipe,
+            TransitionStyle::Wipe => TransitionStyle::Dissolve,
+            TransitionStyle::Dissolve => TransitionStyle::Fade,
+        }
+    }
+}
+
+#[derive(Default, PartialEq)]
+enum TransitionPhase {
+    #[default]
+    Idle,
+    // Covering the old level.
+    Out,
+    // Revealing the new one.
+    In,
+}
+
+// The screen is covered while the old level is swapped for the new one, and
+// input is locked until the new level has been revealed.
+#[derive(Resource, Default)]
+pub struct TransitionState {
+    phase: TransitionPhase,
+    timer: Timer,
+    pending_level: Option<i32>,
+}
+
+impl TransitionState {
+    pub fn is_active(&self) -> bool {
+        self.phase != TransitionPhase::Idle
+    }
+
+    fn coverage(&self) -> f32 {
+        match self.phase {
+            TransitionPhase::Idle => 0.0,
+            TransitionPhase::Out => self.timer.percent(),
+            TransitionPhase::In => self.timer.percent_left(),
+        }
+    }
+}
+
+#[derive(Component)]
+struct TransitionCover;
+
+#[derive(Component)]
+struct DissolveCell {
+    threshold: f32,
+}
+
+#[derive(Component)]
+struct TransitionStyleText;
+
+pub fn transition_idle(transition_state: Res<TransitionState>) -> bool {
+    !transition_state.is_active()
+}
+
+fn spawn_cover(commands: &mut Commands, style: TransitionStyle) {
+    commands
+        .spawn((
+            TransitionCover,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            if style != TransitionStyle::Dissolve {
+                return;
+            }
+            let mut rng = Rng::new(0);
+            let cell_size = 100.0 / DISSOLVE_CELLS as f32;
+            for row in 0..DISSOLVE_CELLS {
+                for column in 0..DISSOLVE_CELLS {
+                    parent.spawn((
+                        DissolveCell {
+                            threshold: rng.below(1000) as f32 / 1000.0,
+                        },
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Val::Percent(column as f32 * cell_size),
+                                top: Val::Percent(row as f32 * cell_size),
+                                width: Val::Percent(cell_size),
+                                height: Val::Percent(cell_size),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ));
+                }
+            }
+        });
+}
+
+fn start_transition(
+    mut commands: Commands,
+    transition_style: Res<TransitionStyle>,
+    mut transition_state: ResMut<TransitionState>,
+    mut next_level_reader: EventReader<NextLevelEvent>,
+    cover_query: Query<Entity, With<TransitionCover>>,
+) {
+    let Some(next_level) = next_level_reader.read().last() else {
+        return;
+    };
+    transition_state.pending_level = Some(next_level.0);
+    if transition_state.phase == TransitionPhase::Out {
+        return;
+    }
+
+    transition_state.phase = TransitionPhase::Out;
+    transition_state.timer = Timer::from_seconds(TRANSITION_SECONDS, TimerMode::Once);
+    if cover_query.is_empty() {
+        spawn_cover(&mut commands, *transition_style);
+    }
+}
+
+fn advance_transition(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut transition_state: ResMut<TransitionState>,
+    mut load_level_writer: EventWriter<LoadLevelEvent>,
+    cover_query: Query<Entity, With<TransitionCover>>,
+) {
+    if !transition_state.is_active() {
+        return;
+    }
+    transition_state.timer.tick(time.delta());
+    if !transition_state.timer.finished() {
+        return;
+    }
+
+    match transition_state.phase {
+        TransitionPhase::Out => {
+            if let Some(level) = transition_state.pending_level.take() {
+                load_level_writer.send(LoadLevelEvent(level));
+            }
+            transition_state.phase = TransitionPhase::In;
+            transition_state.timer = Timer::from_seconds(TRANSITION_SECONDS, TimerMode::Once);
+        }
+        _ => {
+            transition_state.phase = TransitionPhase::Idle;
+            for entity in cover_query.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn draw_transition(
+    transition_style: Res<TransitionStyle>,
+    transition_state: Res<TransitionState>,
+    mut cover_query: Query<(&mut Style, &mut BackgroundColor), With<TransitionCover>>,
+    mut cell_query: Query<(&DissolveCell, &mut BackgroundColor), Without<TransitionCover>>,
+) {
+    let coverage = transition_state.coverage();
+    let Ok((mut style, mut background_color)) = cover_query.get_single_mut() else {
+        return;
+    };
+
+    match *transition_style {
+        TransitionStyle::Fade => {
+            background_color.0 = Color::rgba(0.0, 0.0, 0.0, coverage);
+        }
+        TransitionStyle::Wipe => {
+            // Sweeps in from the left and carries on out to the right.
+            background_color.0 = Color::BLACK;
+            style.width = Val::Percent(coverage * 100.0);
+            if transition_state.phase == TransitionPhase::In {
+                style.left = Val::Auto;
+                style.right = Val::Px(0.0);
+            } else {
+                style.left = Val::Px(0.0);
+                style.right = Val::Auto;
+            }
+        }
+        TransitionStyle::Dissolve => {
+            for (cell, mut cell_color) in &mut cell_query {
+                cell_color.0 = if cell.threshold < coverage {
+                    Color::BLACK
+                } else {
+                    Color::NONE
+                };
+            }
+        }
+    }
+}
+
+fn show_transition_style(mut commands: Commands, transition_style: Res<TransitionStyle>) {
+    commands.spawn((
+        TransitionStyleText,
+        TextBundle {
+            z_index: ZIndex::Global(11),
+            ..TextBundle::from_section(
+                format!("T: transition ({:?})", *transition_style),
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                right: Val::Px(10.0),
+                ..default()
+            })
+        },
+    ));
+}
+
+fn hide_transition_style(
+    mut commands: Commands,
+    style_text_query: Query<Entity, With<TransitionStyleText>>,
+) {
+    for entity in style_text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn cycle_transition_style(
+    keyboard_input: Res<Input<KeyCode>>,
+    note_editor: Res<NoteEditor>,
+    mut transition_style: ResMut<TransitionStyle>,
+    mut style_text_query: Query<&mut Text, With<TransitionStyleText>>,
+) {
+    if note_editor.editing || !keyboard_input.just_pressed(KeyCode::T) {
+        return;
+    }
+    *transition_style = transition_style.next();
+    for mut text in &mut style_text_query {
+        text.sections[0].value = format!("T: transition ({:?})", *transition_style);
+    }
+}
+
+impl Plugin for TransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TransitionStyle::default())
+            .insert_resource(TransitionState::default())
+            .add_systems(OnEnter(GameState::Paused), show_transition_style)
+            .add_systems(OnExit(GameState::Paused), hide_transition_style)
+            .add_systems(
+                Update,
+                cycle_transition_style
+                    .run_if(in_state(GameState::Paused))
+                    .run_if(quit_prompt_closed),
+            )
+            .add_systems(
+                Update,
+                (
+                    start_transition,
+                    advance_transition.after(start_transition),
+                    draw_transition.after(advance_transition),
+                ),
+            );
+    }
+}
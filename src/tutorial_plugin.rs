@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+
+use crate::{
+    pause_plugin::quit_prompt_closed,
+    play_plugin::{
+        BoardChangedEvent, HistoryEvent, LevelCompletedEvent, LevelPack, LevelState, NextLevelEvent,
+    },
+    GameState, Obstacle,
+};
+
+const TUTORIAL_PACK: &str = "Tutorial";
+
+pub struct TutorialPlugin;
+
+fn tutorial_level() -> Vec<Vec<i32>> {
+    vec![
+        vec![8, 8, 8, 8, 8, 8, 8],
+        vec![8, 1, 0, 2, 0, 4, 8],
+        vec![8, 0, 0, 0, 0, 0, 8],
+        vec![8, 0, 0, 2, 0, 4, 8],
+        vec![8, 8, 8, 8, 8, 8, 8],
+    ]
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TutorialTrigger {
+    Move,
+    Undo,
+    Complete,
+}
+
+// Shown until `trigger` happens while `condition` holds, then the script moves on.
+struct TutorialStep {
+    trigger: TutorialTrigger,
+    condition: fn(&LevelState) -> bool,
+    message: &'static str,
+}
+
+#[derive(Resource)]
+pub struct TutorialScript {
+    steps: Vec<TutorialStep>,
+    // `None` when the tutorial isn't being played.
+    current: Option<usize>,
+}
+
+fn always(_: &LevelState) -> bool {
+    true
+}
+
+fn block_on_goal(level_state: &LevelState) -> bool {
+    level_state.goals.keys().any(|goal| {
+        matches!(
+            level_state.obstacles.get(goal),
+            Some((_, Obstacle::Block(_)))
+        )
+    })
+}
+
+impl Default for TutorialScript {
+    fn default() -> Self {
+        Self {
+            steps: vec![
+                TutorialStep {
+                    trigger: TutorialTrigger::Move,
+                    condition: always,
+                    message: "Arrow keys to move",
+                },
+                TutorialStep {
+                    trigger: TutorialTrigger::Move,
+                    condition: block_on_goal,
+                    message: "Push the block onto the goal",
+                },
+                TutorialStep {
+                    trigger: TutorialTrigger::Undo,
+                    condition: always,
+                    message: "Press U to undo",
+                },
+                TutorialStep {
+                    trigger: TutorialTrigger::Complete,
+                    condition: always,
+                    message: "Get a block onto every goal to finish",
+                },
+            ],
+            current: None,
+        }
+    }
+}
+
+#[derive(Component)]
+struct TutorialCallout;
+
+fn start_tutorial(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut tutorial_script: ResMut<TutorialScript>,
+    mut next_level_writer: EventWriter<NextLevelEvent>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::T) {
+        return;
+    }
+    commands.insert_resource(LevelPack {
+        name: TUTORIAL_PACK.to_string(),
+        levels: vec![tutorial_level()],
+        metadata: Vec::new(),
+        return_state: GameState::LevelSelect,
+    });
+    tutorial_script.current = Some(0);
+    next_level_writer.send(NextLevelEvent(1));
+    game_state.set(GameState::Playing);
+}
+
+fn stop_tutorial(mut tutorial_script: ResMut<TutorialScript>) {
+    tutorial_script.current = None;
+}
+
+fn advance_tutorial(
+    level_state: Res<LevelState>,
+    mut tutorial_script: ResMut<TutorialScript>,
+    mut board_changed_reader: EventReader<BoardChangedEvent>,
+    mut history_reader: EventReader<HistoryEvent>,
+    mut level_completed_reader: EventReader<LevelCompletedEvent>,
+) {
+    let mut triggers = Vec::new();
+    if board_changed_reader
+        .read()
+        .any(|board_changed| board_changed.source == "move")
+    {
+        triggers.push(TutorialTrigger::Move);
+    }
+    if history_reader
+        .read()
+        .any(|history_event| !matches!(history_event, HistoryEvent::Redo))
+    {
+        triggers.push(TutorialTrigger::Undo);
+    }
+    if level_completed_reader.read().next().is_some() {
+        triggers.push(TutorialTrigger::Complete);
+    }
+    let Some(current) = tutorial_script.current else {
+        return;
+    };
+
+    let step = &tutorial_script.steps[current];
+    if triggers.contains(&step.trigger) && (step.condition)(&level_state) {
+        let next = current + 1;
+        tutorial_script.current = (next < tutorial_script.steps.len()).then_some(next);
+    }
+}
+
+fn update_tutorial_callout(
+    mut commands: Commands,
+    tutorial_script: Res<TutorialScript>,
+    callout_query: Query<Entity, With<TutorialCallout>>,
+) {
+    if !tutorial_script.is_changed() {
+        return;
+    }
+    for entity in callout_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(step) = tutorial_script
+        .current
+        .map(|current| &tutorial_script.steps[current])
+    else {
+        return;
+    };
+
+    commands
+        .spawn((
+            TutorialCallout,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(40.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(8.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        step.message,
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::YELLOW,
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TutorialScript::default())
+            .add_systems(OnEnter(GameState::LevelSelect), stop_tutorial)
+            .add_systems(
+                Update,
+                (
+                    start_tutorial
+                        .run_if(in_state(GameState::LevelSelect))
+                        .run_if(quit_prompt_closed),
+                    advance_tutorial.run_if(in_state(GameState::Playing)),
+                    update_tutorial_callout
+                        .after(start_tutorial)
+                        .after(advance_tutorial),
+                ),
+            );
+    }
+}